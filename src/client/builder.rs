@@ -1,9 +1,64 @@
+use std::time::Duration;
+
 use crate::constants;
 use crate::PowerSupplyClient;
 use pza_toolkit::config::IPEndpointConfig;
 use pza_toolkit::rumqtt;
 use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
 
+/// Broker address used when the endpoint does not specify one
+const DEFAULT_BROKER_ADDR: &str = "127.0.0.1";
+
+/// Broker port used when the endpoint does not specify one
+const DEFAULT_BROKER_PORT: u16 = 1883;
+
+/// Capacity of the request channel of the MQTT client
+const MQTT_CHANNEL_CAPACITY: usize = 100;
+
+/// Create an MQTT client connected to the given broker
+///
+/// Without endpoint, the toolkit default client is used. Each client gets a
+/// unique identifier so that several clients can share the same broker.
+pub(crate) fn init_mqtt_client(
+    name: &str,
+    ip: Option<&IPEndpointConfig>,
+) -> (rumqttc::AsyncClient, rumqttc::EventLoop) {
+    match ip {
+        Some(ip) => {
+            let mut options = rumqttc::MqttOptions::new(
+                format!("{}-{}", name, uuid::Uuid::new_v4()),
+                ip.addr
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_BROKER_ADDR.to_string()),
+                ip.port.unwrap_or(DEFAULT_BROKER_PORT),
+            );
+            options.set_keep_alive(Duration::from_secs(5));
+            rumqttc::AsyncClient::new(options, MQTT_CHANNEL_CAPACITY)
+        }
+        None => rumqtt::client::init_client(name),
+    }
+}
+
+/// Create an MQTT client accepting packets up to the given size
+///
+/// The default packet size of the MQTT clients is too small for large payloads
+/// such as history responses. Without endpoint, the default broker is used.
+pub(crate) fn init_mqtt_client_with_max_packet_size(
+    name: &str,
+    ip: Option<&IPEndpointConfig>,
+    max_packet_size: usize,
+) -> (rumqttc::AsyncClient, rumqttc::EventLoop) {
+    let mut options = rumqttc::MqttOptions::new(
+        format!("{}-{}", name, uuid::Uuid::new_v4()),
+        ip.and_then(|ip| ip.addr.clone())
+            .unwrap_or_else(|| DEFAULT_BROKER_ADDR.to_string()),
+        ip.and_then(|ip| ip.port).unwrap_or(DEFAULT_BROKER_PORT),
+    );
+    options.set_keep_alive(Duration::from_secs(5));
+    options.set_max_packet_size(max_packet_size, max_packet_size);
+    rumqttc::AsyncClient::new(options, MQTT_CHANNEL_CAPACITY)
+}
+
 #[derive(Default)]
 /// Builder pattern for creating PowerSupplyClient instances
 pub struct PowerSupplyClientBuilder {
@@ -35,21 +90,24 @@ impl PowerSupplyClientBuilder {
 
     /// Build the PowerSupplyClient instance
     pub fn build(self) -> anyhow::Result<PowerSupplyClient> {
-        let (client, event_loop) = rumqtt::client::init_client("power-supply");
+        let (client, event_loop) = init_mqtt_client("power-supply", self.ip.as_ref());
 
         let name = self
             .psu_name
             .clone()
             .ok_or_else(|| anyhow::anyhow!("Power supply name not set"))?;
 
+        // Both clients share the same connection, only the retain flag differs
+        let prefix = format!("{}/{}", constants::SERVER_TYPE_NAME, name);
         Ok(PowerSupplyClient::new_from_builder(
             name.clone(),
             RumqttCustomAsyncClient::new(
-                client,
+                client.clone(),
                 rumqttc::QoS::AtMostOnce,
                 true,
-                format!("{}/{}", constants::SERVER_TYPE_NAME, name),
+                prefix.clone(),
             ),
+            RumqttCustomAsyncClient::new(client, rumqttc::QoS::AtMostOnce, false, prefix),
             event_loop,
         ))
     }
@@ -1,7 +1,17 @@
+use std::time::Instant;
+
+use crate::payload::Status;
+
 pub struct MutableData {
     pub enabled: bool,
     pub voltage: String,
     pub current: String,
+    pub estop_latched: bool,
+    pub measured_voltage: String,
+    pub measured_current: String,
+    pub status: Option<Status>,
+    pub panic_message: Option<String>,
+    pub last_update: Option<Instant>,
 }
 
 impl Default for MutableData {
@@ -10,6 +20,12 @@ impl Default for MutableData {
             enabled: false,
             voltage: "0.00".to_string(),
             current: "0.00".to_string(),
+            estop_latched: false,
+            measured_voltage: "0.000".to_string(),
+            measured_current: "0.000".to_string(),
+            status: None,
+            panic_message: None,
+            last_update: None,
         }
     }
 }
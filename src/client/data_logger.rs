@@ -0,0 +1,223 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use pza_toolkit::config::IPEndpointConfig;
+use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
+use tokio::sync::broadcast;
+use tokio::sync::Mutex;
+use tracing::error;
+use tracing::trace;
+
+use super::builder::init_mqtt_client;
+use super::wait_response;
+use crate::constants::DataLoggerTopics;
+use crate::constants::SERVER_TYPE_NAME;
+use crate::payload::DataLoggerAction;
+use crate::payload::DataLoggerPayload;
+use crate::payload::DataLoggerStatusPayload;
+use crate::payload::ErrorPayload;
+use crate::payload::PzaId;
+
+/// Client controlling the data logger of a server via MQTT protocol.
+///
+/// Starts and stops the recording sessions, the state of the logger is
+/// received on its retained status topic.
+#[derive(Clone)]
+pub struct DataLoggerClient {
+    /// The underlying MQTT client for network communication.
+    mqtt_client: RumqttCustomAsyncClient,
+
+    /// Last state published by the data logger.
+    status: Arc<Mutex<Option<DataLoggerStatusPayload>>>,
+
+    /// Channel for broadcasting data logger state changes.
+    status_channel: broadcast::Sender<Arc<DataLoggerStatusPayload>>,
+    /// Channel for broadcasting errors reported by the data logger.
+    error_channel: broadcast::Sender<Arc<ErrorPayload>>,
+
+    /// MQTT topics of the data logger.
+    topics: DataLoggerTopics,
+}
+
+impl DataLoggerClient {
+    // ------------------------------------------------------------------------------
+
+    /// Creates a client connected to the given broker, the default one if not set.
+    ///
+    /// Automatically starts the background task loop for handling MQTT events.
+    pub fn connect(ip: Option<IPEndpointConfig>) -> Self {
+        let (client, event_loop) = init_mqtt_client("power-supply-logger", ip.as_ref());
+
+        let obj = Self {
+            // Commands are not retained, they would be executed again on restart
+            mqtt_client: RumqttCustomAsyncClient::new(
+                client,
+                rumqttc::QoS::AtMostOnce,
+                false,
+                format!("{}/logger", SERVER_TYPE_NAME),
+            ),
+            status: Arc::new(Mutex::new(None)),
+            status_channel: broadcast::channel(32).0,
+            error_channel: broadcast::channel(32).0,
+            topics: DataLoggerTopics::new(),
+        };
+
+        let _task_handler = tokio::spawn(Self::task_loop(obj.clone(), event_loop));
+        obj
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Background task loop receiving the state and the errors of the data logger.
+    async fn task_loop(client: DataLoggerClient, mut event_loop: rumqttc::EventLoop) {
+        client
+            .mqtt_client
+            .subscribe_to_all(vec![
+                client.topics.status.clone(),
+                client.topics.error.clone(),
+            ])
+            .await;
+
+        loop {
+            while let Ok(event) = event_loop.poll().await {
+                if let rumqttc::Event::Incoming(rumqttc::Packet::Publish(packet)) = event {
+                    client
+                        .handle_incoming_message(&packet.topic, packet.payload)
+                        .await;
+                }
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Handles incoming MQTT messages and broadcasts them.
+    async fn handle_incoming_message(&self, topic: &str, payload: Bytes) {
+        if topic == self.topics.status {
+            match DataLoggerStatusPayload::from_json_bytes(payload) {
+                Ok(status_payload) => {
+                    *self.status.lock().await = Some(status_payload.clone());
+                    // No receiver is not an error, nobody may be listening
+                    let _ = self.status_channel.send(Arc::new(status_payload));
+                }
+                Err(e) => error!("[logger] Failed to parse status payload: {}", e),
+            }
+        } else if topic == self.topics.error {
+            match ErrorPayload::from_json_bytes(payload) {
+                Ok(error_payload) => {
+                    error!("[logger] Error received: {}", error_payload.message);
+                    let _ = self.error_channel.send(Arc::new(error_payload));
+                }
+                Err(e) => error!("[logger] Failed to parse error payload: {}", e),
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Gets the last state published by the data logger.
+    pub async fn get_status(&self) -> Option<DataLoggerStatusPayload> {
+        self.status.lock().await.clone()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Starts a recording session, named after `session` when given.
+    pub async fn start(&self, session: Option<String>) -> anyhow::Result<PzaId> {
+        trace!("[logger] Starting session {:?}", session);
+        let mut payload = DataLoggerPayload::from_action(DataLoggerAction::Start);
+        if let Some(session) = session {
+            payload = payload.with_session(session);
+        }
+        self.send(payload).await
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Starts a recording session and waits for confirmation.
+    ///
+    /// Returns the state of the logger, with the session identifier and the
+    /// file being written.
+    pub async fn start_wait_ack(
+        &self,
+        session: Option<String>,
+        timeout_duration: Duration,
+    ) -> anyhow::Result<DataLoggerStatusPayload> {
+        // Listen before sending the command so a fast response is not missed
+        let status_rx = self.subscribe_status_changes();
+        let error_rx = self.subscribe_errors();
+
+        let id = self.start(session).await?;
+        let status = wait_response(
+            "logger",
+            &id,
+            status_rx,
+            error_rx,
+            timeout_duration,
+            "logger start",
+            |p| p.pza_id.clone(),
+        )
+        .await?;
+        Ok(status.as_ref().clone())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Stops the running recording session.
+    pub async fn stop(&self) -> anyhow::Result<PzaId> {
+        trace!("[logger] Stopping session");
+        self.send(DataLoggerPayload::from_action(DataLoggerAction::Stop))
+            .await
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Stops the running recording session and waits for confirmation.
+    ///
+    /// Returns the state of the logger, with the files written by the session.
+    pub async fn stop_wait_ack(
+        &self,
+        timeout_duration: Duration,
+    ) -> anyhow::Result<DataLoggerStatusPayload> {
+        let status_rx = self.subscribe_status_changes();
+        let error_rx = self.subscribe_errors();
+
+        let id = self.stop().await?;
+        let status = wait_response(
+            "logger",
+            &id,
+            status_rx,
+            error_rx,
+            timeout_duration,
+            "logger stop",
+            |p| p.pza_id.clone(),
+        )
+        .await?;
+        Ok(status.as_ref().clone())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Publishes a command on the command topic of the data logger.
+    async fn send(&self, payload: DataLoggerPayload) -> anyhow::Result<PzaId> {
+        self.mqtt_client
+            .pubsh(&self.topics.cmd, payload.to_json_bytes()?)
+            .await?;
+        Ok(payload.pza_id)
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Subscribes to the state changes of the data logger.
+    pub fn subscribe_status_changes(&self) -> broadcast::Receiver<Arc<DataLoggerStatusPayload>> {
+        self.status_channel.subscribe()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Subscribes to the errors reported by the data logger.
+    pub fn subscribe_errors(&self) -> broadcast::Receiver<Arc<ErrorPayload>> {
+        self.error_channel.subscribe()
+    }
+}
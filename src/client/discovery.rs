@@ -0,0 +1,55 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use pza_toolkit::config::IPEndpointConfig;
+use tokio::time::Instant;
+use tracing::debug;
+use tracing::warn;
+
+use super::builder::init_mqtt_client;
+use crate::constants::ALL_RUNNERS_NAME;
+use crate::constants::SERVER_TYPE_NAME;
+
+/// Discovers the power supplies served on a broker.
+///
+/// Every runner publishes a retained message on its status topic, so the
+/// broker delivers one status per runner on subscription. The names are
+/// collected until the timeout and returned sorted.
+pub async fn discover_power_supplies(
+    ip: IPEndpointConfig,
+    timeout_duration: Duration,
+) -> anyhow::Result<Vec<String>> {
+    let (client, mut event_loop) = init_mqtt_client("power-supply-discovery", Some(&ip));
+    let status_filter = format!("{}/+/status", SERVER_TYPE_NAME);
+    client
+        .subscribe(status_filter.as_str(), rumqttc::QoS::AtMostOnce)
+        .await?;
+
+    let mut names = BTreeSet::new();
+    let deadline = Instant::now() + timeout_duration;
+    loop {
+        match tokio::time::timeout_at(deadline, event_loop.poll()).await {
+            // Timeout, every retained status has been received
+            Err(_) => break,
+            Ok(Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(packet)))) => {
+                let name = packet
+                    .topic
+                    .strip_prefix(&format!("{}/", SERVER_TYPE_NAME))
+                    .and_then(|rest| rest.strip_suffix("/status"));
+                if let Some(name) = name {
+                    if name != ALL_RUNNERS_NAME && names.insert(name.to_string()) {
+                        debug!("Discovered power supply '{}'", name);
+                    }
+                }
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                warn!("Discovery connection error: {}", e);
+                return Err(anyhow::anyhow!("Failed to connect to the broker: {}", e));
+            }
+        }
+    }
+
+    let _ = client.disconnect().await;
+    Ok(names.into_iter().collect())
+}
@@ -6,4 +6,10 @@ pub enum ClientError {
     Generic(String),
     #[error("An error occurred on mqtt communication: {0}")]
     MqttError(String),
+    /// Command rejected by the runner, with the message of its error payload
+    #[error("{0}")]
+    Rejected(String),
+    /// No response of the runner to a command in time
+    #[error("Timeout waiting for {0} confirmation")]
+    Timeout(String),
 }
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use pza_toolkit::config::IPEndpointConfig;
+use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
+use tokio::sync::broadcast;
+use tracing::error;
+use tracing::trace;
+
+use super::builder::init_mqtt_client_with_max_packet_size;
+use super::wait_response;
+use crate::constants::HistoryTopics;
+use crate::constants::HISTORY_MAX_PACKET_SIZE;
+use crate::constants::SERVER_TYPE_NAME;
+use crate::payload::ErrorPayload;
+use crate::payload::HistoryQueryPayload;
+use crate::payload::HistoryResponsePayload;
+use crate::payload::PzaId;
+
+/// Client querying the history store of a server via MQTT protocol.
+///
+/// Queries are published on the command topic, the responses of every client
+/// are received on the response topic and matched by their `pza_id`.
+#[derive(Clone)]
+pub struct HistoryClient {
+    /// The underlying MQTT client for network communication.
+    mqtt_client: RumqttCustomAsyncClient,
+
+    /// Channel for broadcasting the responses to the queries.
+    response_channel: broadcast::Sender<Arc<HistoryResponsePayload>>,
+    /// Channel for broadcasting errors reported by the history store.
+    error_channel: broadcast::Sender<Arc<ErrorPayload>>,
+
+    /// MQTT topics of the history store.
+    topics: HistoryTopics,
+}
+
+impl HistoryClient {
+    // ------------------------------------------------------------------------------
+
+    /// Creates a client connected to the given broker, the default one if not set.
+    ///
+    /// Automatically starts the background task loop for handling MQTT events.
+    pub fn connect(ip: Option<IPEndpointConfig>) -> Self {
+        let (client, event_loop) = init_mqtt_client_with_max_packet_size(
+            "power-supply-history",
+            ip.as_ref(),
+            HISTORY_MAX_PACKET_SIZE,
+        );
+
+        let obj = Self {
+            // Queries are not retained, they would be answered again on restart
+            mqtt_client: RumqttCustomAsyncClient::new(
+                client,
+                rumqttc::QoS::AtMostOnce,
+                false,
+                format!("{}/history", SERVER_TYPE_NAME),
+            ),
+            response_channel: broadcast::channel(32).0,
+            error_channel: broadcast::channel(32).0,
+            topics: HistoryTopics::new(),
+        };
+
+        let _task_handler = tokio::spawn(Self::task_loop(obj.clone(), event_loop));
+        obj
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Background task loop receiving the responses and the errors of the history store.
+    async fn task_loop(client: HistoryClient, mut event_loop: rumqttc::EventLoop) {
+        client
+            .mqtt_client
+            .subscribe_to_all(vec![
+                client.topics.response.clone(),
+                client.topics.error.clone(),
+            ])
+            .await;
+
+        loop {
+            while let Ok(event) = event_loop.poll().await {
+                if let rumqttc::Event::Incoming(rumqttc::Packet::Publish(packet)) = event {
+                    client.handle_incoming_message(&packet.topic, packet.payload);
+                }
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Handles incoming MQTT messages and broadcasts them.
+    fn handle_incoming_message(&self, topic: &str, payload: Bytes) {
+        if topic == self.topics.response {
+            match HistoryResponsePayload::from_json_bytes(payload) {
+                Ok(response) => {
+                    // No receiver is not an error, nobody may be waiting
+                    let _ = self.response_channel.send(Arc::new(response));
+                }
+                Err(e) => error!("[history] Failed to parse response payload: {}", e),
+            }
+        } else if topic == self.topics.error {
+            match ErrorPayload::from_json_bytes(payload) {
+                Ok(error_payload) => {
+                    let _ = self.error_channel.send(Arc::new(error_payload));
+                }
+                Err(e) => error!("[history] Failed to parse error payload: {}", e),
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Publishes a query on the command topic of the history store.
+    pub async fn query(&self, query: HistoryQueryPayload) -> anyhow::Result<PzaId> {
+        trace!("[history] Querying {:?}", query);
+        self.mqtt_client
+            .pubsh(&self.topics.cmd, query.to_json_bytes()?)
+            .await?;
+        Ok(query.pza_id)
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Publishes a query and waits for its response.
+    ///
+    /// A rejected query (unknown runner, invalid range...) is returned as a
+    /// `ClientError::Rejected` error, a missing response as `ClientError::Timeout`.
+    pub async fn query_wait_ack(
+        &self,
+        query: HistoryQueryPayload,
+        timeout_duration: Duration,
+    ) -> anyhow::Result<HistoryResponsePayload> {
+        // Listen before sending the query so a fast response is not missed
+        let response_rx = self.response_channel.subscribe();
+        let error_rx = self.error_channel.subscribe();
+
+        let id = self.query(query).await?;
+        let response = wait_response(
+            "history",
+            &id,
+            response_rx,
+            error_rx,
+            timeout_duration,
+            "history query",
+            |p| p.pza_id.clone(),
+        )
+        .await?;
+        Ok(response.as_ref().clone())
+    }
+}
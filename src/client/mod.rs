@@ -1,6 +1,7 @@
 use bytes::Bytes;
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
 
 use tokio::sync::broadcast;
 use tokio::sync::Mutex;
@@ -13,6 +14,18 @@ use tracing::warn;
 mod builder;
 use builder::PowerSupplyClientBuilder;
 
+/// Discovery of the power supplies served on a broker.
+mod discovery;
+pub use discovery::discover_power_supplies;
+
+/// Control of the data logger of a server.
+mod data_logger;
+pub use data_logger::DataLoggerClient;
+
+/// Queries of the history store of a server.
+mod history;
+pub use history::HistoryClient;
+
 /// Mutable data structures for client state management.
 mod data;
 pub use data::MutableData;
@@ -22,9 +35,13 @@ mod error;
 pub use error::ClientError;
 
 use crate::payload::CurrentPayload;
+use crate::payload::ErrorPayload;
+use crate::payload::EstopPayload;
 use crate::payload::PowerState;
 use crate::payload::PowerStatePayload;
 use crate::payload::PzaId;
+use crate::payload::Status;
+use crate::payload::StatusPayload;
 use crate::payload::VoltagePayload;
 use crate::TopicId;
 use crate::Topics;
@@ -42,6 +59,11 @@ pub struct PowerSupplyClient {
     /// The underlying MQTT client for network communication.
     mqtt_client: RumqttCustomAsyncClient,
 
+    /// MQTT client publishing without retain, for the commands.
+    ///
+    /// A retained command would be replayed by the broker on each restart of the server.
+    command_client: RumqttCustomAsyncClient,
+
     /// Thread-safe mutable data container for current device state.
     mutable_data: Arc<Mutex<MutableData>>,
 
@@ -60,6 +82,31 @@ pub struct PowerSupplyClient {
         broadcast::Sender<Arc<CurrentPayload>>,
         broadcast::Receiver<Arc<CurrentPayload>>,
     ),
+    /// Channel for broadcasting emergency stop latch changes.
+    estop_channel: (
+        broadcast::Sender<Arc<EstopPayload>>,
+        broadcast::Receiver<Arc<EstopPayload>>,
+    ),
+    /// Channel for broadcasting measured output voltage updates.
+    measured_voltage_channel: (
+        broadcast::Sender<Arc<VoltagePayload>>,
+        broadcast::Receiver<Arc<VoltagePayload>>,
+    ),
+    /// Channel for broadcasting measured output current updates.
+    measured_current_channel: (
+        broadcast::Sender<Arc<CurrentPayload>>,
+        broadcast::Receiver<Arc<CurrentPayload>>,
+    ),
+    /// Channel for broadcasting runner status changes.
+    status_channel: (
+        broadcast::Sender<Arc<StatusPayload>>,
+        broadcast::Receiver<Arc<StatusPayload>>,
+    ),
+    /// Channel for broadcasting errors reported by the server.
+    error_channel: (
+        broadcast::Sender<Arc<ErrorPayload>>,
+        broadcast::Receiver<Arc<ErrorPayload>>,
+    ),
 
     /// MQTT topics configuration for this device instance.
     topics: Topics,
@@ -75,6 +122,7 @@ impl Clone for PowerSupplyClient {
         Self {
             psu_name: self.psu_name.clone(),
             mqtt_client: self.mqtt_client.clone(),
+            command_client: self.command_client.clone(),
             mutable_data: Arc::clone(&self.mutable_data),
             state_channel: (
                 self.state_channel.0.clone(),
@@ -88,6 +136,26 @@ impl Clone for PowerSupplyClient {
                 self.current_channel.0.clone(),
                 self.current_channel.1.resubscribe(),
             ),
+            estop_channel: (
+                self.estop_channel.0.clone(),
+                self.estop_channel.1.resubscribe(),
+            ),
+            measured_voltage_channel: (
+                self.measured_voltage_channel.0.clone(),
+                self.measured_voltage_channel.1.resubscribe(),
+            ),
+            measured_current_channel: (
+                self.measured_current_channel.0.clone(),
+                self.measured_current_channel.1.resubscribe(),
+            ),
+            status_channel: (
+                self.status_channel.0.clone(),
+                self.status_channel.1.resubscribe(),
+            ),
+            error_channel: (
+                self.error_channel.0.clone(),
+                self.error_channel.1.resubscribe(),
+            ),
             topics: self.topics.clone(),
         }
     }
@@ -112,24 +180,38 @@ impl PowerSupplyClient {
     fn new_from_builder(
         psu_name: String,
         client: RumqttCustomAsyncClient,
+        command_client: RumqttCustomAsyncClient,
         event_loop: rumqttc::EventLoop,
     ) -> Self {
         // Initialize broadcast channels for state updates
         let (state_tx, state_rx) = broadcast::channel::<Arc<PowerStatePayload>>(32);
         let (voltage_tx, voltage_rx) = broadcast::channel::<Arc<VoltagePayload>>(32);
         let (current_tx, current_rx) = broadcast::channel::<Arc<CurrentPayload>>(32);
+        let (estop_tx, estop_rx) = broadcast::channel::<Arc<EstopPayload>>(32);
+        let (measured_voltage_tx, measured_voltage_rx) =
+            broadcast::channel::<Arc<VoltagePayload>>(32);
+        let (measured_current_tx, measured_current_rx) =
+            broadcast::channel::<Arc<CurrentPayload>>(32);
+        let (status_tx, status_rx) = broadcast::channel::<Arc<StatusPayload>>(32);
+        let (error_tx, error_rx) = broadcast::channel::<Arc<ErrorPayload>>(32);
 
         // Create the client instance
         let obj = Self {
             topics: Topics::new(&psu_name),
             psu_name,
             mqtt_client: client,
+            command_client,
 
             mutable_data: Arc::new(Mutex::new(MutableData::default())),
 
             state_channel: (state_tx, state_rx),
             voltage_channel: (voltage_tx, voltage_rx),
             current_channel: (current_tx, current_rx),
+            estop_channel: (estop_tx, estop_rx),
+            measured_voltage_channel: (measured_voltage_tx, measured_voltage_rx),
+            measured_current_channel: (measured_current_tx, measured_current_rx),
+            status_channel: (status_tx, status_rx),
+            error_channel: (error_tx, error_rx),
         };
 
         // Start the background task loop for handling MQTT events
@@ -179,6 +261,11 @@ impl PowerSupplyClient {
     async fn handle_incoming_message(&self, topic: &String, payload: Bytes) {
         let id = self.topics.topic_to_id(topic);
 
+        // Any message from the server proves the cache is alive
+        if id.is_some() {
+            self.mutable_data.lock().await.last_update = Some(Instant::now());
+        }
+
         match id {
             None => {
                 error!("[{}] Unknown topic received: {}", self.psu_name, topic);
@@ -187,11 +274,46 @@ impl PowerSupplyClient {
             Some(TopicId::Status) => {
                 // Handle status updates
                 trace!("[{}] Status update received", self.psu_name);
+                match StatusPayload::from_json_bytes(payload) {
+                    Ok(status_payload) => {
+                        // Update internal state
+                        {
+                            let mut data = self.mutable_data.lock().await;
+                            data.status = Some(status_payload.status.clone());
+                            data.panic_message = status_payload.panic_message.clone();
+                        }
+
+                        // Broadcast to all listeners
+                        self.status_channel
+                            .0
+                            .send(Arc::new(status_payload))
+                            .expect("channel error");
+                    }
+                    Err(e) => {
+                        error!("[{}] Failed to parse status payload: {}", self.psu_name, e);
+                    }
+                }
             }
             Some(TopicId::Error) => {
                 // Handle error messages
-                let msg = String::from_utf8(payload.to_vec()).unwrap_or_default();
-                error!("[{}] Error received: {}", self.psu_name, msg);
+                match ErrorPayload::from_json_bytes(payload.clone()) {
+                    Ok(error_payload) => {
+                        error!(
+                            "[{}] Error received: {}",
+                            self.psu_name, error_payload.message
+                        );
+
+                        // Broadcast to all listeners
+                        self.error_channel
+                            .0
+                            .send(Arc::new(error_payload))
+                            .expect("channel error");
+                    }
+                    Err(_) => {
+                        let msg = String::from_utf8(payload.to_vec()).unwrap_or_default();
+                        error!("[{}] Error received: {}", self.psu_name, msg);
+                    }
+                }
             }
             Some(TopicId::State) => {
                 // Handle state updates (PowerStatePayload)
@@ -258,7 +380,80 @@ impl PowerSupplyClient {
                     }
                 }
             }
-            Some(TopicId::StateCmd) | Some(TopicId::VoltageCmd) | Some(TopicId::CurrentCmd) => {
+            Some(TopicId::Estop) => {
+                // Handle emergency stop latch updates
+                match EstopPayload::from_json_bytes(payload) {
+                    Ok(estop_payload) => {
+                        // Update internal state
+                        {
+                            let mut data = self.mutable_data.lock().await;
+                            data.estop_latched = estop_payload.latched;
+                        }
+
+                        // Broadcast to all listeners
+                        self.estop_channel
+                            .0
+                            .send(Arc::new(estop_payload))
+                            .expect("channel error");
+                    }
+                    Err(e) => {
+                        error!("[{}] Failed to parse estop payload: {}", self.psu_name, e);
+                    }
+                }
+            }
+            Some(TopicId::MeasureVoltage) => {
+                // Handle measured voltage updates
+                match VoltagePayload::from_json_bytes(payload) {
+                    Ok(voltage_payload) => {
+                        // Update internal state
+                        {
+                            let mut data = self.mutable_data.lock().await;
+                            data.measured_voltage = voltage_payload.voltage.clone();
+                        }
+
+                        // Broadcast to all listeners
+                        self.measured_voltage_channel
+                            .0
+                            .send(Arc::new(voltage_payload))
+                            .expect("channel error");
+                    }
+                    Err(e) => {
+                        error!(
+                            "[{}] Failed to parse measured voltage payload: {}",
+                            self.psu_name, e
+                        );
+                    }
+                }
+            }
+            Some(TopicId::MeasureCurrent) => {
+                // Handle measured current updates
+                match CurrentPayload::from_json_bytes(payload) {
+                    Ok(current_payload) => {
+                        // Update internal state
+                        {
+                            let mut data = self.mutable_data.lock().await;
+                            data.measured_current = current_payload.current.clone();
+                        }
+
+                        // Broadcast to all listeners
+                        self.measured_current_channel
+                            .0
+                            .send(Arc::new(current_payload))
+                            .expect("channel error");
+                    }
+                    Err(e) => {
+                        error!(
+                            "[{}] Failed to parse measured current payload: {}",
+                            self.psu_name, e
+                        );
+                    }
+                }
+            }
+            Some(TopicId::StateCmd)
+            | Some(TopicId::VoltageCmd)
+            | Some(TopicId::CurrentCmd)
+            | Some(TopicId::EstopCmd)
+            | Some(TopicId::AllEstop) => {
                 // These are command topics that the client sends to, not receives from
                 warn!(
                     "[{}] Unexpected command topic received: {}",
@@ -291,11 +486,86 @@ impl PowerSupplyClient {
 
     // ------------------------------------------------------------------------------
 
+    /// Gets the last measured output voltage of the power supply.
+    pub async fn get_measured_voltage(&self) -> String {
+        self.mutable_data.lock().await.measured_voltage.clone()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Gets the last measured output current of the power supply.
+    pub async fn get_measured_current(&self) -> String {
+        self.mutable_data.lock().await.measured_current.clone()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Gets the emergency stop latch state of the power supply.
+    pub async fn get_estop_latched(&self) -> bool {
+        self.mutable_data.lock().await.estop_latched
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Gets the last status reported by the power supply runner, None if unknown yet.
+    pub async fn get_status(&self) -> Option<Status> {
+        self.mutable_data.lock().await.status.clone()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Gets the panic message reported with the last status, if any.
+    pub async fn get_panic_message(&self) -> Option<String> {
+        self.mutable_data.lock().await.panic_message.clone()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Gets the time elapsed since the last message received from the server.
+    ///
+    /// Returns None if nothing has been received yet.
+    pub async fn cache_age(&self) -> Option<Duration> {
+        self.mutable_data
+            .lock()
+            .await
+            .last_update
+            .map(|instant| instant.elapsed())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Checks whether the cached data is older than the given age.
+    pub async fn is_cache_stale(&self, max_age: Duration) -> bool {
+        self.cache_age().await.map_or(true, |age| age > max_age)
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Refreshes the cached data and waits for the output state to be received.
+    ///
+    /// The server publishes retained messages, subscribing again makes the
+    /// broker deliver the last known values. Returns an error on timeout.
+    pub async fn refresh(&self, timeout_duration: Duration) -> anyhow::Result<()> {
+        trace!("[{}] Refreshing cache", self.psu_name);
+        let mut state_rx = self.subscribe_state_changes();
+        self.mqtt_client
+            .subscribe_to_all(self.topics.vec_sub_client())
+            .await;
+
+        match tokio::time::timeout(timeout_duration, state_rx.recv()).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(anyhow::anyhow!("Failed to receive state update: {}", e)),
+            Err(_) => Err(anyhow::anyhow!("Timeout waiting for cache refresh")),
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
     /// Enables the power supply output by sending an ON command.
     pub async fn enable_output(&self) -> anyhow::Result<PzaId> {
         trace!("[{}] Enabling output", self.psu_name);
         let payload = PowerStatePayload::from_state(PowerState::On);
-        self.mqtt_client
+        self.command_client
             .pubsh(&self.topics.state_cmd, payload.to_json_bytes()?)
             .await?;
         Ok(payload.pza_id)
@@ -305,46 +575,36 @@ impl PowerSupplyClient {
 
     /// Enables the power supply output and waits for confirmation.
     ///
-    /// Returns an error if the command fails or times out.
-    pub async fn enable_output_wait_ack(&self, timeout_duration: Duration) -> anyhow::Result<()> {
-        // Send the enable command
-        let id = self.enable_output().await?;
+    /// Returns the output state read back from the device. Returns an error if
+    /// the command is rejected by the server, fails or times out.
+    pub async fn enable_output_wait_ack(
+        &self,
+        timeout_duration: Duration,
+    ) -> anyhow::Result<PowerState> {
+        // Listen before sending the command so a fast response is not missed
+        let state_rx = self.subscribe_state_changes();
+        let error_rx = self.subscribe_errors();
 
-        // Wait for confirmation of state change
-        let mut state_rx = self.subscribe_state_changes();
-        let result = tokio::time::timeout(timeout_duration, async {
-            loop {
-                match state_rx.recv().await {
-                    Ok(state_payload) => {
-                        if state_payload.pza_id == id {
-                            if state_payload.state == PowerState::On {
-                                return Ok(());
-                            } else {
-                                return Err(anyhow::anyhow!(
-                                    "Enable output command failed - received state: {:?}",
-                                    state_payload.state
-                                ));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!(
-                            "[{}] Error receiving output enable state: {}",
-                            self.psu_name, e
-                        );
-                    }
-                }
-            }
-        })
-        .await;
-
-        // Return based on the result
-        match result {
-            Ok(Ok(())) => Ok(()),
-            Ok(Err(e)) => Err(e),
-            Err(_) => Err(anyhow::anyhow!(
-                "Timeout waiting for output enable confirmation"
-            )),
+        // Send the enable command and wait for the state read back by the server
+        let id = self.enable_output().await?;
+        let state_payload = wait_response(
+            &self.psu_name,
+            &id,
+            state_rx,
+            error_rx,
+            timeout_duration,
+            "output enable",
+            |p| p.pza_id.clone(),
+        )
+        .await?;
+
+        if state_payload.state == PowerState::On {
+            Ok(state_payload.state.clone())
+        } else {
+            Err(anyhow::anyhow!(
+                "Enable output command failed - received state: {:?}",
+                state_payload.state
+            ))
         }
     }
 
@@ -354,7 +614,7 @@ impl PowerSupplyClient {
     pub async fn disable_output(&self) -> anyhow::Result<PzaId> {
         trace!("[{}] Disabling output", self.psu_name);
         let payload = PowerStatePayload::from_state(PowerState::Off);
-        self.mqtt_client
+        self.command_client
             .pubsh(&self.topics.state_cmd, payload.to_json_bytes()?)
             .await?;
         Ok(payload.pza_id)
@@ -364,46 +624,36 @@ impl PowerSupplyClient {
 
     /// Disables the power supply output and waits for confirmation.
     ///
-    /// Returns an error if the command fails or times out.
-    pub async fn disable_output_wait_ack(&self, timeout_duration: Duration) -> anyhow::Result<()> {
-        // Send the disable command
-        let id = self.disable_output().await?;
+    /// Returns the output state read back from the device. Returns an error if
+    /// the command is rejected by the server, fails or times out.
+    pub async fn disable_output_wait_ack(
+        &self,
+        timeout_duration: Duration,
+    ) -> anyhow::Result<PowerState> {
+        // Listen before sending the command so a fast response is not missed
+        let state_rx = self.subscribe_state_changes();
+        let error_rx = self.subscribe_errors();
 
-        // Wait for confirmation of state change
-        let mut state_rx = self.subscribe_state_changes();
-        let result = tokio::time::timeout(timeout_duration, async {
-            loop {
-                match state_rx.recv().await {
-                    Ok(state_payload) => {
-                        if state_payload.pza_id == id {
-                            if state_payload.state == PowerState::Off {
-                                return Ok(());
-                            } else {
-                                return Err(anyhow::anyhow!(
-                                    "Disable output command failed - received state: {:?}",
-                                    state_payload.state
-                                ));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!(
-                            "[{}] Error receiving output disable state: {}",
-                            self.psu_name, e
-                        );
-                    }
-                }
-            }
-        })
-        .await;
-
-        // Return based on the result
-        match result {
-            Ok(Ok(())) => Ok(()),
-            Ok(Err(e)) => Err(e),
-            Err(_) => Err(anyhow::anyhow!(
-                "Timeout waiting for output disable confirmation"
-            )),
+        // Send the disable command and wait for the state read back by the server
+        let id = self.disable_output().await?;
+        let state_payload = wait_response(
+            &self.psu_name,
+            &id,
+            state_rx,
+            error_rx,
+            timeout_duration,
+            "output disable",
+            |p| p.pza_id.clone(),
+        )
+        .await?;
+
+        if state_payload.state == PowerState::Off {
+            Ok(state_payload.state.clone())
+        } else {
+            Err(anyhow::anyhow!(
+                "Disable output command failed - received state: {:?}",
+                state_payload.state
+            ))
         }
     }
 
@@ -413,7 +663,7 @@ impl PowerSupplyClient {
     pub async fn set_voltage(&self, voltage: String) -> anyhow::Result<PzaId> {
         trace!("[{}] Setting voltage to {}", self.psu_name, voltage);
         let payload = VoltagePayload::from_string(voltage);
-        self.mqtt_client
+        self.command_client
             .pubsh(&self.topics.voltage_cmd, payload.to_json_bytes()?)
             .await?;
         Ok(payload.pza_id)
@@ -423,47 +673,32 @@ impl PowerSupplyClient {
 
     /// Sets the voltage of the power supply and waits for confirmation.
     ///
-    /// Returns an error if the command fails or times out.
+    /// Returns the voltage read back from the device, which may be rounded
+    /// to the device resolution. Returns an error if the command is rejected
+    /// by the server (e.g. out of the security limits), fails or times out.
     pub async fn set_voltage_wait_ack(
         &self,
         voltage: String,
         timeout_duration: Duration,
-    ) -> anyhow::Result<()> {
-        // Send the voltage command
-        let id = self.set_voltage(voltage.clone()).await?;
-
-        // Wait for confirmation of voltage change
-        let mut voltage_rx = self.subscribe_voltage_changes();
-        let result = tokio::time::timeout(timeout_duration, async {
-            loop {
-                match voltage_rx.recv().await {
-                    Ok(voltage_payload) => {
-                        if voltage_payload.pza_id == id {
-                            if voltage_payload.voltage == voltage {
-                                return Ok(());
-                            } else {
-                                return Err(anyhow::anyhow!(
-                                    "Set voltage command failed - expected: {}, received: {}",
-                                    voltage,
-                                    voltage_payload.voltage
-                                ));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("[{}] Error receiving voltage update: {}", self.psu_name, e);
-                    }
-                }
-            }
-        })
-        .await;
-
-        // Return based on the result
-        match result {
-            Ok(Ok(())) => Ok(()),
-            Ok(Err(e)) => Err(e),
-            Err(_) => Err(anyhow::anyhow!("Timeout waiting for voltage confirmation")),
-        }
+    ) -> anyhow::Result<String> {
+        // Listen before sending the command so a fast response is not missed
+        let voltage_rx = self.subscribe_voltage_changes();
+        let error_rx = self.subscribe_errors();
+
+        // Send the voltage command and wait for the value read back by the server
+        let id = self.set_voltage(voltage).await?;
+        let voltage_payload = wait_response(
+            &self.psu_name,
+            &id,
+            voltage_rx,
+            error_rx,
+            timeout_duration,
+            "voltage",
+            |p| p.pza_id.clone(),
+        )
+        .await?;
+
+        Ok(voltage_payload.voltage.clone())
     }
 
     // ------------------------------------------------------------------------------
@@ -472,7 +707,7 @@ impl PowerSupplyClient {
     pub async fn set_current(&self, current: String) -> anyhow::Result<PzaId> {
         trace!("[{}] Setting current to {}", self.psu_name, current);
         let payload = CurrentPayload::from_string(current);
-        self.mqtt_client
+        self.command_client
             .pubsh(&self.topics.current_cmd, payload.to_json_bytes()?)
             .await?;
         Ok(payload.pza_id)
@@ -482,47 +717,65 @@ impl PowerSupplyClient {
 
     /// Sets the current limit of the power supply and waits for confirmation.
     ///
-    /// Returns an error if the command fails or times out.
+    /// Returns the current read back from the device, which may be rounded
+    /// to the device resolution. Returns an error if the command is rejected
+    /// by the server (e.g. out of the security limits), fails or times out.
     pub async fn set_current_wait_ack(
         &self,
         current: String,
         timeout_duration: Duration,
-    ) -> anyhow::Result<()> {
-        // Send the current command
-        let id = self.set_current(current.clone()).await?;
-
-        // Wait for confirmation of current change
-        let mut current_rx = self.subscribe_current_changes();
-        let result = tokio::time::timeout(timeout_duration, async {
-            loop {
-                match current_rx.recv().await {
-                    Ok(current_payload) => {
-                        if current_payload.pza_id == id {
-                            if current_payload.current == current {
-                                return Ok(());
-                            } else {
-                                return Err(anyhow::anyhow!(
-                                    "Set current command failed - expected: {}, received: {}",
-                                    current,
-                                    current_payload.current
-                                ));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("[{}] Error receiving current update: {}", self.psu_name, e);
-                    }
-                }
-            }
-        })
-        .await;
-
-        // Return based on the result
-        match result {
-            Ok(Ok(())) => Ok(()),
-            Ok(Err(e)) => Err(e),
-            Err(_) => Err(anyhow::anyhow!("Timeout waiting for current confirmation")),
-        }
+    ) -> anyhow::Result<String> {
+        // Listen before sending the command so a fast response is not missed
+        let current_rx = self.subscribe_current_changes();
+        let error_rx = self.subscribe_errors();
+
+        // Send the current command and wait for the value read back by the server
+        let id = self.set_current(current).await?;
+        let current_payload = wait_response(
+            &self.psu_name,
+            &id,
+            current_rx,
+            error_rx,
+            timeout_duration,
+            "current",
+            |p| p.pza_id.clone(),
+        )
+        .await?;
+
+        Ok(current_payload.current.clone())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Triggers the server-wide emergency stop.
+    ///
+    /// Every runner of the server disables its output and rejects ON commands
+    /// until its emergency stop is reset. The command is not retained, the
+    /// runners persist the latch themselves.
+    pub async fn emergency_stop(&self) -> anyhow::Result<PzaId> {
+        warn!(
+            "[{}] Triggering emergency stop on all runners",
+            self.psu_name
+        );
+        let payload = EstopPayload::from_latched(true);
+        self.command_client
+            .pubsh(&self.topics.all_estop, payload.to_json_bytes()?)
+            .await?;
+        Ok(payload.pza_id)
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Resets the emergency stop latch of this power supply.
+    ///
+    /// The output stays OFF, it must be enabled again explicitly.
+    pub async fn reset_emergency_stop(&self) -> anyhow::Result<PzaId> {
+        trace!("[{}] Resetting emergency stop", self.psu_name);
+        let payload = EstopPayload::from_latched(false);
+        self.command_client
+            .pubsh(&self.topics.estop_cmd, payload.to_json_bytes()?)
+            .await?;
+        Ok(payload.pza_id)
     }
 
     // ------------------------------------------------------------------------------
@@ -558,4 +811,104 @@ impl PowerSupplyClient {
     }
 
     // ------------------------------------------------------------------------------
+
+    /// Subscribes to emergency stop latch changes.
+    ///
+    /// Returns a receiver that will receive notifications when the power
+    /// supply emergency stop is latched or reset.
+    pub fn subscribe_estop_changes(&self) -> broadcast::Receiver<Arc<EstopPayload>> {
+        self.estop_channel.0.subscribe()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Subscribes to measured output voltage updates.
+    ///
+    /// Returns a receiver that will receive every voltage measurement
+    /// published by the power supply runner.
+    pub fn subscribe_measured_voltage_changes(&self) -> broadcast::Receiver<Arc<VoltagePayload>> {
+        self.measured_voltage_channel.0.subscribe()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Subscribes to measured output current updates.
+    ///
+    /// Returns a receiver that will receive every current measurement
+    /// published by the power supply runner.
+    pub fn subscribe_measured_current_changes(&self) -> broadcast::Receiver<Arc<CurrentPayload>> {
+        self.measured_current_channel.0.subscribe()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Subscribes to runner status changes.
+    ///
+    /// Returns a receiver that will receive notifications when the power
+    /// supply runner status changes (initializing, running, panicking).
+    pub fn subscribe_status_changes(&self) -> broadcast::Receiver<Arc<StatusPayload>> {
+        self.status_channel.0.subscribe()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Subscribes to errors reported by the server.
+    ///
+    /// Returns a receiver that will receive every error published by the
+    /// power supply runner, including command rejections.
+    pub fn subscribe_errors(&self) -> broadcast::Receiver<Arc<ErrorPayload>> {
+        self.error_channel.0.subscribe()
+    }
+
+    // ------------------------------------------------------------------------------
+}
+
+// ================
+
+/// Waits for the response of the server to the command with the given id.
+///
+/// The response is either the value read back by the server, received on
+/// `response_rx`, or a rejection published on the error topic. In the latter
+/// case the error message of the server is returned as a
+/// `ClientError::Rejected` error, a missing response as `ClientError::Timeout`.
+///
+/// Shared by the clients of the crate, `log_prefix` identifies the client in the logs.
+async fn wait_response<T: Send + Sync>(
+    log_prefix: &str,
+    id: &PzaId,
+    mut response_rx: broadcast::Receiver<Arc<T>>,
+    mut error_rx: broadcast::Receiver<Arc<ErrorPayload>>,
+    timeout_duration: Duration,
+    what: &str,
+    response_id: fn(&T) -> PzaId,
+) -> anyhow::Result<Arc<T>> {
+    let result = tokio::time::timeout(timeout_duration, async {
+        loop {
+            tokio::select! {
+                response = response_rx.recv() => match response {
+                    Ok(payload) if &response_id(&payload) == id => return Ok(payload),
+                    Ok(_) => {}
+                    Err(e) => {
+                        error!("[{}] Error receiving {} update: {}", log_prefix, what, e);
+                    }
+                },
+                error = error_rx.recv() => match error {
+                    Ok(payload) if &payload.pza_id == id => {
+                        return Err(ClientError::Rejected(payload.message.clone()).into());
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        error!("[{}] Error receiving error update: {}", log_prefix, e);
+                    }
+                },
+            }
+        }
+    })
+    .await;
+
+    // Return based on the result
+    match result {
+        Ok(response) => response,
+        Err(_) => Err(ClientError::Timeout(what.to_string()).into()),
+    }
 }
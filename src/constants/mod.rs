@@ -1,5 +1,11 @@
 mod topics;
-pub use topics::{TopicId, Topics};
+pub use topics::{DataLoggerTopics, HistoryTopics, SequenceTopics, TopicId, Topics};
 
 /// Name of the server type
 pub const SERVER_TYPE_NAME: &str = "power-supply";
+
+/// Reserved name used for topics addressed to every runner of the server
+pub const ALL_RUNNERS_NAME: &str = "_all";
+
+/// Maximum size of the MQTT packets of the history queries and responses
+pub const HISTORY_MAX_PACKET_SIZE: usize = 1024 * 1024;
@@ -1,3 +1,4 @@
+use crate::constants::ALL_RUNNERS_NAME;
 use crate::constants::SERVER_TYPE_NAME;
 
 pub enum TopicId {
@@ -9,6 +10,11 @@ pub enum TopicId {
     Voltage,
     CurrentCmd,
     Current,
+    EstopCmd,
+    Estop,
+    AllEstop,
+    MeasureVoltage,
+    MeasureCurrent,
 }
 
 /// Topics used for MQTT communication with the power supply
@@ -37,6 +43,23 @@ pub struct Topics {
     pub current_cmd: String,
     /// /current
     pub current: String,
+    // ---
+    /// Topic to latch or reset the emergency stop of this runner
+    /// /estop/cmd
+    pub estop_cmd: String,
+    /// Topic to receive the emergency stop latch state
+    /// /estop
+    pub estop: String,
+    /// Server-wide emergency stop topic shared by every runner
+    /// power-supply/_all/estop
+    pub all_estop: String,
+    // ---
+    /// Topic to receive the measured output voltage
+    /// /measure/voltage
+    pub measure_voltage: String,
+    /// Topic to receive the measured output current
+    /// /measure/current
+    pub measure_current: String,
 }
 
 impl Topics {
@@ -52,6 +75,11 @@ impl Topics {
             voltage: format!("{}/voltage", prefix),
             current_cmd: format!("{}/current/cmd", prefix),
             current: format!("{}/current", prefix),
+            estop_cmd: format!("{}/estop/cmd", prefix),
+            estop: format!("{}/estop", prefix),
+            all_estop: format!("{}/{}/estop", SERVER_TYPE_NAME, ALL_RUNNERS_NAME),
+            measure_voltage: format!("{}/measure/voltage", prefix),
+            measure_current: format!("{}/measure/current", prefix),
         }
     }
 
@@ -63,6 +91,9 @@ impl Topics {
             self.state.clone(),
             self.voltage.clone(),
             self.current.clone(),
+            self.estop.clone(),
+            self.measure_voltage.clone(),
+            self.measure_current.clone(),
         ]
     }
 
@@ -72,6 +103,8 @@ impl Topics {
             self.state_cmd.clone(),
             self.voltage_cmd.clone(),
             self.current_cmd.clone(),
+            self.estop_cmd.clone(),
+            self.all_estop.clone(),
         ]
     }
 
@@ -92,6 +125,16 @@ impl Topics {
             Some(TopicId::CurrentCmd)
         } else if topic == self.current {
             Some(TopicId::Current)
+        } else if topic == self.estop_cmd {
+            Some(TopicId::EstopCmd)
+        } else if topic == self.estop {
+            Some(TopicId::Estop)
+        } else if topic == self.all_estop {
+            Some(TopicId::AllEstop)
+        } else if topic == self.measure_voltage {
+            Some(TopicId::MeasureVoltage)
+        } else if topic == self.measure_current {
+            Some(TopicId::MeasureCurrent)
         } else {
             None
         }
@@ -107,6 +150,101 @@ impl Topics {
             TopicId::Voltage => &self.voltage,
             TopicId::CurrentCmd => &self.current_cmd,
             TopicId::Current => &self.current,
+            TopicId::EstopCmd => &self.estop_cmd,
+            TopicId::Estop => &self.estop,
+            TopicId::AllEstop => &self.all_estop,
+            TopicId::MeasureVoltage => &self.measure_voltage,
+            TopicId::MeasureCurrent => &self.measure_current,
+        }
+    }
+}
+
+/// Topics used for MQTT communication with a power sequence
+#[derive(Debug, Clone)]
+pub struct SequenceTopics {
+    /// Topic to send power-up/power-down commands
+    /// power-supply/sequence/<name>/cmd
+    pub cmd: String,
+    /// Topic to receive the sequence execution state
+    /// power-supply/sequence/<name>
+    pub status: String,
+    /// Topic for error messages
+    /// pza_id match the one from the command that caused the error
+    pub error: String,
+}
+
+impl SequenceTopics {
+    /// Create a new SequenceTopics instance for the given sequence name
+    pub fn new<A: AsRef<str>>(name: A) -> Self {
+        let prefix = format!("{}/sequence/{}", SERVER_TYPE_NAME, name.as_ref());
+        Self {
+            cmd: format!("{}/cmd", prefix),
+            status: prefix.clone(),
+            error: format!("{}/error", prefix),
+        }
+    }
+}
+
+/// Topics used for MQTT communication with the data logger
+#[derive(Debug, Clone)]
+pub struct DataLoggerTopics {
+    /// Topic to start or stop a recording session
+    /// power-supply/logger/cmd
+    pub cmd: String,
+    /// Topic to receive the data logger state
+    /// power-supply/logger
+    pub status: String,
+    /// Topic for error messages
+    /// pza_id match the one from the command that caused the error
+    pub error: String,
+}
+
+impl DataLoggerTopics {
+    /// Create a new DataLoggerTopics instance
+    pub fn new() -> Self {
+        let prefix = format!("{}/logger", SERVER_TYPE_NAME);
+        Self {
+            cmd: format!("{}/cmd", prefix),
+            status: prefix.clone(),
+            error: format!("{}/error", prefix),
         }
     }
 }
+
+impl Default for DataLoggerTopics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Topics used for MQTT communication with the history store
+#[derive(Debug, Clone)]
+pub struct HistoryTopics {
+    /// Topic to query the history of a runner
+    /// power-supply/history/cmd
+    pub cmd: String,
+    /// Topic to receive the responses to the queries
+    /// power-supply/history
+    pub response: String,
+    /// Topic for error messages
+    /// pza_id match the one from the query that caused the error
+    pub error: String,
+}
+
+impl HistoryTopics {
+    /// Create a new HistoryTopics instance
+    pub fn new() -> Self {
+        let prefix = format!("{}/history", SERVER_TYPE_NAME);
+        Self {
+            cmd: format!("{}/cmd", prefix),
+            response: prefix.clone(),
+            error: format!("{}/error", prefix),
+        }
+    }
+}
+
+impl Default for HistoryTopics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
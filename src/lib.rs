@@ -2,5 +2,10 @@ pub mod client;
 mod constants;
 pub mod payload;
 
+pub use client::DataLoggerClient;
+pub use client::HistoryClient;
 pub use client::PowerSupplyClient;
-pub use constants::{TopicId, Topics, SERVER_TYPE_NAME};
+pub use constants::{
+    DataLoggerTopics, HistoryTopics, SequenceTopics, TopicId, Topics, ALL_RUNNERS_NAME,
+    HISTORY_MAX_PACKET_SIZE, SERVER_TYPE_NAME,
+};
@@ -0,0 +1,98 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Action requested on the data logger
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DataLoggerAction {
+    /// Start a new recording session, the running one is closed first
+    #[serde(rename = "START")]
+    Start,
+    /// Stop the running recording session
+    #[serde(rename = "STOP")]
+    Stop,
+}
+
+/// Command payload to control the data logger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataLoggerPayload {
+    /// PZA identifier
+    /// On the command, the client generates this ID
+    /// On the response, the server echoes this ID
+    pub pza_id: String,
+    /// Action to execute
+    pub action: DataLoggerAction,
+    /// Name of the session to start, used in the file names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+}
+
+impl DataLoggerPayload {
+    /// Create a new DataLoggerPayload from an action
+    pub fn from_action(action: DataLoggerAction) -> Self {
+        Self {
+            pza_id: super::generate_pza_id(),
+            action,
+            session: None,
+        }
+    }
+
+    /// Set the name of the session to start
+    pub fn with_session(mut self, session: String) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Serialize the DataLoggerPayload to JSON bytes
+    pub fn to_json_bytes(&self) -> anyhow::Result<Bytes> {
+        Ok(Bytes::from(serde_json::to_string(self)?))
+    }
+
+    /// Deserialize a DataLoggerPayload from JSON bytes
+    pub fn from_json_bytes(bytes: Bytes) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// Status payload reporting the state of the data logger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataLoggerStatusPayload {
+    /// PZA identifier
+    /// On the response, the server echoes the ID of the command that changed the state
+    pub pza_id: String,
+    /// Whether a session is being recorded
+    pub recording: bool,
+    /// Identifier of the recorded session, or of the last one once stopped
+    pub session: Option<String>,
+    /// Files written by the session, the last one is the file being written
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+impl DataLoggerStatusPayload {
+    /// Create a new DataLoggerStatusPayload as a response to a command with the given pza_id
+    pub fn from_state_as_response(recording: bool, pza_id: String) -> Self {
+        Self {
+            pza_id,
+            recording,
+            session: None,
+            files: Vec::new(),
+        }
+    }
+
+    /// Set the session identifier and its files
+    pub fn with_session(mut self, session: String, files: Vec<String>) -> Self {
+        self.session = Some(session);
+        self.files = files;
+        self
+    }
+
+    /// Serialize the DataLoggerStatusPayload to JSON bytes
+    pub fn to_json_bytes(&self) -> anyhow::Result<Bytes> {
+        Ok(Bytes::from(serde_json::to_string(self)?))
+    }
+
+    /// Deserialize a DataLoggerStatusPayload from JSON bytes
+    pub fn from_json_bytes(bytes: Bytes) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
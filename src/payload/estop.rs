@@ -0,0 +1,38 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Emergency stop payload for latching and resetting runners
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstopPayload {
+    /// PZA identifier
+    /// On the command, the client generates this ID
+    /// On the response, the server echoes this ID
+    pub pza_id: String,
+    /// True when the emergency stop is latched (outputs locked OFF)
+    pub latched: bool,
+}
+
+impl EstopPayload {
+    /// Create a new EstopPayload from a latch state
+    pub fn from_latched(latched: bool) -> Self {
+        Self {
+            pza_id: super::generate_pza_id(),
+            latched,
+        }
+    }
+
+    /// Create a new EstopPayload as a response to a command with the given pza_id
+    pub fn from_latched_as_response(latched: bool, pza_id: String) -> Self {
+        Self { pza_id, latched }
+    }
+
+    /// Serialize the EstopPayload to JSON bytes
+    pub fn to_json_bytes(&self) -> anyhow::Result<Bytes> {
+        Ok(Bytes::from(serde_json::to_string(self)?))
+    }
+
+    /// Deserialize an EstopPayload from JSON bytes
+    pub fn from_json_bytes(bytes: Bytes) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
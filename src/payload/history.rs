@@ -0,0 +1,123 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Query of the history of a runner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryQueryPayload {
+    /// PZA identifier
+    /// On the query, the client generates this ID
+    /// On the response, the server echoes this ID
+    pub pza_id: String,
+    /// Name of the runner
+    pub runner: String,
+    /// Series to return, all the series if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<String>>,
+    /// Start of the time range, in milliseconds since the Unix epoch
+    /// Oldest retained sample if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_ms: Option<u64>,
+    /// End of the time range, in milliseconds since the Unix epoch
+    /// Reception time of the query if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_ms: Option<u64>,
+    /// Maximum number of points of each series, the samples are downsampled beyond
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_points: Option<usize>,
+}
+
+impl HistoryQueryPayload {
+    /// Create a new HistoryQueryPayload for all the series of a runner
+    pub fn from_runner(runner: String) -> Self {
+        Self {
+            pza_id: super::generate_pza_id(),
+            runner,
+            events: None,
+            from_ms: None,
+            to_ms: None,
+            max_points: None,
+        }
+    }
+
+    /// Select the series to return
+    pub fn with_events(mut self, events: Vec<String>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Set the time range, in milliseconds since the Unix epoch
+    pub fn with_range(mut self, from_ms: Option<u64>, to_ms: Option<u64>) -> Self {
+        self.from_ms = from_ms;
+        self.to_ms = to_ms;
+        self
+    }
+
+    /// Set the maximum number of points of each series
+    pub fn with_max_points(mut self, max_points: usize) -> Self {
+        self.max_points = Some(max_points);
+        self
+    }
+
+    /// Serialize the HistoryQueryPayload to JSON bytes
+    pub fn to_json_bytes(&self) -> anyhow::Result<Bytes> {
+        Ok(Bytes::from(serde_json::to_string(self)?))
+    }
+
+    /// Deserialize a HistoryQueryPayload from JSON bytes
+    pub fn from_json_bytes(bytes: Bytes) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// Point of a series, aggregating the samples of a time bucket
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryPoint {
+    /// Start of the bucket, in milliseconds since the Unix epoch
+    pub time_ms: u64,
+    /// Mean of the samples of the bucket
+    pub mean: f64,
+    /// Minimum of the samples of the bucket
+    pub min: f64,
+    /// Maximum of the samples of the bucket
+    pub max: f64,
+}
+
+/// Series of values of a runner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySeries {
+    /// Kind of value: `state`, `voltage`, `current`, `measured_voltage`,
+    /// `measured_current` or `estop`
+    pub event: String,
+    /// Number of samples in the time range, before downsampling
+    pub samples: usize,
+    /// Points of the series, in chronological order
+    pub points: Vec<HistoryPoint>,
+}
+
+/// Response to a history query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryResponsePayload {
+    /// PZA identifier
+    /// On the response, the server echoes the ID of the query
+    pub pza_id: String,
+    /// Name of the runner
+    pub runner: String,
+    /// Start of the time range, in milliseconds since the Unix epoch
+    pub from_ms: u64,
+    /// End of the time range, in milliseconds since the Unix epoch
+    pub to_ms: u64,
+    /// Series of the runner
+    pub series: Vec<HistorySeries>,
+}
+
+impl HistoryResponsePayload {
+    /// Serialize the HistoryResponsePayload to JSON bytes
+    pub fn to_json_bytes(&self) -> anyhow::Result<Bytes> {
+        Ok(Bytes::from(serde_json::to_string(self)?))
+    }
+
+    /// Deserialize a HistoryResponsePayload from JSON bytes
+    pub fn from_json_bytes(bytes: Bytes) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
@@ -1,12 +1,20 @@
 mod current;
+mod data_logger;
 mod error;
+mod estop;
+mod history;
 mod power_state;
+mod sequence;
 mod status;
 mod voltage;
 
 pub use current::CurrentPayload;
+pub use data_logger::{DataLoggerAction, DataLoggerPayload, DataLoggerStatusPayload};
 pub use error::ErrorPayload;
+pub use estop::EstopPayload;
+pub use history::{HistoryPoint, HistoryQueryPayload, HistoryResponsePayload, HistorySeries};
 pub use power_state::{PowerState, PowerStatePayload};
+pub use sequence::{SequenceAction, SequencePayload, SequenceState, SequenceStatusPayload};
 pub use status::Status;
 pub use status::StatusPayload;
 pub use voltage::VoltagePayload;
@@ -0,0 +1,100 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Action requested on a power sequence
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SequenceAction {
+    /// Bring the rails up in the configured order
+    #[serde(rename = "UP")]
+    Up,
+    /// Tear the rails down in the reverse order
+    #[serde(rename = "DOWN")]
+    Down,
+}
+
+/// Execution state of a power sequence
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SequenceState {
+    /// The sequence has not been executed yet
+    Idle,
+    /// The power-up steps are being executed
+    PoweringUp,
+    /// All the rails of the sequence are up
+    Up,
+    /// The power-down steps are being executed
+    PoweringDown,
+    /// All the rails of the sequence are down
+    Down,
+    /// A step failed, the group has been powered down
+    Aborted,
+}
+
+/// Command payload to execute a power sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencePayload {
+    /// PZA identifier
+    /// On the command, the client generates this ID
+    /// On the response, the server echoes this ID
+    pub pza_id: String,
+    /// Action to execute
+    pub action: SequenceAction,
+}
+
+impl SequencePayload {
+    /// Create a new SequencePayload from an action
+    pub fn from_action(action: SequenceAction) -> Self {
+        Self {
+            pza_id: super::generate_pza_id(),
+            action,
+        }
+    }
+
+    /// Serialize the SequencePayload to JSON bytes
+    pub fn to_json_bytes(&self) -> anyhow::Result<Bytes> {
+        Ok(Bytes::from(serde_json::to_string(self)?))
+    }
+
+    /// Deserialize a SequencePayload from JSON bytes
+    pub fn from_json_bytes(bytes: Bytes) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// Status payload reporting the execution state of a power sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceStatusPayload {
+    /// PZA identifier
+    /// On the response, the server echoes the ID of the command being executed
+    pub pza_id: String,
+    /// Current execution state of the sequence
+    pub state: SequenceState,
+    /// Optional message describing the failure if state is Aborted
+    pub message: Option<String>,
+}
+
+impl SequenceStatusPayload {
+    /// Create a new SequenceStatusPayload as a response to a command with the given pza_id
+    pub fn from_state_as_response(state: SequenceState, pza_id: String) -> Self {
+        Self {
+            pza_id,
+            state,
+            message: None,
+        }
+    }
+
+    /// Set the message describing the failure
+    pub fn with_message(mut self, message: String) -> Self {
+        self.message = Some(message);
+        self
+    }
+
+    /// Serialize the SequenceStatusPayload to JSON bytes
+    pub fn to_json_bytes(&self) -> anyhow::Result<Bytes> {
+        Ok(Bytes::from(serde_json::to_string(self)?))
+    }
+
+    /// Deserialize a SequenceStatusPayload from JSON bytes
+    pub fn from_json_bytes(bytes: Bytes) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
@@ -1,5 +1,7 @@
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 /// Status of a power supply instance
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +25,10 @@ pub struct StatusPayload {
     pub status: Status,
     /// Optional panic message if status is Panicking
     pub panic_message: Option<String>,
+    /// Publication time, in milliseconds since the Unix epoch
+    /// Tells a status of the running server from one retained by a previous run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_ms: Option<u64>,
 }
 
 impl StatusPayload {
@@ -32,6 +38,10 @@ impl StatusPayload {
             pza_id: super::generate_pza_id(),
             status,
             panic_message: None,
+            time_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_millis() as u64),
         }
     }
 
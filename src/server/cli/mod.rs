@@ -2,8 +2,11 @@ use clap::{Parser, Subcommand};
 
 /// Command line interface for the power supply application.
 ///
-/// Provides the `list` subcommand to enumerate resources and the `run`
-/// subcommand to start the application with optional services disabled.
+/// Provides the `list` subcommand to enumerate resources, the `run`
+/// subcommand to start the application with optional services disabled,
+/// the `mcp-stdio` subcommand to serve MCP tools over stdio, the
+/// `console` subcommand to open the TUI on a remote broker and the
+/// `wait-ready` subcommand to wait for a running server to be ready.
 #[derive(Parser, Debug, Clone, PartialEq)]
 #[command(version, about, long_about = None)]
 pub struct Args {
@@ -37,6 +40,39 @@ pub enum Commands {
         services: ServicesOverrides,
     },
 
+    /// Serve the MCP tools of one power supply over stdin/stdout
+    ///
+    /// Meant to be launched as a subprocess by desktop agents, the runner must
+    /// be served by a `run` instance connected to the same broker.
+    McpStdio {
+        /// Name of the power supply (runner) to control
+        name: String,
+    },
+
+    /// Open the TUI on the power supplies served on a remote broker
+    ///
+    /// The runners are discovered through their status topics, no service is
+    /// started locally.
+    Console {
+        /// Address of the broker, as `host` or `host:port` (default port 1883)
+        #[arg(long = "broker")]
+        broker: String,
+    },
+
+    /// Wait until a running server is ready, for scripts and orchestration
+    ///
+    /// Polls the `/readyz` endpoint of the HTTP API. Exits with 0 once the
+    /// server is ready, 1 on timeout and 2 when no address is known.
+    WaitReady {
+        /// Maximum time to wait, in seconds
+        #[arg(long = "timeout", default_value_t = 60)]
+        timeout: u64,
+
+        /// Address of the HTTP API as `host:port` (default from the configuration)
+        #[arg(long = "address")]
+        address: Option<String>,
+    },
+
     /// Display the version of the application
     Version,
 }
@@ -56,6 +92,10 @@ pub struct ServicesOverrides {
     #[arg(long = "no-mcp")]
     pub no_mcp: bool,
 
+    /// Disable the HTTP API
+    #[arg(long = "no-http")]
+    pub no_http: bool,
+
     /// Disable runners
     #[arg(long = "no-runners")]
     pub no_runners: bool,
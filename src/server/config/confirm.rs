@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Thresholds above which MCP commands must be confirmed by the user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConfirmConfig {
+    /// Confirm enabling the output (or raising the voltage while it is ON) above this voltage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_above_voltage: Option<f32>,
+
+    /// Confirm setting the current limit above this current
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_above: Option<f32>,
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DataLoggerConfig {
+    /// Names of the runners to record, all the runners if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runners: Option<Vec<String>>,
+
+    /// Directory of the log files, relative to the Panduza user directory
+    /// (`logs/power-supply` by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+
+    /// Write a JSON Lines file next to each CSV file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonl: Option<bool>,
+
+    /// Size of a file after which the session continues in a new file, in kilobytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_size_kb: Option<u64>,
+
+    /// Start a session when the server starts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autostart: Option<bool>,
+}
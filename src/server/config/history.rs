@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Names of the runners to keep the history of, all the runners if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runners: Option<Vec<String>>,
+
+    /// Maximum number of samples kept for each series of a runner, the oldest
+    /// ones are dropped beyond
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_samples: Option<usize>,
+
+    /// Age after which the samples are dropped, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention_s: Option<u64>,
+
+    /// Keep the history in a file of the Panduza user directory, so it
+    /// survives a restart of the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub persist: Option<bool>,
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use super::McpAuthConfig;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Enable or disable the HTTP API
+    pub enable: bool,
+    /// Bind address of the HTTP API
+    pub host: String,
+    /// Port of the HTTP API
+    pub port: u16,
+    /// Time to wait for the runner to acknowledge a command, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ack_timeout_ms: Option<u64>,
+    /// Tokens required by the commands, same format as the MCP authentication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<McpAuthConfig>,
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InterlockConfig {
+    /// Names of the runners that must be ON before this runner can be enabled
+    pub requires: Vec<String>,
+}
+
+// ================
+
+/// Check that the interlock rules only reference configured runners and have no cycle
+///
+/// A cycle (A requires B, B requires A) would lock its runners OFF forever.
+pub fn check_interlocks(
+    interlocks: &HashMap<String, InterlockConfig>,
+    runner_names: &[String],
+) -> anyhow::Result<()> {
+    // Every runner of a rule must be configured
+    for (name, interlock) in interlocks {
+        for runner in std::iter::once(name).chain(&interlock.requires) {
+            if !runner_names.contains(runner) {
+                return Err(anyhow::anyhow!(
+                    "Interlock of '{}' references unknown runner '{}'",
+                    name,
+                    runner
+                ));
+            }
+        }
+    }
+
+    // Depth-first search from each rule, keeping the path to report the cycle
+    let mut checked: Vec<&String> = Vec::new();
+    let mut names: Vec<&String> = interlocks.keys().collect();
+    names.sort();
+    for name in names {
+        let mut path = Vec::new();
+        find_cycle(interlocks, name, &mut path, &mut checked)?;
+    }
+    Ok(())
+}
+
+// ================
+
+/// Follow the prerequisites of a runner, failing if one of them is already on the path
+fn find_cycle<'a>(
+    interlocks: &'a HashMap<String, InterlockConfig>,
+    name: &'a String,
+    path: &mut Vec<&'a String>,
+    checked: &mut Vec<&'a String>,
+) -> anyhow::Result<()> {
+    if let Some(start) = path.iter().position(|runner| *runner == name) {
+        let cycle: Vec<&str> = path[start..]
+            .iter()
+            .chain(std::iter::once(&name))
+            .map(|runner| runner.as_str())
+            .collect();
+        return Err(anyhow::anyhow!(
+            "Interlock cycle between runners: {}",
+            cycle.join(" -> ")
+        ));
+    }
+    if checked.contains(&name) {
+        return Ok(());
+    }
+
+    path.push(name);
+    if let Some(interlock) = interlocks.get(name) {
+        for required in &interlock.requires {
+            find_cycle(interlocks, required, path, checked)?;
+        }
+    }
+    path.pop();
+    checked.push(name);
+    Ok(())
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --------------------------------------------------------------------------------
+
+    fn interlocks(rules: &[(&str, &[&str])]) -> HashMap<String, InterlockConfig> {
+        rules
+            .iter()
+            .map(|(name, requires)| {
+                (
+                    name.to_string(),
+                    InterlockConfig {
+                        requires: requires.iter().map(|r| r.to_string()).collect(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    fn runners(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn accepts_chains_and_shared_prerequisites() {
+        let rules = interlocks(&[
+            ("io", &["core"]),
+            ("core", &["aux"]),
+            ("dut", &["core", "aux"]),
+        ]);
+        assert!(check_interlocks(&rules, &runners(&["io", "core", "aux", "dut"])).is_ok());
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn rejects_unknown_runners() {
+        let rules = interlocks(&[("io", &["core"])]);
+        let error = check_interlocks(&rules, &runners(&["io"])).unwrap_err();
+        assert!(error.to_string().contains("unknown runner 'core'"));
+
+        let error = check_interlocks(&rules, &runners(&["core"])).unwrap_err();
+        assert!(error.to_string().contains("unknown runner 'io'"));
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn rejects_cycles() {
+        let rules = interlocks(&[("a", &["b"]), ("b", &["a"])]);
+        let error = check_interlocks(&rules, &runners(&["a", "b"])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Interlock cycle between runners: a -> b -> a"
+        );
+
+        let rules = interlocks(&[("a", &["a"])]);
+        assert!(check_interlocks(&rules, &runners(&["a"])).is_err());
+
+        let rules = interlocks(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+        assert!(check_interlocks(&rules, &runners(&["a", "b", "c"])).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::McpAuthConfig;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct McpConfig {
     /// Enable or disable the MCP server
@@ -8,4 +10,13 @@ pub struct McpConfig {
     pub host: String,
     /// Port of the MCP server
     pub port: u16,
+    /// Time to wait for the runner to acknowledge a command, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ack_timeout_ms: Option<u64>,
+    /// Authentication of the clients, no authentication if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<McpAuthConfig>,
+    /// Origins allowed by CORS, any origin is allowed if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cors_allowed_origins: Option<Vec<String>>,
 }
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// Authentication of the MCP HTTP server
+///
+/// Clients must send one of the tokens, either as `Authorization: Bearer <token>`
+/// or as `X-API-Key: <token>`. Requests without a valid token are rejected.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct McpAuthConfig {
+    /// Tokens stored directly in the configuration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<Vec<McpTokenConfig>>,
+
+    /// JSON5 file holding a list of tokens, relative paths are resolved from the configuration directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secrets_file: Option<String>,
+}
+
+/// Token granting access to the MCP HTTP server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct McpTokenConfig {
+    /// Optional name of the token owner, used in the logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Secret value of the token
+    pub token: String,
+
+    /// What the token allows
+    pub scope: McpScope,
+}
+
+/// What a token allows on the MCP server
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpScope {
+    /// Read tools, resources and prompts only
+    ReadOnly,
+    /// Everything, including the commands changing the power supply output
+    Control,
+}
@@ -1,12 +1,31 @@
+mod confirm;
+mod data_logger;
+mod history;
+mod http;
+mod interlock;
 mod mcp;
-mod path;
+mod mcp_auth;
+pub mod path;
 mod power_supply;
+mod sequence;
 mod tui;
 
-use crate::server::config::mcp::McpConfig;
+pub use confirm::ConfirmConfig;
+pub use data_logger::DataLoggerConfig;
+pub use history::HistoryConfig;
+pub use http::HttpConfig;
+use interlock::check_interlocks;
+pub use interlock::InterlockConfig;
+pub use mcp::McpConfig;
+pub use mcp_auth::McpAuthConfig;
+pub use mcp_auth::McpScope;
+pub use mcp_auth::McpTokenConfig;
 pub use power_supply::PowerSupplyConfig;
+pub use power_supply::RestorePolicy;
 use pza_toolkit::config::MqttBrokerConfig;
 use pza_toolkit::dioxus::logger::LoggerBuilder;
+pub use sequence::SequenceConfig;
+pub use sequence::SequenceStepConfig;
 use serde::de;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -23,11 +42,31 @@ pub struct ServerConfig {
     /// MCP server configuration
     pub mcp: McpConfig,
 
+    /// HTTP API configuration, disabled if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpConfig>,
+
     /// MQTT broker configuration
     pub broker: MqttBrokerConfig,
 
     /// Power supply configurations, keyed by their unique identifiers
     pub runners: Option<HashMap<String, PowerSupplyConfig>>,
+
+    /// Power sequences across multiple runners, keyed by their names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequences: Option<HashMap<String, SequenceConfig>>,
+
+    /// Interlock rules, keyed by the name of the dependent runner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interlocks: Option<HashMap<String, InterlockConfig>>,
+
+    /// Data logger recording the runners to files, disabled if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_logger: Option<DataLoggerConfig>,
+
+    /// History of the runners queryable by time range, disabled if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistoryConfig>,
 }
 
 impl Default for ServerConfig {
@@ -43,6 +82,10 @@ impl Default for ServerConfig {
                 security_max_voltage: Some(30.0),
                 security_min_current: Some(0.0),
                 security_max_current: Some(5.0),
+                measure_period_ms: Some(1000),
+                restore: None,
+                save_to_memory: None,
+                confirm: None,
             },
         );
 
@@ -51,14 +94,29 @@ impl Default for ServerConfig {
             tui: tui::TuiConfig {
                 enable: Some(true),
                 power_toggle_key: Some("p".to_string()),
+                emergency_stop_key: Some("s".to_string()),
             },
             mcp: McpConfig {
                 enable: false,
                 host: "127.0.0.1".to_string(),
                 port: 50051,
+                ack_timeout_ms: None,
+                auth: None,
+                cors_allowed_origins: None,
             },
+            http: Some(HttpConfig {
+                enable: false,
+                host: "127.0.0.1".to_string(),
+                port: 8080,
+                ack_timeout_ms: None,
+                auth: None,
+            }),
             broker: MqttBrokerConfig::default(),
             runners: Some(runners),
+            sequences: None,
+            interlocks: None,
+            data_logger: None,
+            history: None,
         }
     }
 }
@@ -70,7 +128,11 @@ impl ServerConfig {
         let config_path = path::server_config_file()
             .ok_or_else(|| anyhow::anyhow!("Failed to determine server configuration file path"))?;
 
-        pza_toolkit::config::read_config::<ServerConfig>(&config_path)
+        let config = pza_toolkit::config::read_config::<ServerConfig>(&config_path)?;
+        if let Some(interlocks) = &config.interlocks {
+            check_interlocks(interlocks, &config.runner_names())?;
+        }
+        Ok(config)
     }
 
     /// List MCP server URLs from the configuration
@@ -89,6 +151,15 @@ impl ServerConfig {
                 );
                 urls.push(url);
             }
+
+            // Aggregated endpoint covering all the runners
+            urls.push(format!(
+                "http://{}:{}/{}/{}",
+                self.mcp.host,
+                self.mcp.port,
+                pza_power_supply_client::SERVER_TYPE_NAME,
+                pza_power_supply_client::ALL_RUNNERS_NAME
+            ));
         }
 
         urls
@@ -115,6 +186,11 @@ impl ServerConfig {
         if overrides.no_mcp {
             self.mcp.enable = false;
         }
+        if overrides.no_http {
+            if let Some(http) = self.http.as_mut() {
+                http.enable = false;
+            }
+        }
         if overrides.no_tui {
             self.tui.enable = Some(false);
         }
@@ -132,13 +208,16 @@ impl ServerConfig {
         }
     }
 
-    /// Determine if tracing should be enabled based on TUI configuration
+    /// Determine if tracing to the terminal should be enabled based on TUI configuration
     pub fn should_enable_tracing(&self) -> bool {
-        // Enable tracing if TUI is disabled
-        !self.tui.enable.unwrap_or(false)
+        // Enable tracing if TUI is disabled, the TUI is enabled by default
+        !self.tui.enable.unwrap_or(true)
     }
 
     /// Setup tracing based on the configuration
+    ///
+    /// With the TUI the terminal belongs to the TUI, events are recorded in
+    /// the log buffer displayed by its log pane instead.
     pub fn setup_tracing(self) -> Self {
         if self.should_enable_tracing() {
             LoggerBuilder::default()
@@ -150,6 +229,8 @@ impl ServerConfig {
                 .filter_warnings()
                 .build()
                 .expect("failed to init logger");
+        } else {
+            crate::server::logs::init_buffer_tracing().expect("failed to init logger");
         }
         self
     }
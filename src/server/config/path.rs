@@ -5,6 +5,7 @@
 
 use pza_power_supply_client::SERVER_TYPE_NAME;
 use pza_toolkit::path::server_configs_dir;
+use pza_toolkit::path::user_root_dir;
 use std::path::PathBuf;
 
 /// Get the path to the server configuration file
@@ -12,3 +13,48 @@ use std::path::PathBuf;
 pub fn server_config_file() -> Option<PathBuf> {
     server_configs_dir().map(|root| root.join(format!("pza-{}.json5", SERVER_TYPE_NAME)))
 }
+
+/// Get the path to the server state file (last setpoints of each runner)
+///
+pub fn server_state_file() -> Option<PathBuf> {
+    user_root_dir().map(|root| root.join(format!("pza-{}-state.json", SERVER_TYPE_NAME)))
+}
+
+/// Get the path to the history file (samples of the history store)
+///
+pub fn server_history_file() -> Option<PathBuf> {
+    user_root_dir().map(|root| root.join(format!("pza-{}-history.csv", SERVER_TYPE_NAME)))
+}
+
+/// Get the default directory of the data logger files
+///
+pub fn data_logger_dir() -> Option<PathBuf> {
+    user_root_dir().map(|root| root.join("logs").join(SERVER_TYPE_NAME))
+}
+
+/// Resolve a directory of the data logger, relative paths start from the Panduza user directory
+///
+pub fn resolve_data_logger_dir(directory: Option<&str>) -> Option<PathBuf> {
+    match directory {
+        None => data_logger_dir(),
+        Some(directory) => {
+            let path = PathBuf::from(directory);
+            if path.is_absolute() {
+                Some(path)
+            } else {
+                user_root_dir().map(|root| root.join(path))
+            }
+        }
+    }
+}
+
+/// Resolve a path given in the configuration, relative paths start from the configuration directory
+///
+pub fn resolve_config_path(path: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        server_configs_dir().map(|root| root.join(path))
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::ConfirmConfig;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PowerSupplyConfig {
     /// Unique identifier for the power supply
@@ -21,4 +23,33 @@ pub struct PowerSupplyConfig {
     /// Security limits for current
     #[serde(skip_serializing_if = "Option::is_none")]
     pub security_max_current: Option<f32>,
+
+    /// Period between two measurements of the output, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub measure_period_ms: Option<u64>,
+
+    /// Policy applied to the persisted setpoints when the runner starts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restore: Option<RestorePolicy>,
+
+    /// Save the settings to the device memory after each change (KD3005P only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_to_memory: Option<bool>,
+
+    /// Commands from MCP agents requiring a confirmation of the user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<ConfirmConfig>,
+}
+
+/// Policy applied to the persisted setpoints and output state on startup
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestorePolicy {
+    /// Restore the last setpoints and output state
+    Restore,
+    /// Restore the last setpoints but keep the output OFF
+    RestoreOff,
+    /// Keep the driver defaults
+    #[default]
+    Defaults,
 }
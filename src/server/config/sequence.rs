@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequenceConfig {
+    /// Optional description of the sequence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Ordered steps applied on power-up, applied in reverse order on power-down
+    pub steps: Vec<SequenceStepConfig>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequenceStepConfig {
+    /// Name of the runner driven by this step
+    pub runner: String,
+
+    /// Delay before this step, in milliseconds (after this step on power-down)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+
+    /// Voltage setpoint applied before enabling the output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voltage: Option<String>,
+    /// Current limit applied before enabling the output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+
+    /// Optional condition to wait for after enabling the output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_voltage: Option<SequenceWaitConfig>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequenceWaitConfig {
+    /// Accepted gap between the measured voltage and the setpoint, in Volts
+    pub tolerance: f32,
+
+    /// Maximum time to wait for the measured voltage, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
@@ -9,4 +9,8 @@ pub struct TuiConfig {
     /// Keyboard shortcut to toggle power output
     #[serde(skip_serializing_if = "Option::is_none")]
     pub power_toggle_key: Option<String>,
+
+    /// Keyboard shortcut to trigger the emergency stop of all runners
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emergency_stop_key: Option<String>,
 }
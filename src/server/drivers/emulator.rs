@@ -3,6 +3,9 @@ use crate::server::drivers::PowerSupplyDriver;
 use async_trait::async_trait;
 use tracing::info;
 
+/// Resistance of the load simulated on the emulator output, in Ohms
+const EMULATED_LOAD_OHMS: f32 = 10.0;
+
 /// A power supply emulator for testing and development purposes
 pub struct PowerSupplyEmulator {
     state_oe: bool,
@@ -205,4 +208,27 @@ impl PowerSupplyDriver for PowerSupplyEmulator {
     fn security_max_current(&self) -> Option<f32> {
         self.security_max_current
     }
+
+    //--------------------------------------------------------------------------
+
+    /// Measure the output voltage, the emulated load never pulls the voltage down
+    async fn measure_voltage(&mut self) -> anyhow::Result<String> {
+        if !self.state_oe {
+            return Ok("0.000".to_string());
+        }
+        let voltage: f32 = self.voltage.parse().unwrap_or(0.0);
+        Ok(format!("{:.3}", voltage))
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Measure the output current drawn by the emulated resistive load
+    async fn measure_current(&mut self) -> anyhow::Result<String> {
+        if !self.state_oe {
+            return Ok("0.000".to_string());
+        }
+        let voltage: f32 = self.voltage.parse().unwrap_or(0.0);
+        let limit: f32 = self.current.parse().unwrap_or(0.0);
+        Ok(format!("{:.3}", (voltage / EMULATED_LOAD_OHMS).min(limit)))
+    }
 }
@@ -43,6 +43,26 @@ impl Kd3005pDriver {
             "security_max_current": Some(3.0_f32),
         })
     }
+
+    //--------------------------------------------------------------------------
+
+    /// Save the settings to the device's memory slot 1, unless disabled by configuration
+    ///
+    /// The device memory has limited write endurance, it can be preserved by
+    /// disabling this and relying on the server side state persistence.
+    async fn save_to_memory(&self) -> anyhow::Result<()> {
+        if !self.config.save_to_memory.unwrap_or(true) {
+            return Ok(());
+        }
+        self.driver
+            .as_ref()
+            .expect("Driver not initialized")
+            .lock()
+            .await
+            .execute(Command::Save(1))
+            .map_err(|e| anyhow::anyhow!("Failed to save: {:?}", e))?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -110,13 +130,7 @@ impl PowerSupplyDriver for Kd3005pDriver {
 
         // Save the settings to the device's memory
         // Important to avoid bad config after power cycle
-        self.driver
-            .as_ref()
-            .expect("Driver not initialized")
-            .lock()
-            .await
-            .execute(Command::Save(1))
-            .map_err(|e| anyhow::anyhow!("Failed to save: {:?}", e))?;
+        self.save_to_memory().await?;
 
         Ok(())
     }
@@ -184,13 +198,7 @@ impl PowerSupplyDriver for Kd3005pDriver {
 
         // Save the settings to the device's memory
         // Important to avoid bad config after power cycle
-        self.driver
-            .as_ref()
-            .expect("Driver not initialized")
-            .lock()
-            .await
-            .execute(Command::Save(1))
-            .map_err(|e| anyhow::anyhow!("Failed to save: {:?}", e))?;
+        self.save_to_memory().await?;
 
         // Wait a bit for the device to process the command
         sleep(Duration::from_millis(100)).await;
@@ -278,13 +286,7 @@ impl PowerSupplyDriver for Kd3005pDriver {
 
         // Save the settings to the device's memory
         // Important to avoid bad config after power cycle
-        self.driver
-            .as_ref()
-            .expect("Driver not initialized")
-            .lock()
-            .await
-            .execute(Command::Save(1))
-            .map_err(|e| anyhow::anyhow!("Failed to save: {:?}", e))?;
+        self.save_to_memory().await?;
 
         // Wait a bit for the device to process the command
         sleep(Duration::from_millis(100)).await;
@@ -308,4 +310,34 @@ impl PowerSupplyDriver for Kd3005pDriver {
     fn security_max_current(&self) -> Option<f32> {
         self.config.security_max_current
     }
+
+    //--------------------------------------------------------------------------
+
+    /// Measure the output voltage
+    async fn measure_voltage(&mut self) -> anyhow::Result<String> {
+        let status = self
+            .driver
+            .as_ref()
+            .expect("Driver not initialized")
+            .lock()
+            .await
+            .status()
+            .map_err(|e| anyhow::anyhow!("Failed to read status: {:?}", e))?;
+        Ok(format!("{:.2}", status.voltage))
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Measure the output current
+    async fn measure_current(&mut self) -> anyhow::Result<String> {
+        let status = self
+            .driver
+            .as_ref()
+            .expect("Driver not initialized")
+            .lock()
+            .await
+            .status()
+            .map_err(|e| anyhow::anyhow!("Failed to read status: {:?}", e))?;
+        Ok(format!("{:.3}", status.current))
+    }
 }
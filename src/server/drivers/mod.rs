@@ -54,6 +54,13 @@ pub trait PowerSupplyDriver: Send + Sync {
     // Security limits
     fn security_min_current(&self) -> Option<f32>;
     fn security_max_current(&self) -> Option<f32>;
+
+    // --- Measurements ---
+
+    /// Measure the actual output voltage
+    async fn measure_voltage(&mut self) -> anyhow::Result<String>;
+    /// Measure the actual output current
+    async fn measure_current(&mut self) -> anyhow::Result<String>;
 }
 
 #[derive(ThisError, Debug, Clone)]
@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use once_cell::sync::Lazy;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::Event;
+use tracing::Level;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Layer;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Maximum number of events kept in the log buffer
+const LOG_BUFFER_CAPACITY: usize = 2000;
+
+/// Filter applied when `RUST_LOG` is not set, the broker internals are too verbose
+const DEFAULT_FILTER: &str = "debug,rumqttd=warn,rumqttc=warn";
+
+/// Buffer shared by the tracing layer and its readers
+static LOG_BUFFER: Lazy<LogBuffer> = Lazy::new(|| LogBuffer::new(LOG_BUFFER_CAPACITY));
+
+/// Event captured from tracing
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    /// Time at which the event was recorded
+    pub time: SystemTime,
+    /// Level of the event
+    pub level: Level,
+    /// Module that emitted the event
+    pub target: String,
+    /// Runner concerned by the event, from a `runner` field or a `[name]` message prefix
+    pub runner: Option<String>,
+    /// Message of the event, followed by its other fields
+    pub message: String,
+}
+
+impl LogEntry {
+    // ------------------------------------------------------------------------------
+
+    /// Check whether the event concerns the given runner
+    ///
+    /// Messages of the runners service name the runner between quotes.
+    pub fn concerns(&self, runner: &str) -> bool {
+        self.runner.as_deref() == Some(runner) || self.message.contains(&format!("'{}'", runner))
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Time of the event as `HH:MM:SS` (UTC)
+    pub fn time_of_day(&self) -> String {
+        let seconds = self
+            .time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+            % 86400;
+        format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60
+        )
+    }
+}
+
+/// Ring buffer of the last tracing events
+pub struct LogBuffer {
+    /// Events, oldest first
+    entries: Mutex<VecDeque<LogEntry>>,
+    /// Maximum number of events kept
+    capacity: usize,
+}
+
+impl LogBuffer {
+    // ------------------------------------------------------------------------------
+
+    /// Create an empty buffer
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Get the buffer filled by the tracing layer
+    pub fn global() -> &'static LogBuffer {
+        &LOG_BUFFER
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Add an event, dropping the oldest one when the buffer is full
+    fn push(&self, entry: LogEntry) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Get the most recent events accepted by the filter, newest first
+    ///
+    /// The `skip` newest matching events are ignored, at most `count` events are returned.
+    pub fn recent<F>(&self, skip: usize, count: usize, filter: F) -> Vec<LogEntry>
+    where
+        F: Fn(&LogEntry) -> bool,
+    {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .rev()
+            .filter(|entry| filter(entry))
+            .skip(skip)
+            .take(count)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Collect the fields of an event
+#[derive(Default)]
+struct EntryVisitor {
+    message: String,
+    fields: String,
+    runner: Option<String>,
+}
+
+impl Visit for EntryVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "runner" => self.runner = Some(value.to_string()),
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            "runner" => self.runner = Some(format!("{:?}", value)),
+            // Metadata added by the `log` compatibility layer
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+/// Tracing layer recording the events in the global log buffer
+pub struct LogBufferLayer;
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = EntryVisitor::default();
+        event.record(&mut visitor);
+
+        // Runners prefix their messages with their name between brackets
+        let runner = visitor.runner.or_else(|| {
+            visitor
+                .message
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once(']'))
+                .map(|(name, _)| name.to_string())
+        });
+
+        LOG_BUFFER.push(LogEntry {
+            time: SystemTime::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            runner,
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+/// Install a tracing subscriber recording the events in the log buffer only
+///
+/// Used when the terminal belongs to the TUI, `RUST_LOG` overrides the default filter.
+pub fn init_buffer_tracing() -> anyhow::Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(LogBufferLayer)
+        .try_init()?;
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use once_cell::sync::Lazy;
+use prometheus::core::Collector;
+use prometheus::Gauge;
+use prometheus::GaugeVec;
+use prometheus::HistogramOpts;
+use prometheus::HistogramTimer;
+use prometheus::HistogramVec;
+use prometheus::IntCounterVec;
+use prometheus::IntGauge;
+use prometheus::IntGaugeVec;
+use prometheus::Opts;
+use prometheus::Registry;
+use prometheus::TextEncoder;
+use pza_power_supply_client::payload::Status;
+use tracing::warn;
+
+/// Prefix of the names of the metrics
+const NAMESPACE: &str = "pza_power_supply";
+
+/// Ratio of the current limit above which the output is considered current limited
+const CURRENT_LIMIT_RATIO: f64 = 0.98;
+
+/// Buckets of the command latency histogram, in seconds
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// Names of the runner statuses, one gauge per status
+const STATUS_NAMES: [&str; 3] = ["Initializing", "Running", "Panicking"];
+
+/// Metrics of the server, shared by the runners and the HTTP API
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// Register a metric, names are constants so a failure is a programming error
+fn register<T: Collector + Clone + 'static>(registry: &Registry, metric: T) -> T {
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric registered twice");
+    metric
+}
+
+// ------------------------------------------------------------------------------
+
+/// Options of a metric of the namespace
+fn opts(name: &str, help: &str) -> Opts {
+    Opts::new(name, help).namespace(NAMESPACE)
+}
+
+// ------------------------------------------------------------------------------
+
+/// Parse a value published by a runner, values that are not numbers are ignored
+fn parse_value(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok()
+}
+
+/// Prometheus metrics of the power supplies
+pub struct Metrics {
+    registry: Registry,
+    output_enabled: IntGaugeVec,
+    voltage_setpoint: GaugeVec,
+    current_setpoint: GaugeVec,
+    measured_voltage: GaugeVec,
+    measured_current: GaugeVec,
+    measured_power: GaugeVec,
+    current_limited: IntGaugeVec,
+    estop_latched: IntGaugeVec,
+    status: IntGaugeVec,
+    commands: IntCounterVec,
+    errors: IntCounterVec,
+    restarts: IntCounterVec,
+    protection_trips: IntCounterVec,
+    command_latency: HistogramVec,
+}
+
+impl Metrics {
+    // ------------------------------------------------------------------------------
+
+    /// Create and register the metrics
+    fn new() -> Self {
+        let registry = Registry::new();
+        let gauge = |name: &str, help: &str| {
+            register(
+                &registry,
+                GaugeVec::new(opts(name, help), &["runner"]).expect("valid metric"),
+            )
+        };
+        let int_gauge = |name: &str, help: &str, labels: &[&str]| {
+            register(
+                &registry,
+                IntGaugeVec::new(opts(name, help), labels).expect("valid metric"),
+            )
+        };
+        let counter = |name: &str, help: &str, labels: &[&str]| {
+            register(
+                &registry,
+                IntCounterVec::new(opts(name, help), labels).expect("valid metric"),
+            )
+        };
+
+        Self {
+            output_enabled: int_gauge(
+                "output_enabled",
+                "Output state of the power supply, 1 when enabled",
+                &["runner"],
+            ),
+            voltage_setpoint: gauge(
+                "voltage_setpoint_volts",
+                "Voltage setpoint read back from the device",
+            ),
+            current_setpoint: gauge(
+                "current_setpoint_amperes",
+                "Current limit read back from the device",
+            ),
+            measured_voltage: gauge("measured_voltage_volts", "Last measured output voltage"),
+            measured_current: gauge("measured_current_amperes", "Last measured output current"),
+            measured_power: gauge("measured_power_watts", "Last measured output power"),
+            current_limited: int_gauge(
+                "current_limited",
+                "1 while the measured current reaches the current limit with the output enabled",
+                &["runner"],
+            ),
+            estop_latched: int_gauge(
+                "estop_latched",
+                "1 while the emergency stop of the runner is latched",
+                &["runner"],
+            ),
+            status: int_gauge(
+                "status",
+                "Status of the runner, 1 for the current status",
+                &["runner", "status"],
+            ),
+            commands: counter(
+                "commands_total",
+                "Commands received by the runner",
+                &["runner", "command"],
+            ),
+            errors: counter(
+                "errors_total",
+                "Errors published by the runner, command rejections included",
+                &["runner", "command"],
+            ),
+            restarts: counter(
+                "runner_restarts_total",
+                "Restarts of the runner after a crash",
+                &["runner", "outcome"],
+            ),
+            protection_trips: counter(
+                "protection_trips_total",
+                "Protections triggered on the runner: estop, interlock or current_limit",
+                &["runner", "protection"],
+            ),
+            command_latency: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "command_latency_seconds",
+                        "Time between the reception of a command and its acknowledgement",
+                    )
+                    .namespace(NAMESPACE)
+                    .buckets(LATENCY_BUCKETS.to_vec()),
+                    &["runner", "command"],
+                )
+                .expect("valid metric"),
+            ),
+            registry,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Get the metrics of the server
+    pub fn global() -> &'static Metrics {
+        &METRICS
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Get the metrics of a runner
+    pub fn runner(&self, name: &str) -> RunnerMetrics {
+        RunnerMetrics {
+            name: name.to_string(),
+            output_enabled: self.output_enabled.with_label_values(&[name]),
+            voltage_setpoint: self.voltage_setpoint.with_label_values(&[name]),
+            current_setpoint: self.current_setpoint.with_label_values(&[name]),
+            measured_voltage: self.measured_voltage.with_label_values(&[name]),
+            measured_current: self.measured_current.with_label_values(&[name]),
+            measured_power: self.measured_power.with_label_values(&[name]),
+            current_limited: self.current_limited.with_label_values(&[name]),
+            estop_latched: self.estop_latched.with_label_values(&[name]),
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Record a restart attempt of a crashed runner
+    pub fn record_restart(&self, runner: &str, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.restarts.with_label_values(&[runner, outcome]).inc();
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Encode the metrics in the Prometheus text format
+    pub fn encode(&self) -> anyhow::Result<String> {
+        Ok(TextEncoder::new().encode_to_string(&self.registry.gather())?)
+    }
+}
+
+/// Metrics of one runner, updated by the runner itself
+pub struct RunnerMetrics {
+    name: String,
+    output_enabled: IntGauge,
+    voltage_setpoint: Gauge,
+    current_setpoint: Gauge,
+    measured_voltage: Gauge,
+    measured_current: Gauge,
+    measured_power: Gauge,
+    current_limited: IntGauge,
+    estop_latched: IntGauge,
+}
+
+impl RunnerMetrics {
+    // ------------------------------------------------------------------------------
+
+    /// Record the output state read back from the device
+    pub fn set_output(&self, enabled: bool) {
+        self.output_enabled.set(enabled as i64);
+        if !enabled {
+            self.current_limited.set(0);
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Record the voltage setpoint read back from the device
+    pub fn set_voltage(&self, voltage: &str) {
+        if let Some(value) = parse_value(voltage) {
+            self.voltage_setpoint.set(value);
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Record the current limit read back from the device
+    pub fn set_current(&self, current: &str) {
+        if let Some(value) = parse_value(current) {
+            self.current_setpoint.set(value);
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Record a measurement of the output
+    ///
+    /// The output is current limited when the measured current reaches the
+    /// current limit, each transition to this state counts as a protection trip.
+    pub fn set_measurements(&self, voltage: &str, current: &str) {
+        let (Some(voltage), Some(current)) = (parse_value(voltage), parse_value(current)) else {
+            return;
+        };
+        self.measured_voltage.set(voltage);
+        self.measured_current.set(current);
+        self.measured_power.set(voltage * current);
+
+        let limit = self.current_setpoint.get();
+        let limited =
+            self.output_enabled.get() == 1 && limit > 0.0 && current >= limit * CURRENT_LIMIT_RATIO;
+        if limited && self.current_limited.get() == 0 {
+            warn!(
+                "[{}] Output current limited: {} A for a limit of {} A",
+                self.name, current, limit
+            );
+            self.protection_trip("current_limit");
+        }
+        self.current_limited.set(limited as i64);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Record the emergency stop latch state
+    pub fn set_estop(&self, latched: bool) {
+        self.estop_latched.set(latched as i64);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Record the status of the runner
+    pub fn set_status(&self, status: &Status) {
+        let current = format!("{:?}", status);
+        for name in STATUS_NAMES {
+            METRICS
+                .status
+                .with_label_values(&[self.name.as_str(), name])
+                .set((name == current) as i64);
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Count a command and start measuring its latency
+    ///
+    /// The latency is recorded when the returned timer is dropped or observed,
+    /// once the command is acknowledged or rejected.
+    pub fn command(&self, command: &str) -> HistogramTimer {
+        METRICS
+            .commands
+            .with_label_values(&[self.name.as_str(), command])
+            .inc();
+        METRICS
+            .command_latency
+            .with_label_values(&[self.name.as_str(), command])
+            .start_timer()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Count an error published by the runner
+    pub fn error(&self, command: &str) {
+        METRICS
+            .errors
+            .with_label_values(&[self.name.as_str(), command])
+            .inc();
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Count a protection triggered on the runner
+    pub fn protection_trip(&self, protection: &str) {
+        METRICS
+            .protection_trips
+            .with_label_values(&[self.name.as_str(), protection])
+            .inc();
+    }
+}
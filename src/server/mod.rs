@@ -1,6 +1,8 @@
 pub mod cli;
 pub mod config;
 pub mod drivers;
+pub mod logs;
+pub mod metrics;
 // pub mod factory;
 // pub mod mcp;
 // pub mod mqtt;
@@ -11,11 +13,16 @@ pub mod services;
 
 use clap::Parser;
 use config::ServerConfig;
+use pza_toolkit::config::IPEndpointConfig;
 
 // pub use state::ServerState;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// Period of the readiness probes of the `wait-ready` subcommand
+const WAIT_READY_PERIOD: Duration = Duration::from_millis(500);
+
 /// Run the power supply server
 pub async fn run_server() {
     // Parse CLI arguments first to determine if TUI will be used
@@ -42,6 +49,65 @@ pub async fn run_server() {
                 // Implementation for listing devices goes here
             }
         }
+        cli::Commands::McpStdio { name } => {
+            // Stdout is the MCP transport, traces must go to stderr
+            tracing_subscriber::fmt()
+                .with_writer(std::io::stderr)
+                .with_ansi(false)
+                .init();
+
+            // Load server configuration
+            let server_config = ServerConfig::from_user_file()
+                .unwrap_or_else(|err| panic!("Failed to load server configuration: {}", err));
+
+            if let Err(e) = services::McpService::serve_stdio(server_config, name).await {
+                eprintln!("MCP stdio server failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        cli::Commands::Console { broker } => {
+            // The terminal belongs to the TUI, traces go to the log pane
+            if let Err(e) = logs::init_buffer_tracing() {
+                eprintln!("Failed to initialize tracing: {}", e);
+            }
+
+            let broker = match parse_broker_endpoint(&broker) {
+                Ok(broker) => broker,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            // Key bindings come from the user configuration when there is one
+            let tui_config = ServerConfig::from_user_file()
+                .map(|config| config.tui)
+                .unwrap_or_else(|_| ServerConfig::default().tui);
+
+            if let Err(e) = services::TuiService::run_console(broker, tui_config).await {
+                eprintln!("Console failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        cli::Commands::WaitReady { timeout, address } => {
+            // The address of the HTTP API comes from the configuration when not given
+            let address = address.or_else(|| {
+                ServerConfig::from_user_file()
+                    .ok()
+                    .and_then(|config| config.http)
+                    .map(|http| format!("{}:{}", http.host, http.port))
+            });
+            let Some(address) = address else {
+                eprintln!("No HTTP API address, configure the HTTP API or use --address");
+                std::process::exit(2);
+            };
+
+            if !wait_ready(&address, Duration::from_secs(timeout)).await {
+                eprintln!("Server on {} not ready after {} seconds", address, timeout);
+                std::process::exit(1);
+            }
+            println!("Server on {} is ready", address);
+        }
         cli::Commands::Version => {
             // Display the version from Cargo.toml
             println!("{}", env!("PKG_VERSION"));
@@ -68,3 +134,71 @@ pub async fn run_server() {
         }
     }
 }
+
+/// Probe the readiness of the server until it is ready or the timeout expires
+///
+/// Connection errors are expected while the server starts, so they only end
+/// the wait with the timeout.
+async fn wait_ready(address: &str, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if matches!(services::probe_ready(address).await, Ok(true)) {
+            return true;
+        }
+        if tokio::time::Instant::now() + WAIT_READY_PERIOD > deadline {
+            return false;
+        }
+        tokio::time::sleep(WAIT_READY_PERIOD).await;
+    }
+}
+
+/// Parse a broker address given as `host` or `host:port`
+///
+/// The port defaults to the standard MQTT port when missing, an invalid port is an error.
+fn parse_broker_endpoint(broker: &str) -> anyhow::Result<IPEndpointConfig> {
+    let (addr, port) = match broker.rsplit_once(':') {
+        Some((addr, port)) => {
+            let port = port.parse().map_err(|_| {
+                anyhow::anyhow!("Invalid broker endpoint '{}': bad port '{}'", broker, port)
+            })?;
+            (addr, port)
+        }
+        None => (broker, 1883),
+    };
+    Ok(IPEndpointConfig {
+        addr: Some(addr.to_string()),
+        port: Some(port),
+    })
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn parse_broker_endpoint_defaults_the_port() {
+        let broker = parse_broker_endpoint("192.168.1.10").unwrap();
+        assert_eq!(broker.addr.as_deref(), Some("192.168.1.10"));
+        assert_eq!(broker.port, Some(1883));
+
+        let broker = parse_broker_endpoint("localhost:1884").unwrap();
+        assert_eq!(broker.addr.as_deref(), Some("localhost"));
+        assert_eq!(broker.port, Some(1884));
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn parse_broker_endpoint_rejects_invalid_ports() {
+        let error = parse_broker_endpoint("localhost:18830x").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid broker endpoint 'localhost:18830x': bad port '18830x'"
+        );
+        assert!(parse_broker_endpoint("localhost:").is_err());
+    }
+}
@@ -0,0 +1,410 @@
+mod session;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::Utc;
+use pza_power_supply_client::payload::DataLoggerAction;
+use pza_power_supply_client::payload::DataLoggerPayload;
+use pza_power_supply_client::payload::DataLoggerStatusPayload;
+use pza_power_supply_client::payload::ErrorPayload;
+use pza_power_supply_client::DataLoggerTopics;
+use pza_power_supply_client::PowerSupplyClient;
+use pza_power_supply_client::SERVER_TYPE_NAME;
+use pza_toolkit::rumqtt::client::init_client;
+use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::error;
+use tracing::info;
+use tracing::trace;
+
+use session::Record;
+use session::Session;
+
+use crate::server::config::path::resolve_data_logger_dir;
+use crate::server::config::ServerConfig;
+
+/// Default size of a file after which the session continues in a new file
+const DEFAULT_MAX_FILE_SIZE_KB: u64 = 10 * 1024;
+
+/// Number of records buffered between the runner clients and the writer
+const RECORD_BUFFER: usize = 1024;
+
+/// Delay before polling the broker again after a connection error
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Name of the sessions started without a name
+const DEFAULT_SESSION_NAME: &str = "session";
+
+/// Service recording the measurements, setpoints and state changes of the runners to files
+pub struct DataLoggerService {
+    /// MQTT client used to publish the logger state and errors
+    client: RumqttCustomAsyncClient,
+
+    /// MQTT topics of the data logger
+    topics: DataLoggerTopics,
+
+    /// Directory of the log files
+    directory: PathBuf,
+
+    /// Write a JSON Lines file next to each CSV file
+    jsonl: bool,
+
+    /// Size of a file after which the session continues in a new file, in bytes
+    max_file_size: u64,
+
+    /// Session being recorded
+    session: Option<Session>,
+
+    /// Identifier and files of the last stopped session
+    last_session: Option<(String, Vec<String>)>,
+}
+
+impl DataLoggerService {
+    // --------------------------------------------------------------------------------
+
+    /// Start the data logger service
+    pub async fn start(
+        server_config: ServerConfig,
+    ) -> anyhow::Result<JoinHandle<Result<(), anyhow::Error>>> {
+        let config = server_config
+            .data_logger
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No data logger configuration"))?;
+        let directory = resolve_data_logger_dir(config.directory.as_deref())
+            .ok_or_else(|| anyhow::anyhow!("Failed to determine the data logger directory"))?;
+
+        // Record the selected runners, all of them by default
+        let runner_names = server_config.runner_names();
+        let recorded = match &config.runners {
+            Some(runners) => {
+                if let Some(unknown) = runners.iter().find(|r| !runner_names.contains(r)) {
+                    return Err(anyhow::anyhow!(
+                        "Data logger references unknown runner '{}'",
+                        unknown
+                    ));
+                }
+                runners.clone()
+            }
+            None => runner_names,
+        };
+
+        // One client per recorded runner, their events are merged into one channel
+        let (record_sender, record_receiver) = mpsc::channel(RECORD_BUFFER);
+        for runner in &recorded {
+            let psu_client = PowerSupplyClient::builder()
+                .with_ip(server_config.broker.tcp.clone().unwrap())
+                .with_power_supply_name(runner.clone())
+                .build()?;
+            forward_runner(runner, &psu_client, &record_sender);
+        }
+        info!(
+            "[logger] Recording runners {:?} to {}",
+            recorded,
+            directory.display()
+        );
+
+        let (client, event_loop) = init_client("logger");
+        let custom_client = RumqttCustomAsyncClient::new(
+            client,
+            rumqttc::QoS::AtMostOnce,
+            true,
+            format!("{}/logger", SERVER_TYPE_NAME),
+        );
+
+        let service = DataLoggerService {
+            client: custom_client,
+            topics: DataLoggerTopics::new(),
+            directory,
+            jsonl: config.jsonl.unwrap_or(false),
+            max_file_size: config.max_file_size_kb.unwrap_or(DEFAULT_MAX_FILE_SIZE_KB) * 1024,
+            session: None,
+            last_session: None,
+        };
+
+        Ok(tokio::spawn(Self::task_loop(
+            event_loop,
+            record_receiver,
+            service,
+            config.autostart.unwrap_or(false),
+        )))
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// The main async task loop for the data logger service
+    ///
+    /// Commands and records are handled by the same loop, so the session is
+    /// never written while it is being started or stopped.
+    async fn task_loop(
+        mut event_loop: rumqttc::EventLoop,
+        mut record_receiver: mpsc::Receiver<Record>,
+        mut service: DataLoggerService,
+        autostart: bool,
+    ) -> anyhow::Result<()> {
+        if autostart {
+            let pza_id = pza_power_supply_client::payload::generate_pza_id();
+            if let Err(e) = service.start_session(None, pza_id.clone()).await {
+                error!("[logger] Failed to start the session on startup: {}", e);
+                service.publish_error(e.to_string(), pza_id).await;
+            }
+        } else {
+            service
+                .publish_status(pza_power_supply_client::payload::generate_pza_id())
+                .await;
+        }
+
+        service
+            .client
+            .subscribe_to_all(vec![service.topics.cmd.clone()])
+            .await;
+
+        // Without recorded runner the record channel is closed from the start
+        let mut records_open = true;
+        loop {
+            tokio::select! {
+                event = event_loop.poll() => match event {
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(packet))) => {
+                        trace!("[logger] Received message on topic: {}", packet.topic);
+                        if packet.topic == service.topics.cmd {
+                            service.handle_command(packet.payload).await;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        debug!("[logger] Connection error: {}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                },
+                record = record_receiver.recv(), if records_open => match record {
+                    Some(record) => service.write(record).await,
+                    None => records_open = false,
+                },
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Handle a command, errors are published on the error topic
+    async fn handle_command(&mut self, payload: Bytes) {
+        let cmd = match DataLoggerPayload::from_json_bytes(payload.clone()) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                // Try to extract the pza_id of the invalid command for the error response
+                let pza_id = serde_json::from_slice::<serde_json::Value>(&payload)
+                    .ok()
+                    .and_then(|v| v.get("pza_id").and_then(|id| id.as_str()).map(String::from))
+                    .unwrap_or_else(|| "????".to_string());
+                error!("[logger] Invalid command payload: {}", e);
+                self.publish_error(format!("Invalid logger command payload: {}", e), pza_id)
+                    .await;
+                return;
+            }
+        };
+
+        let result = match cmd.action {
+            DataLoggerAction::Start => self.start_session(cmd.session, cmd.pza_id.clone()).await,
+            DataLoggerAction::Stop => {
+                self.stop_session();
+                self.publish_status(cmd.pza_id.clone()).await;
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            error!("[logger] Command failed: {}", e);
+            self.publish_error(e.to_string(), cmd.pza_id).await;
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Start a new session, the running one is stopped first
+    async fn start_session(&mut self, name: Option<String>, pza_id: String) -> anyhow::Result<()> {
+        self.stop_session();
+
+        let name = name.unwrap_or_else(|| DEFAULT_SESSION_NAME.to_string());
+        let session = Session::open(&self.directory, &name, self.jsonl)?;
+        info!(
+            "[logger] Session '{}' started in {}",
+            session.id(),
+            self.directory.display()
+        );
+        self.session = Some(session);
+        self.publish_status(pza_id).await;
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Stop the running session, if any
+    fn stop_session(&mut self) {
+        if let Some(session) = self.session.take() {
+            info!(
+                "[logger] Session '{}' stopped, files: {:?}",
+                session.id(),
+                session.files()
+            );
+            self.last_session = Some((session.id().to_string(), session.files()));
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Write a record to the running session, records are dropped without session
+    ///
+    /// A write error stops the session, so a full disk does not flood the logs.
+    async fn write(&mut self, record: Record) {
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+
+        match session.write(&record, self.max_file_size) {
+            Ok(false) => {}
+            Ok(true) => {
+                info!("[logger] Session continues in {:?}", session.files().last());
+                self.publish_status(pza_power_supply_client::payload::generate_pza_id())
+                    .await;
+            }
+            Err(e) => {
+                error!("[logger] Failed to write the session, stopping it: {}", e);
+                self.stop_session();
+                let pza_id = pza_power_supply_client::payload::generate_pza_id();
+                self.publish_status(pza_id.clone()).await;
+                self.publish_error(format!("Session stopped on write error: {}", e), pza_id)
+                    .await;
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Publish the state of the logger, as a response to the command with the given pza_id
+    async fn publish_status(&self, pza_id: String) {
+        let payload = match (&self.session, &self.last_session) {
+            (Some(session), _) => DataLoggerStatusPayload::from_state_as_response(true, pza_id)
+                .with_session(session.id().to_string(), session.files()),
+            (None, Some((id, files))) => {
+                DataLoggerStatusPayload::from_state_as_response(false, pza_id)
+                    .with_session(id.clone(), files.clone())
+            }
+            (None, None) => DataLoggerStatusPayload::from_state_as_response(false, pza_id),
+        };
+        match payload.to_json_bytes() {
+            Ok(bytes) => {
+                if let Err(e) = self.client.pubsh(&self.topics.status, bytes).await {
+                    error!("[logger] Failed to publish status: {}", e);
+                }
+            }
+            Err(e) => error!("[logger] Failed to serialize status: {}", e),
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Publish an error message on the error topic of the logger
+    async fn publish_error(&self, message: String, pza_id: String) {
+        let error_payload = ErrorPayload::from_message_as_response(message, pza_id)
+            .to_json_bytes()
+            .expect("Failed to serialize error payload");
+
+        if let Err(e) = self.client.pubsh(&self.topics.error, error_payload).await {
+            error!("[logger] Failed to publish error: {}", e);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Forward the values of a client channel to the writer, as records of the given event
+fn forward<T: Send + Sync + 'static>(
+    runner: String,
+    event: &'static str,
+    mut receiver: broadcast::Receiver<Arc<T>>,
+    sender: mpsc::Sender<Record>,
+    value: fn(&T) -> String,
+) {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(payload) => {
+                    let record = Record {
+                        time: Utc::now(),
+                        runner: runner.clone(),
+                        event,
+                        value: value(&payload),
+                    };
+                    if sender.send(record).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(count)) => {
+                    error!("[logger] [{}] {} {} records dropped", runner, count, event);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// ------------------------------------------------------------------------------
+
+/// Forward the measurements, setpoints and state changes of a runner to the writer
+fn forward_runner(runner: &str, client: &PowerSupplyClient, sender: &mpsc::Sender<Record>) {
+    let runner = runner.to_string();
+    forward(
+        runner.clone(),
+        "state",
+        client.subscribe_state_changes(),
+        sender.clone(),
+        |p| format!("{:?}", p.state).to_uppercase(),
+    );
+    forward(
+        runner.clone(),
+        "voltage",
+        client.subscribe_voltage_changes(),
+        sender.clone(),
+        |p| p.voltage.clone(),
+    );
+    forward(
+        runner.clone(),
+        "current",
+        client.subscribe_current_changes(),
+        sender.clone(),
+        |p| p.current.clone(),
+    );
+    forward(
+        runner.clone(),
+        "measured_voltage",
+        client.subscribe_measured_voltage_changes(),
+        sender.clone(),
+        |p| p.voltage.clone(),
+    );
+    forward(
+        runner.clone(),
+        "measured_current",
+        client.subscribe_measured_current_changes(),
+        sender.clone(),
+        |p| p.current.clone(),
+    );
+    forward(
+        runner.clone(),
+        "estop",
+        client.subscribe_estop_changes(),
+        sender.clone(),
+        |p| p.latched.to_string(),
+    );
+    forward(
+        runner,
+        "status",
+        client.subscribe_status_changes(),
+        sender.clone(),
+        |p| format!("{:?}", p.status),
+    );
+}
@@ -0,0 +1,332 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use serde::Serialize;
+
+/// Header line of the CSV files
+const CSV_HEADER: &str = "timestamp,elapsed_s,runner,event,value\n";
+
+/// Value published by a runner, with its reception time
+pub(super) struct Record {
+    /// Reception time of the value
+    pub(super) time: DateTime<Utc>,
+    /// Name of the runner that published the value
+    pub(super) runner: String,
+    /// Kind of value: `state`, `voltage`, `current`, `measured_voltage`,
+    /// `measured_current`, `estop` or `status`
+    pub(super) event: &'static str,
+    /// Value as published by the runner
+    pub(super) value: String,
+}
+
+/// Line of the JSON Lines files
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    timestamp: String,
+    elapsed_s: f64,
+    runner: &'a str,
+    event: &'a str,
+    value: &'a str,
+}
+
+/// Files of a recording session
+///
+/// The files are named `<session>_<start time>_<part>.csv`, a new part is
+/// opened when the current one exceeds the maximum size.
+pub(super) struct Session {
+    /// Identifier of the session, `<name>_<start time>`, with a counter if already used
+    id: String,
+    /// Directory of the files
+    directory: PathBuf,
+    /// Start time of the session, origin of the elapsed times
+    started: DateTime<Utc>,
+    /// Write a JSON Lines file next to each CSV file
+    jsonl: bool,
+    /// Index of the current part, from 1
+    part: u32,
+    /// CSV file of the current part
+    csv: File,
+    /// JSON Lines file of the current part
+    jsonl_file: Option<File>,
+    /// Bytes written to the CSV file of the current part
+    size: u64,
+    /// Paths of the files written by the session
+    files: Vec<PathBuf>,
+}
+
+impl Session {
+    // --------------------------------------------------------------------------------
+
+    /// Create the first files of a new session in the directory
+    pub(super) fn open(directory: &Path, name: &str, jsonl: bool) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(directory).map_err(|e| {
+            anyhow::anyhow!("Failed to create directory {}: {}", directory.display(), e)
+        })?;
+
+        let started = Utc::now();
+        let id = session_id(directory, name, started);
+        let (csv, jsonl_file, files) = create_part(directory, &id, 1, jsonl)?;
+        Ok(Self {
+            id,
+            directory: directory.to_path_buf(),
+            started,
+            jsonl,
+            part: 1,
+            csv,
+            jsonl_file,
+            size: CSV_HEADER.len() as u64,
+            files,
+        })
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Identifier of the session
+    pub(super) fn id(&self) -> &str {
+        &self.id
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Paths of the files written by the session, the last ones are being written
+    pub(super) fn files(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Write a record, then open a new part if the current one is full
+    ///
+    /// Returns true when a new part has been opened. Each line is written at
+    /// once so the files can be read while the session is recorded.
+    pub(super) fn write(&mut self, record: &Record, max_file_size: u64) -> anyhow::Result<bool> {
+        let timestamp = record.time.to_rfc3339_opts(SecondsFormat::Millis, true);
+        let elapsed_s = (record.time - self.started).num_milliseconds() as f64 / 1000.0;
+
+        let line = format!(
+            "{},{:.3},{},{},{}\n",
+            timestamp,
+            elapsed_s,
+            csv_field(&record.runner),
+            record.event,
+            csv_field(&record.value)
+        );
+        self.csv.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+
+        if let Some(file) = self.jsonl_file.as_mut() {
+            let mut json = serde_json::to_string(&JsonRecord {
+                timestamp,
+                elapsed_s,
+                runner: &record.runner,
+                event: record.event,
+                value: &record.value,
+            })?;
+            json.push('\n');
+            file.write_all(json.as_bytes())?;
+        }
+
+        if self.size >= max_file_size {
+            self.open_next_part()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Open the files of the next part, the previous ones are closed on drop
+    fn open_next_part(&mut self) -> anyhow::Result<()> {
+        let (csv, jsonl_file, files) =
+            create_part(&self.directory, &self.id, self.part + 1, self.jsonl)?;
+        self.part += 1;
+        self.csv = csv;
+        self.jsonl_file = jsonl_file;
+        self.size = CSV_HEADER.len() as u64;
+        self.files.extend(files);
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Identifier of a session, unique in the directory
+///
+/// The start time has a millisecond resolution, a counter is appended if a
+/// session with the same identifier was already written in the directory.
+fn session_id(directory: &Path, name: &str, started: DateTime<Utc>) -> String {
+    let base = format!(
+        "{}_{}",
+        sanitize_name(name),
+        started.format("%Y%m%d-%H%M%S-%3f")
+    );
+    let mut id = base.clone();
+    let mut counter = 1;
+    while directory.join(format!("{}_001.csv", id)).exists() {
+        counter += 1;
+        id = format!("{}-{}", base, counter);
+    }
+    id
+}
+
+// ------------------------------------------------------------------------------
+
+/// Create the files of a part of a session, the CSV file starts with its header
+///
+/// Returns the CSV file, the JSON Lines file if enabled and their paths.
+fn create_part(
+    directory: &Path,
+    id: &str,
+    part: u32,
+    jsonl: bool,
+) -> anyhow::Result<(File, Option<File>, Vec<PathBuf>)> {
+    let base = format!("{}_{:03}", id, part);
+
+    let csv_path = directory.join(format!("{}.csv", base));
+    let mut csv = create_file(&csv_path)?;
+    csv.write_all(CSV_HEADER.as_bytes())?;
+    let mut paths = vec![csv_path];
+
+    let jsonl_file = if jsonl {
+        let jsonl_path = directory.join(format!("{}.jsonl", base));
+        let file = create_file(&jsonl_path)?;
+        paths.push(jsonl_path);
+        Some(file)
+    } else {
+        None
+    };
+    Ok((csv, jsonl_file, paths))
+}
+
+// ------------------------------------------------------------------------------
+
+/// Create a new file, an existing file is never overwritten
+fn create_file(path: &Path) -> anyhow::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))
+}
+
+// ------------------------------------------------------------------------------
+
+/// Keep the characters of a session name that are safe in a file name
+fn sanitize_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        super::DEFAULT_SESSION_NAME.to_string()
+    } else {
+        name
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Quote a CSV field when it contains a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --------------------------------------------------------------------------------
+
+    /// Empty directory of a test, removed first if left by a previous run
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "pza-power-supply-logger-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn csv_field_keeps_plain_values() {
+        assert_eq!(csv_field("5.000"), "5.000");
+        assert_eq!(csv_field("ON"), "ON");
+        assert_eq!(csv_field(""), "");
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn csv_field_quotes_special_characters() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("carriage\rreturn"), "\"carriage\rreturn\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn sanitize_name_replaces_unsafe_characters() {
+        assert_eq!(sanitize_name(" bench/run 1 "), "bench_run_1");
+        assert_eq!(sanitize_name("ramp-up_2"), "ramp-up_2");
+        assert_eq!(sanitize_name("  "), super::super::DEFAULT_SESSION_NAME);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn sessions_started_at_once_get_their_own_files() {
+        let directory = test_directory("same-time");
+
+        let first = Session::open(&directory, "bench", false).unwrap();
+        let second = Session::open(&directory, "bench", false).unwrap();
+        assert_ne!(first.id(), second.id());
+        assert_ne!(first.files(), second.files());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn session_id_appends_a_counter_when_used() {
+        let directory = test_directory("counter");
+        std::fs::create_dir_all(&directory).unwrap();
+        let started = Utc::now();
+
+        let first = session_id(&directory, "bench", started);
+        assert!(first.starts_with("bench_"));
+        std::fs::write(directory.join(format!("{}_001.csv", first)), "").unwrap();
+
+        let second = session_id(&directory, "bench", started);
+        assert_eq!(second, format!("{}-2", first));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
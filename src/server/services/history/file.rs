@@ -0,0 +1,152 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tracing::warn;
+
+use super::store::HistoryStore;
+use super::store::Sample;
+use super::store::SERIES;
+
+/// Header line of the history file
+const HEADER: &str = "time_ms,runner,event,value\n";
+
+/// File keeping the samples of the history store across restarts
+///
+/// Samples are appended as they are received, the file is rewritten from the
+/// store from time to time to drop the samples the store no longer holds.
+pub(super) struct HistoryFile {
+    /// Path of the file
+    path: PathBuf,
+    /// Writer appending to the file
+    writer: BufWriter<File>,
+}
+
+impl HistoryFile {
+    // --------------------------------------------------------------------------------
+
+    /// Load the samples of the file into the store
+    ///
+    /// A missing file is an empty history, invalid lines are skipped.
+    pub(super) fn load(path: &Path, store: &mut HistoryStore) -> anyhow::Result<()> {
+        if path.exists() {
+            let file = File::open(path)
+                .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+            let mut invalid = 0;
+            for line in BufReader::new(file).lines().skip(1) {
+                match parse_line(&line?) {
+                    Some((runner, event, sample)) => store.push(&runner, event, sample),
+                    None => invalid += 1,
+                }
+            }
+            if invalid > 0 {
+                warn!(
+                    "[history] {} invalid lines skipped in {}",
+                    invalid,
+                    path.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Rewrite the file with the samples of the store
+    ///
+    /// The file is written next to its destination and then renamed, so that
+    /// a crash never leaves a truncated history behind.
+    pub(super) fn compact(path: &Path, store: &HistoryStore) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("csv.tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            writer.write_all(HEADER.as_bytes())?;
+            for (runner, event, sample) in store.samples() {
+                write_line(&mut writer, runner, event, sample)?;
+            }
+            writer.flush()?;
+        }
+        std::fs::rename(&tmp_path, path)?;
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Path of the file
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Append a sample, written to the disk on the next flush
+    pub(super) fn append(
+        &mut self,
+        runner: &str,
+        event: &str,
+        sample: &Sample,
+    ) -> anyhow::Result<()> {
+        write_line(&mut self.writer, runner, event, sample)
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Write the appended samples to the disk
+    pub(super) fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Write a sample as a line of the file
+fn write_line<W: Write>(
+    writer: &mut W,
+    runner: &str,
+    event: &str,
+    sample: &Sample,
+) -> anyhow::Result<()> {
+    writeln!(
+        writer,
+        "{},{},{},{}",
+        sample.time_ms, runner, event, sample.value
+    )?;
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------
+
+/// Parse a line of the file, the runner name may contain commas
+fn parse_line(line: &str) -> Option<(String, &'static str, Sample)> {
+    let (time_ms, rest) = line.split_once(',')?;
+    let mut fields = rest.rsplitn(3, ',');
+    let value = fields.next()?.parse().ok()?;
+    let event = fields.next()?;
+    let runner = fields.next()?;
+
+    let event = SERIES.iter().copied().find(|name| *name == event)?;
+    Some((
+        runner.to_string(),
+        event,
+        Sample {
+            time_ms: time_ms.parse().ok()?,
+            value,
+        },
+    ))
+}
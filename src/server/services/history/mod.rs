@@ -0,0 +1,563 @@
+mod file;
+mod store;
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use bytes::Bytes;
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use pza_power_supply_client::payload::ErrorPayload;
+use pza_power_supply_client::payload::HistoryQueryPayload;
+use pza_power_supply_client::payload::HistoryResponsePayload;
+use pza_power_supply_client::payload::PowerState;
+use pza_power_supply_client::HistoryTopics;
+use pza_power_supply_client::PowerSupplyClient;
+use pza_power_supply_client::HISTORY_MAX_PACKET_SIZE;
+use pza_power_supply_client::SERVER_TYPE_NAME;
+use pza_toolkit::config::IPEndpointConfig;
+use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::error;
+use tracing::info;
+use tracing::trace;
+
+use file::HistoryFile;
+use store::HistoryStore;
+use store::Sample;
+use store::SERIES;
+
+use crate::server::config::path::server_history_file;
+use crate::server::config::ServerConfig;
+
+/// Default maximum number of samples of a series, a day at one sample per second
+const DEFAULT_MAX_SAMPLES: usize = 86_400;
+
+/// Default age after which the samples are dropped, a day
+const DEFAULT_RETENTION_S: u64 = 86_400;
+
+/// Default maximum number of points of a series in a response
+const DEFAULT_MAX_POINTS: usize = 500;
+
+/// Maximum number of points of a series in a response, bounds the payload size
+const MAX_POINTS: usize = 1000;
+
+/// Number of samples buffered between the runner clients and the store
+const SAMPLE_BUFFER: usize = 1024;
+
+/// Period of the pruning of the store and of the flush of the history file
+const FLUSH_PERIOD: Duration = Duration::from_secs(5);
+
+/// Period of the rewrite of the history file, dropping the pruned samples
+const COMPACT_PERIOD: Duration = Duration::from_secs(3600);
+
+/// Delay before polling the broker again after a connection error
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Broker address used when the endpoint does not specify one
+const DEFAULT_BROKER_ADDR: &str = "127.0.0.1";
+
+/// Broker port used when the endpoint does not specify one
+const DEFAULT_BROKER_PORT: u16 = 1883;
+
+/// Sample received from a runner client
+struct Record {
+    /// Name of the runner that published the value
+    runner: String,
+    /// Series of the value
+    event: &'static str,
+    /// Value and reception time
+    sample: Sample,
+}
+
+/// Service keeping the measurements and state changes of the runners in memory,
+/// queryable by runner and time range
+pub struct HistoryService {
+    /// MQTT client used to publish the responses and errors
+    client: RumqttCustomAsyncClient,
+
+    /// MQTT topics of the history store
+    topics: HistoryTopics,
+
+    /// Samples of the runners
+    store: HistoryStore,
+
+    /// File keeping the samples across restarts, None if persistence is disabled
+    file: Option<HistoryFile>,
+}
+
+impl HistoryService {
+    // --------------------------------------------------------------------------------
+
+    /// Start the history service
+    pub async fn start(
+        server_config: ServerConfig,
+    ) -> anyhow::Result<JoinHandle<Result<(), anyhow::Error>>> {
+        let config = server_config
+            .history
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No history configuration"))?;
+
+        // Keep the history of the selected runners, all of them by default
+        let runner_names = server_config.runner_names();
+        let recorded = match &config.runners {
+            Some(runners) => {
+                if let Some(unknown) = runners.iter().find(|r| !runner_names.contains(r)) {
+                    return Err(anyhow::anyhow!(
+                        "History references unknown runner '{}'",
+                        unknown
+                    ));
+                }
+                runners.clone()
+            }
+            None => runner_names,
+        };
+
+        let mut store = HistoryStore::new(
+            &recorded,
+            config.max_samples.unwrap_or(DEFAULT_MAX_SAMPLES),
+            config
+                .retention_s
+                .unwrap_or(DEFAULT_RETENTION_S)
+                .saturating_mul(1000),
+        );
+
+        // Reload the samples of the previous runs
+        let file = if config.persist.unwrap_or(false) {
+            let path = server_history_file()
+                .ok_or_else(|| anyhow::anyhow!("Failed to determine the history file path"))?;
+            HistoryFile::load(&path, &mut store)?;
+            store.prune(now_ms());
+            let file = HistoryFile::compact(&path, &store)?;
+            info!("[history] History persisted in {}", path.display());
+            Some(file)
+        } else {
+            None
+        };
+
+        // One client per recorded runner, their samples are merged into one channel
+        let (sample_sender, sample_receiver) = mpsc::channel(SAMPLE_BUFFER);
+        for runner in &recorded {
+            let psu_client = PowerSupplyClient::builder()
+                .with_ip(server_config.broker.tcp.clone().unwrap())
+                .with_power_supply_name(runner.clone())
+                .build()?;
+            forward_runner(runner, &psu_client, &sample_sender);
+        }
+        info!("[history] Keeping the history of runners {:?}", recorded);
+
+        let (client, event_loop) = init_history_client(server_config.broker.tcp.as_ref());
+        // Responses are not retained, they only matter to the querying client
+        let custom_client = RumqttCustomAsyncClient::new(
+            client,
+            rumqttc::QoS::AtMostOnce,
+            false,
+            format!("{}/history", SERVER_TYPE_NAME),
+        );
+
+        let service = HistoryService {
+            client: custom_client,
+            topics: HistoryTopics::new(),
+            store,
+            file,
+        };
+
+        Ok(tokio::spawn(Self::task_loop(
+            event_loop,
+            sample_receiver,
+            service,
+        )))
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// The main async task loop for the history service
+    ///
+    /// Queries and samples are handled by the same loop, so a query always sees
+    /// the samples received before it.
+    async fn task_loop(
+        mut event_loop: rumqttc::EventLoop,
+        mut sample_receiver: mpsc::Receiver<Record>,
+        mut service: HistoryService,
+    ) -> anyhow::Result<()> {
+        service
+            .client
+            .subscribe_to_all(vec![service.topics.cmd.clone()])
+            .await;
+
+        let mut flush_interval = tokio::time::interval(FLUSH_PERIOD);
+        let mut last_compaction = Instant::now();
+
+        // Without recorded runner the sample channel is closed from the start
+        let mut samples_open = true;
+        loop {
+            tokio::select! {
+                event = event_loop.poll() => match event {
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(packet))) => {
+                        trace!("[history] Received message on topic: {}", packet.topic);
+                        if packet.topic == service.topics.cmd {
+                            service.handle_query(packet.payload).await;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        debug!("[history] Connection error: {}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                },
+                record = sample_receiver.recv(), if samples_open => match record {
+                    Some(record) => service.push(record),
+                    None => samples_open = false,
+                },
+                _ = flush_interval.tick() => {
+                    service.store.prune(now_ms());
+                    let compact = last_compaction.elapsed() >= COMPACT_PERIOD;
+                    if compact {
+                        last_compaction = Instant::now();
+                    }
+                    service.save(compact);
+                }
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Add a sample to the store, and to the history file if persistence is enabled
+    fn push(&mut self, record: Record) {
+        if let Some(file) = self.file.as_mut() {
+            if let Err(e) = file.append(&record.runner, record.event, &record.sample) {
+                error!("[history] Failed to write {}: {}", file.path().display(), e);
+            }
+        }
+        self.store.push(&record.runner, record.event, record.sample);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Flush the history file, or rewrite it from the store when `compact` is set
+    ///
+    /// A failed rewrite keeps the current file, appended samples are not lost.
+    fn save(&mut self, compact: bool) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        if let Err(e) = file.flush() {
+            error!("[history] Failed to flush {}: {}", file.path().display(), e);
+        }
+        if compact {
+            match HistoryFile::compact(file.path(), &self.store) {
+                Ok(compacted) => {
+                    debug!("[history] {} compacted", compacted.path().display());
+                    *file = compacted;
+                }
+                Err(e) => error!(
+                    "[history] Failed to compact {}: {}",
+                    file.path().display(),
+                    e
+                ),
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Answer a query, errors are published on the error topic
+    async fn handle_query(&mut self, payload: Bytes) {
+        let query = match HistoryQueryPayload::from_json_bytes(payload.clone()) {
+            Ok(query) => query,
+            Err(e) => {
+                // Try to extract the pza_id of the invalid query for the error response
+                let pza_id = serde_json::from_slice::<serde_json::Value>(&payload)
+                    .ok()
+                    .and_then(|v| v.get("pza_id").and_then(|id| id.as_str()).map(String::from))
+                    .unwrap_or_else(|| "????".to_string());
+                error!("[history] Invalid query payload: {}", e);
+                self.publish_error(format!("Invalid history query payload: {}", e), pza_id)
+                    .await;
+                return;
+            }
+        };
+
+        match self.answer(&query) {
+            Ok(response) => match response.to_json_bytes() {
+                Ok(bytes) => {
+                    if let Err(e) = self.client.pubsh(&self.topics.response, bytes).await {
+                        error!("[history] Failed to publish response: {}", e);
+                    }
+                }
+                Err(e) => error!("[history] Failed to serialize response: {}", e),
+            },
+            Err(e) => {
+                debug!("[history] Query rejected: {}", e);
+                self.publish_error(e.to_string(), query.pza_id).await;
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Build the response to a query
+    ///
+    /// The range defaults to the retention window ending at the reception of the query.
+    fn answer(&self, query: &HistoryQueryPayload) -> anyhow::Result<HistoryResponsePayload> {
+        let events = match &query.events {
+            None => SERIES.to_vec(),
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    SERIES
+                        .iter()
+                        .copied()
+                        .find(|event| *event == name.as_str())
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Unknown history series '{}', available: {:?}",
+                                name,
+                                SERIES
+                            )
+                        })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        };
+
+        let to_ms = query.to_ms.unwrap_or_else(now_ms);
+        let from_ms = query
+            .from_ms
+            .unwrap_or_else(|| to_ms.saturating_sub(self.store.retention_ms()));
+        if from_ms > to_ms {
+            return Err(anyhow::anyhow!(
+                "Invalid history range: from ({}) is after to ({})",
+                from_ms,
+                to_ms
+            ));
+        }
+
+        let max_points = query.max_points.unwrap_or(DEFAULT_MAX_POINTS);
+        if max_points == 0 || max_points > MAX_POINTS {
+            return Err(anyhow::anyhow!(
+                "Invalid max_points {}, expected 1 to {}",
+                max_points,
+                MAX_POINTS
+            ));
+        }
+
+        let series = self
+            .store
+            .query(&query.runner, &events, from_ms, to_ms, max_points)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No history for runner '{}', available: {:?}",
+                    query.runner,
+                    self.store.runners()
+                )
+            })?;
+        Ok(HistoryResponsePayload {
+            pza_id: query.pza_id.clone(),
+            runner: query.runner.clone(),
+            from_ms,
+            to_ms,
+            series,
+        })
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Publish an error message on the error topic of the history store
+    async fn publish_error(&self, message: String, pza_id: String) {
+        let error_payload = ErrorPayload::from_message_as_response(message, pza_id)
+            .to_json_bytes()
+            .expect("Failed to serialize error payload");
+
+        if let Err(e) = self.client.pubsh(&self.topics.error, error_payload).await {
+            error!("[history] Failed to publish error: {}", e);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Current time, in milliseconds since the Unix epoch
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// ------------------------------------------------------------------------------
+
+/// Parse a time of a history query into milliseconds since the Unix epoch
+///
+/// Accepts milliseconds since the Unix epoch, an RFC 3339 date, a date and time
+/// without offset in the local time of the server, `now`, or a duration before
+/// now such as `-30s`, `-5m`, `-2h` or `-1d`.
+pub(super) fn parse_time(text: &str) -> anyhow::Result<u64> {
+    let text = text.trim();
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid time '{}', expected milliseconds since the Unix epoch, an RFC 3339 date, \
+             a local date and time, `now` or a duration before now such as `-5m`",
+            text
+        )
+    };
+
+    if text == "now" {
+        return Ok(now_ms());
+    }
+    if let Some(duration) = text.strip_prefix('-') {
+        let unit_ms = match duration.chars().last() {
+            Some('s') => 1000,
+            Some('m') => 60 * 1000,
+            Some('h') => 3600 * 1000,
+            Some('d') => 86_400 * 1000,
+            _ => return Err(invalid()),
+        };
+        let count: u64 = duration[..duration.len() - 1]
+            .parse()
+            .map_err(|_| invalid())?;
+        return Ok(now_ms().saturating_sub(count.saturating_mul(unit_ms)));
+    }
+    if let Ok(time_ms) = text.parse::<u64>() {
+        return Ok(time_ms);
+    }
+
+    let time = match DateTime::parse_from_rfc3339(text) {
+        Ok(time) => time.timestamp_millis(),
+        Err(_) => ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+            .and_then(|time| time.and_local_timezone(Local).earliest())
+            .ok_or_else(invalid)?
+            .timestamp_millis(),
+    };
+    u64::try_from(time).map_err(|_| invalid())
+}
+
+// ------------------------------------------------------------------------------
+
+/// Format milliseconds since the Unix epoch as an RFC 3339 UTC date
+pub(super) fn format_time(time_ms: u64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(time_ms as i64)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_else(|| time_ms.to_string())
+}
+
+// ------------------------------------------------------------------------------
+
+/// Create the MQTT client of the service, accepting large responses
+fn init_history_client(
+    broker: Option<&IPEndpointConfig>,
+) -> (rumqttc::AsyncClient, rumqttc::EventLoop) {
+    let mut options = rumqttc::MqttOptions::new(
+        format!("history-{}", uuid::Uuid::new_v4()),
+        broker
+            .and_then(|broker| broker.addr.clone())
+            .unwrap_or_else(|| DEFAULT_BROKER_ADDR.to_string()),
+        broker
+            .and_then(|broker| broker.port)
+            .unwrap_or(DEFAULT_BROKER_PORT),
+    );
+    options.set_keep_alive(Duration::from_secs(5));
+    options.set_max_packet_size(HISTORY_MAX_PACKET_SIZE, HISTORY_MAX_PACKET_SIZE);
+    rumqttc::AsyncClient::new(options, 100)
+}
+
+// ------------------------------------------------------------------------------
+
+/// Forward the values of a client channel to the store, as samples of the given series
+///
+/// Values that are not numbers are dropped.
+fn forward<T: Send + Sync + 'static>(
+    runner: String,
+    event: &'static str,
+    mut receiver: broadcast::Receiver<Arc<T>>,
+    sender: mpsc::Sender<Record>,
+    value: fn(&T) -> Option<f64>,
+) {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(payload) => {
+                    let Some(value) = value(&payload) else {
+                        continue;
+                    };
+                    let record = Record {
+                        runner: runner.clone(),
+                        event,
+                        sample: Sample {
+                            time_ms: now_ms(),
+                            value,
+                        },
+                    };
+                    if sender.send(record).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(count)) => {
+                    error!("[history] [{}] {} {} samples dropped", runner, count, event);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// ------------------------------------------------------------------------------
+
+/// Forward the measurements, setpoints and state changes of a runner to the store
+fn forward_runner(runner: &str, client: &PowerSupplyClient, sender: &mpsc::Sender<Record>) {
+    let runner = runner.to_string();
+    forward(
+        runner.clone(),
+        "state",
+        client.subscribe_state_changes(),
+        sender.clone(),
+        |p| Some(if p.state == PowerState::On { 1.0 } else { 0.0 }),
+    );
+    forward(
+        runner.clone(),
+        "voltage",
+        client.subscribe_voltage_changes(),
+        sender.clone(),
+        |p| p.voltage.trim().parse().ok(),
+    );
+    forward(
+        runner.clone(),
+        "current",
+        client.subscribe_current_changes(),
+        sender.clone(),
+        |p| p.current.trim().parse().ok(),
+    );
+    forward(
+        runner.clone(),
+        "measured_voltage",
+        client.subscribe_measured_voltage_changes(),
+        sender.clone(),
+        |p| p.voltage.trim().parse().ok(),
+    );
+    forward(
+        runner.clone(),
+        "measured_current",
+        client.subscribe_measured_current_changes(),
+        sender.clone(),
+        |p| p.current.trim().parse().ok(),
+    );
+    forward(
+        runner,
+        "estop",
+        client.subscribe_estop_changes(),
+        sender.clone(),
+        |p| Some(if p.latched { 1.0 } else { 0.0 }),
+    );
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+use pza_power_supply_client::payload::HistoryPoint;
+use pza_power_supply_client::payload::HistorySeries;
+
+/// Series kept for each runner, in the order of the responses
+pub(super) const SERIES: [&str; 6] = [
+    "state",
+    "voltage",
+    "current",
+    "measured_voltage",
+    "measured_current",
+    "estop",
+];
+
+/// Value of a series at a given time
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Sample {
+    /// Reception time, in milliseconds since the Unix epoch
+    pub(super) time_ms: u64,
+    /// Value, states are stored as 1 (ON, latched) or 0 (OFF, released)
+    pub(super) value: f64,
+}
+
+/// Ring buffers of the samples of the runners
+///
+/// Each series keeps at most `max_samples` samples, and none older than the
+/// retention, so the memory used by the store is bounded.
+pub(super) struct HistoryStore {
+    /// Samples in chronological order, keyed by runner then by series
+    series: HashMap<String, HashMap<&'static str, VecDeque<Sample>>>,
+    /// Maximum number of samples of a series
+    max_samples: usize,
+    /// Age after which the samples are dropped, in milliseconds
+    retention_ms: u64,
+}
+
+impl HistoryStore {
+    // --------------------------------------------------------------------------------
+
+    /// Create an empty store for the given runners
+    pub(super) fn new(runners: &[String], max_samples: usize, retention_ms: u64) -> Self {
+        let series = runners
+            .iter()
+            .map(|runner| {
+                let series = SERIES
+                    .iter()
+                    .map(|event| (*event, VecDeque::new()))
+                    .collect();
+                (runner.clone(), series)
+            })
+            .collect();
+        Self {
+            series,
+            max_samples,
+            retention_ms,
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Names of the runners of the store, sorted
+    pub(super) fn runners(&self) -> Vec<&String> {
+        let mut runners: Vec<&String> = self.series.keys().collect();
+        runners.sort();
+        runners
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Age after which the samples are dropped, in milliseconds
+    pub(super) fn retention_ms(&self) -> u64 {
+        self.retention_ms
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Add a sample to a series, samples of unknown runners or series are ignored
+    ///
+    /// Samples are normally received in order, a late one is inserted at its place.
+    pub(super) fn push(&mut self, runner: &str, event: &str, sample: Sample) {
+        let Some(samples) = self
+            .series
+            .get_mut(runner)
+            .and_then(|series| series.get_mut(event))
+        else {
+            return;
+        };
+
+        match samples.back() {
+            Some(last) if last.time_ms > sample.time_ms => {
+                let index = samples.partition_point(|s| s.time_ms <= sample.time_ms);
+                samples.insert(index, sample);
+            }
+            _ => samples.push_back(sample),
+        }
+        while samples.len() > self.max_samples {
+            samples.pop_front();
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Drop the samples older than the retention
+    pub(super) fn prune(&mut self, now_ms: u64) {
+        let oldest_ms = now_ms.saturating_sub(self.retention_ms);
+        for samples in self
+            .series
+            .values_mut()
+            .flat_map(|series| series.values_mut())
+        {
+            while samples.front().is_some_and(|s| s.time_ms < oldest_ms) {
+                samples.pop_front();
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Every sample of the store, with its runner and series
+    pub(super) fn samples(&self) -> impl Iterator<Item = (&str, &'static str, &Sample)> {
+        self.series.iter().flat_map(|(runner, series)| {
+            series.iter().flat_map(move |(event, samples)| {
+                samples
+                    .iter()
+                    .map(move |sample| (runner.as_str(), *event, sample))
+            })
+        })
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Series of a runner between two times (inclusive), downsampled to `max_points`
+    ///
+    /// The value in effect at `from_ms`, recorded before the range, is reported
+    /// as a first point at `from_ms`: setpoints and states are only recorded
+    /// when they change. Returns None for an unknown runner.
+    pub(super) fn query(
+        &self,
+        runner: &str,
+        events: &[&'static str],
+        from_ms: u64,
+        to_ms: u64,
+        max_points: usize,
+    ) -> Option<Vec<HistorySeries>> {
+        let series = self.series.get(runner)?;
+        Some(
+            events
+                .iter()
+                .map(|event| {
+                    let samples = series.get(event).map(|s| range(s, from_ms, to_ms));
+                    let (count, samples) = samples.unwrap_or_default();
+                    HistorySeries {
+                        event: event.to_string(),
+                        samples: count,
+                        points: downsample(&samples, from_ms, to_ms, max_points),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Samples between two times (inclusive), preceded by the value in effect at `from_ms`
+///
+/// Returns the number of samples recorded in the range and the samples.
+fn range(samples: &VecDeque<Sample>, from_ms: u64, to_ms: u64) -> (usize, Vec<Sample>) {
+    let start = samples.partition_point(|s| s.time_ms < from_ms);
+    let end = samples.partition_point(|s| s.time_ms <= to_ms).max(start);
+
+    let mut selected = Vec::with_capacity(end - start + 1);
+    let starts_later = samples.get(start).is_none_or(|s| s.time_ms > from_ms);
+    if start > 0 && starts_later && from_ms <= to_ms {
+        selected.push(Sample {
+            time_ms: from_ms,
+            value: samples[start - 1].value,
+        });
+    }
+    selected.extend(samples.range(start..end).copied());
+    (end - start, selected)
+}
+
+// ------------------------------------------------------------------------------
+
+/// Aggregate the samples into at most `max_points` buckets of equal duration
+///
+/// Each point holds the mean, minimum and maximum of the samples of its bucket,
+/// empty buckets have no point. Samples are kept as they are below the limit.
+fn downsample(
+    samples: &[Sample],
+    from_ms: u64,
+    to_ms: u64,
+    max_points: usize,
+) -> Vec<HistoryPoint> {
+    if samples.len() <= max_points {
+        return samples
+            .iter()
+            .map(|s| HistoryPoint {
+                time_ms: s.time_ms,
+                mean: s.value,
+                min: s.value,
+                max: s.value,
+            })
+            .collect();
+    }
+
+    let duration_ms = (to_ms - from_ms).saturating_add(1);
+    let bucket_ms = duration_ms.div_ceil(max_points as u64).max(1);
+
+    let mut points: Vec<HistoryPoint> = Vec::with_capacity(max_points);
+    let mut bucket: Option<(u64, f64, usize)> = None;
+    for sample in samples {
+        let time_ms = from_ms + (sample.time_ms - from_ms) / bucket_ms * bucket_ms;
+        match bucket.as_mut() {
+            Some((bucket_time, sum, count)) if *bucket_time == time_ms => {
+                *sum += sample.value;
+                *count += 1;
+                if let Some(point) = points.last_mut() {
+                    point.min = point.min.min(sample.value);
+                    point.max = point.max.max(sample.value);
+                    point.mean = *sum / *count as f64;
+                }
+            }
+            _ => {
+                bucket = Some((time_ms, sample.value, 1));
+                points.push(HistoryPoint {
+                    time_ms,
+                    mean: sample.value,
+                    min: sample.value,
+                    max: sample.value,
+                });
+            }
+        }
+    }
+    points
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --------------------------------------------------------------------------------
+
+    fn samples(points: &[(u64, f64)]) -> VecDeque<Sample> {
+        points
+            .iter()
+            .map(|(time_ms, value)| Sample {
+                time_ms: *time_ms,
+                value: *value,
+            })
+            .collect()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    fn times(samples: &[Sample]) -> Vec<u64> {
+        samples.iter().map(|s| s.time_ms).collect()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn range_is_inclusive() {
+        let samples = samples(&[(10, 1.0), (20, 2.0), (30, 3.0), (40, 4.0)]);
+
+        let (count, selected) = range(&samples, 20, 30);
+        assert_eq!(count, 2);
+        assert_eq!(times(&selected), vec![20, 30]);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn range_carries_the_previous_value_to_its_start() {
+        let samples = samples(&[(10, 1.0), (20, 2.0), (30, 3.0), (40, 4.0)]);
+
+        let (count, selected) = range(&samples, 15, 30);
+        assert_eq!(count, 2);
+        assert_eq!(times(&selected), vec![15, 20, 30]);
+        assert_eq!(selected[0].value, 1.0);
+
+        // After the last sample, only the value in effect is reported
+        let (count, selected) = range(&samples, 50, 60);
+        assert_eq!(count, 0);
+        assert_eq!(times(&selected), vec![50]);
+        assert_eq!(selected[0].value, 4.0);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn range_is_empty_before_the_first_sample() {
+        let samples = samples(&[(10, 1.0), (20, 2.0)]);
+
+        let (count, selected) = range(&samples, 0, 5);
+        assert_eq!(count, 0);
+        assert!(selected.is_empty());
+
+        // An inverted range selects nothing
+        let (count, selected) = range(&samples, 20, 10);
+        assert_eq!(count, 0);
+        assert!(selected.is_empty());
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn downsample_keeps_the_samples_below_the_limit() {
+        let samples: Vec<Sample> = samples(&[(0, 1.0), (5, 2.0), (9, 3.0)]).into();
+
+        let points = downsample(&samples, 0, 9, 3);
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].time_ms, 5);
+        assert_eq!(points[1].mean, 2.0);
+        assert_eq!(points[1].min, 2.0);
+        assert_eq!(points[1].max, 2.0);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn downsample_aggregates_buckets() {
+        let samples: Vec<Sample> = (0..3000)
+            .map(|i| Sample {
+                time_ms: i,
+                value: (i % 10) as f64,
+            })
+            .collect();
+
+        let points = downsample(&samples, 0, 2999, 10);
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[0].time_ms, 0);
+        assert_eq!(points[0].mean, 4.5);
+        assert_eq!(points[0].min, 0.0);
+        assert_eq!(points[0].max, 9.0);
+        assert_eq!(points[9].time_ms, 2700);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn push_keeps_the_samples_ordered_and_bounded() {
+        let mut store = HistoryStore::new(&["psu".to_string()], 3, 60_000);
+        for time_ms in [10, 30, 20, 40] {
+            store.push(
+                "psu",
+                "voltage",
+                Sample {
+                    time_ms,
+                    value: 1.0,
+                },
+            );
+        }
+        // Unknown runners and series are ignored
+        store.push(
+            "other",
+            "voltage",
+            Sample {
+                time_ms: 50,
+                value: 1.0,
+            },
+        );
+        store.push(
+            "psu",
+            "unknown",
+            Sample {
+                time_ms: 50,
+                value: 1.0,
+            },
+        );
+
+        let mut stored: Vec<u64> = store.samples().map(|(_, _, s)| s.time_ms).collect();
+        stored.sort();
+        assert_eq!(stored, vec![20, 30, 40]);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn prune_drops_the_samples_older_than_the_retention() {
+        let mut store = HistoryStore::new(&["psu".to_string()], 100, 1000);
+        for time_ms in [0, 500, 1500] {
+            store.push(
+                "psu",
+                "current",
+                Sample {
+                    time_ms,
+                    value: 1.0,
+                },
+            );
+        }
+
+        store.prune(2000);
+        let stored: Vec<u64> = store.samples().map(|(_, _, s)| s.time_ms).collect();
+        assert_eq!(stored, vec![1500]);
+        assert!(store.query("unknown", &["current"], 0, 2000, 10).is_none());
+    }
+}
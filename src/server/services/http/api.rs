@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::rejection::JsonRejection;
+use axum::extract::Path;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::header;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::Json;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::info;
+use tracing::warn;
+
+use pza_power_supply_client::client::ClientError;
+use pza_power_supply_client::payload::PowerState;
+use pza_power_supply_client::HistoryClient;
+use pza_power_supply_client::PowerSupplyClient;
+
+use crate::server::config::McpScope;
+use crate::server::config::PowerSupplyConfig;
+use crate::server::metrics::Metrics;
+use crate::server::services::mcp::auth::McpAuth;
+
+/// Age after which the client cache is refreshed before answering a read request
+const CACHE_MAX_AGE: Duration = Duration::from_secs(3);
+
+/// Maximum time to wait for the cache to be refreshed
+const CACHE_REFRESH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// State shared by the handlers of the API
+#[derive(Clone)]
+pub(super) struct ApiState {
+    /// Clients of the power supplies, keyed by runner name
+    pub(super) clients: Arc<HashMap<String, PowerSupplyClient>>,
+    /// Security limits of the power supplies, keyed by runner name
+    pub(super) limits: Arc<HashMap<String, SecurityLimits>>,
+    /// Time to wait for the runner to acknowledge a command
+    pub(super) ack_timeout: Duration,
+    /// Ready signal of the server
+    pub(super) ready: watch::Receiver<bool>,
+    /// Client of the history service, None if the history is disabled
+    pub(super) history: Option<HistoryClient>,
+}
+
+impl ApiState {
+    // ------------------------------------------------------------------------------
+
+    /// Get the client of a power supply, an unknown name is a 404 error
+    pub(super) fn client(&self, name: &str) -> Result<PowerSupplyClient, ApiError> {
+        self.clients.get(name).cloned().ok_or_else(|| ApiError {
+            status: StatusCode::NOT_FOUND,
+            kind: "not_found",
+            message: format!("Unknown power supply '{}'", name),
+        })
+    }
+}
+
+/// Error returned by the API, serialized as `ErrorResponse`
+#[derive(Debug)]
+pub(super) struct ApiError {
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    // ------------------------------------------------------------------------------
+
+    /// Error with the given status, kind and message
+    pub(super) fn new(status: StatusCode, kind: &'static str, message: String) -> Self {
+        Self {
+            status,
+            kind,
+            message,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Invalid parameters of a request
+    pub(super) fn bad_request(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            kind: "invalid_request",
+            message,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Map the error of a command to its HTTP status
+    ///
+    /// Rejections carry the message of the error payload published by the runner.
+    fn from_command(what: &str, error: anyhow::Error) -> Self {
+        let (status, kind) = match error.downcast_ref::<ClientError>() {
+            Some(ClientError::Rejected(_)) => (StatusCode::CONFLICT, "rejected"),
+            Some(ClientError::Timeout(_)) => (StatusCode::GATEWAY_TIMEOUT, "timeout"),
+            _ => (StatusCode::BAD_GATEWAY, "communication"),
+        };
+        Self {
+            status,
+            kind,
+            message: format!("Failed to set {}: {}", what, error),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            kind: "invalid_request",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        warn!("HTTP API error ({}): {}", self.status, self.message);
+        let body = ErrorResponse {
+            error: self.kind.to_string(),
+            message: self.message,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Body of the error responses
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct ErrorResponse {
+    /// Kind of error: `not_found`, `invalid_request`, `unauthorized`, `forbidden`, `rejected`, `timeout` or `communication`
+    pub(super) error: String,
+    /// Description of the error, the message of the runner for rejected commands
+    pub(super) message: String,
+}
+
+/// Setpoint given as a string (`"5.0"`) or as a number (`5.0`)
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub(super) enum SetpointValue {
+    Text(String),
+    Number(f64),
+}
+
+impl SetpointValue {
+    // ------------------------------------------------------------------------------
+
+    /// Setpoint as sent to the runner
+    fn to_command(&self) -> String {
+        match self {
+            SetpointValue::Text(text) => text.trim().to_string(),
+            SetpointValue::Number(number) => number.to_string(),
+        }
+    }
+}
+
+/// Security limits of a power supply, setpoints outside are rejected by the runner
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub(super) struct SecurityLimits {
+    /// Minimum voltage (V)
+    pub(super) min_voltage: Option<f32>,
+    /// Maximum voltage (V)
+    pub(super) max_voltage: Option<f32>,
+    /// Minimum current limit (A)
+    pub(super) min_current: Option<f32>,
+    /// Maximum current limit (A)
+    pub(super) max_current: Option<f32>,
+}
+
+impl From<&PowerSupplyConfig> for SecurityLimits {
+    fn from(config: &PowerSupplyConfig) -> Self {
+        Self {
+            min_voltage: config.security_min_voltage,
+            max_voltage: config.security_max_voltage,
+            min_current: config.security_min_current,
+            max_current: config.security_max_current,
+        }
+    }
+}
+
+/// Full state of a power supply
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct PowerSupplyState {
+    /// Name of the power supply (runner)
+    pub(super) name: String,
+    /// Whether the output is enabled
+    pub(super) output: bool,
+    /// Voltage setpoint read back from the device (V)
+    pub(super) voltage: String,
+    /// Current limit read back from the device (A)
+    pub(super) current: String,
+    /// Last measured output voltage (V)
+    pub(super) measured_voltage: String,
+    /// Last measured output current (A)
+    pub(super) measured_current: String,
+    /// Whether the emergency stop is latched
+    pub(super) estop_latched: bool,
+    /// Status of the runner: `Initializing`, `Running` or `Panicking`
+    pub(super) status: Option<String>,
+    /// Panic message of the runner, if any
+    pub(super) panic_message: Option<String>,
+    /// Security limits of the runner configuration
+    pub(super) limits: SecurityLimits,
+    /// Age of the cached values, in milliseconds
+    pub(super) cache_age_ms: Option<u64>,
+    /// Whether the cached values may be outdated
+    pub(super) stale: bool,
+}
+
+/// Body of the output commands
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct OutputRequest {
+    /// Enable (true) or disable (false) the output
+    pub(super) enable: bool,
+}
+
+/// Answer to the output commands
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct OutputResponse {
+    /// Output state acknowledged by the runner
+    pub(super) output: bool,
+}
+
+/// Body of the voltage commands
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct VoltageRequest {
+    /// Voltage setpoint (V)
+    pub(super) voltage: SetpointValue,
+}
+
+/// Answer to the voltage commands
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct VoltageResponse {
+    /// Voltage read back from the device, rounded to its resolution (V)
+    pub(super) voltage: String,
+}
+
+/// Body of the current commands
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct CurrentRequest {
+    /// Current limit (A)
+    pub(super) current: SetpointValue,
+}
+
+/// Answer to the current commands
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct CurrentResponse {
+    /// Current limit read back from the device, rounded to its resolution (A)
+    pub(super) current: String,
+}
+
+/// Body of the settings commands, every field is optional
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct SettingsRequest {
+    /// Enable (true) or disable (false) the output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) output: Option<bool>,
+    /// Voltage setpoint (V)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) voltage: Option<SetpointValue>,
+    /// Current limit (A)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) current: Option<SetpointValue>,
+}
+
+/// Answer to the settings commands, with the values acknowledged by the runner
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct SettingsResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) output: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) voltage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) current: Option<String>,
+}
+
+/// Answer to the health probe
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct HealthResponse {
+    /// Always `ok`, the HTTP API answers
+    pub(super) status: String,
+}
+
+/// Answer to the readiness probe
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct ReadyResponse {
+    /// Whether the broker, the runners and the MCP server are up
+    pub(super) ready: bool,
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /power-supply`: names of the power supplies
+pub(super) async fn list_power_supplies(State(state): State<ApiState>) -> Json<Vec<String>> {
+    let mut names: Vec<String> = state.clients.keys().cloned().collect();
+    names.sort();
+    Json(names)
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /power-supply/{name}`: full state of a power supply
+///
+/// The client cache is refreshed first if it is stale, a failed refresh is
+/// not an error and the `stale` flag tells the caller the values may be outdated.
+pub(super) async fn get_power_supply(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<PowerSupplyState>, ApiError> {
+    let client = state.client(&name)?;
+    if client.is_cache_stale(CACHE_MAX_AGE).await {
+        if let Err(e) = client.refresh(CACHE_REFRESH_TIMEOUT).await {
+            warn!("[{}] Failed to refresh client cache: {}", name, e);
+        }
+    }
+
+    let cache_age = client.cache_age().await;
+    Ok(Json(PowerSupplyState {
+        output: client.get_oe().await,
+        voltage: client.get_voltage().await,
+        current: client.get_current().await,
+        measured_voltage: client.get_measured_voltage().await,
+        measured_current: client.get_measured_current().await,
+        estop_latched: client.get_estop_latched().await,
+        status: client
+            .get_status()
+            .await
+            .map(|status| format!("{:?}", status)),
+        panic_message: client.get_panic_message().await,
+        limits: state.limits.get(&name).cloned().unwrap_or_default(),
+        cache_age_ms: cache_age.map(|age| age.as_millis() as u64),
+        stale: !matches!(cache_age, Some(age) if age <= CACHE_MAX_AGE),
+        name,
+    }))
+}
+
+// ------------------------------------------------------------------------------
+
+/// `PUT|POST /power-supply/{name}/output`: enable or disable the output
+pub(super) async fn set_output(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    body: Result<Json<OutputRequest>, JsonRejection>,
+) -> Result<Json<OutputResponse>, ApiError> {
+    let client = state.client(&name)?;
+    let Json(request) = body?;
+
+    let output = apply_output(&client, request.enable, state.ack_timeout).await?;
+    info!("[{}] Output set to {} from HTTP API", name, output);
+    Ok(Json(OutputResponse { output }))
+}
+
+// ------------------------------------------------------------------------------
+
+/// `PUT|POST /power-supply/{name}/voltage`: set the voltage
+pub(super) async fn set_voltage(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    body: Result<Json<VoltageRequest>, JsonRejection>,
+) -> Result<Json<VoltageResponse>, ApiError> {
+    let client = state.client(&name)?;
+    let Json(request) = body?;
+
+    let voltage = client
+        .set_voltage_wait_ack(request.voltage.to_command(), state.ack_timeout)
+        .await
+        .map_err(|e| ApiError::from_command("voltage", e))?;
+    info!("[{}] Voltage set to {} from HTTP API", name, voltage);
+    Ok(Json(VoltageResponse { voltage }))
+}
+
+// ------------------------------------------------------------------------------
+
+/// `PUT|POST /power-supply/{name}/current`: set the current limit
+pub(super) async fn set_current(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    body: Result<Json<CurrentRequest>, JsonRejection>,
+) -> Result<Json<CurrentResponse>, ApiError> {
+    let client = state.client(&name)?;
+    let Json(request) = body?;
+
+    let current = client
+        .set_current_wait_ack(request.current.to_command(), state.ack_timeout)
+        .await
+        .map_err(|e| ApiError::from_command("current", e))?;
+    info!("[{}] Current set to {} from HTTP API", name, current);
+    Ok(Json(CurrentResponse { current }))
+}
+
+// ------------------------------------------------------------------------------
+
+/// `PUT|POST /power-supply/{name}/settings`: apply several settings at once
+///
+/// The output is disabled first and enabled last, so that the output is never
+/// ON with a mix of the old and new setpoints. The settings are applied one
+/// after the other and the first failure stops the sequence.
+pub(super) async fn set_settings(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    body: Result<Json<SettingsRequest>, JsonRejection>,
+) -> Result<Json<SettingsResponse>, ApiError> {
+    let client = state.client(&name)?;
+    let Json(request) = body?;
+
+    let mut output = None;
+    if request.output == Some(false) {
+        output = Some(apply_output(&client, false, state.ack_timeout).await?);
+    }
+
+    let mut voltage = None;
+    if let Some(requested) = &request.voltage {
+        voltage = Some(
+            client
+                .set_voltage_wait_ack(requested.to_command(), state.ack_timeout)
+                .await
+                .map_err(|e| ApiError::from_command("voltage", e))?,
+        );
+    }
+
+    let mut current = None;
+    if let Some(requested) = &request.current {
+        current = Some(
+            client
+                .set_current_wait_ack(requested.to_command(), state.ack_timeout)
+                .await
+                .map_err(|e| ApiError::from_command("current", e))?,
+        );
+    }
+
+    if request.output == Some(true) {
+        output = Some(apply_output(&client, true, state.ack_timeout).await?);
+    }
+
+    info!("[{}] Settings applied from HTTP API", name);
+    Ok(Json(SettingsResponse {
+        output,
+        voltage,
+        current,
+    }))
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /healthz`: liveness of the server, answers as long as the HTTP API runs
+pub(super) async fn healthz() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+    })
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /readyz`: readiness of the server, 503 until the ready signal is emitted
+///
+/// The readiness is lost again while the broker is unreachable or a runner is
+/// not running, so the answer may go back to 503 after startup.
+pub(super) async fn readyz(State(state): State<ApiState>) -> (StatusCode, Json<ReadyResponse>) {
+    let ready = *state.ready.borrow();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(ReadyResponse { ready }))
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /metrics`: metrics of the runners in the Prometheus text format
+pub(super) async fn metrics() -> Response {
+    match Metrics::global().encode() {
+        Ok(text) => (
+            [(
+                header::CONTENT_TYPE,
+                "text/plain; version=0.0.4; charset=utf-8",
+            )],
+            text,
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to encode metrics: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Middleware refusing the commands sent without a control token
+///
+/// Applied to the command routes when the authentication of the API is configured.
+pub(super) async fn require_control(
+    State(auth): State<Arc<McpAuth>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match auth.scope_of(request.headers()) {
+        Some(McpScope::Control) => next.run(request).await,
+        Some(McpScope::ReadOnly) => ApiError::new(
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            "Commands changing the power supply require a control token".to_string(),
+        )
+        .into_response(),
+        None => {
+            let mut response = ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+                "Missing or invalid token".to_string(),
+            )
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            response
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Enable or disable the output and wait for the acknowledgement of the runner
+///
+/// Returns the output state read back from the device, true when ON.
+async fn apply_output(
+    client: &PowerSupplyClient,
+    enable: bool,
+    ack_timeout: Duration,
+) -> Result<bool, ApiError> {
+    let result = if enable {
+        client.enable_output_wait_ack(ack_timeout).await
+    } else {
+        client.disable_output_wait_ack(ack_timeout).await
+    };
+    result
+        .map(|state| state == PowerState::On)
+        .map_err(|e| ApiError::from_command("output", e))
+}
@@ -0,0 +1,308 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use axum::extract::ws::Message;
+use axum::extract::ws::WebSocket;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::response::sse::Event;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
+use axum::response::Response;
+use futures::Stream;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::debug;
+use tracing::info;
+
+use pza_power_supply_client::PowerSupplyClient;
+use pza_power_supply_client::ALL_RUNNERS_NAME;
+
+use super::api::ApiError;
+use super::api::ApiState;
+
+/// Number of events buffered for a slow stream client, older events of the
+/// client channels are dropped beyond
+const STREAM_BUFFER: usize = 256;
+
+/// Kinds of events streamed, named after the topic they come from
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EventKind {
+    State,
+    Voltage,
+    Current,
+    MeasuredVoltage,
+    MeasuredCurrent,
+    Estop,
+    Status,
+    Error,
+}
+
+impl EventKind {
+    /// Every kind, in the order of the documentation
+    const ALL: [EventKind; 8] = [
+        EventKind::State,
+        EventKind::Voltage,
+        EventKind::Current,
+        EventKind::MeasuredVoltage,
+        EventKind::MeasuredCurrent,
+        EventKind::Estop,
+        EventKind::Status,
+        EventKind::Error,
+    ];
+
+    // ------------------------------------------------------------------------------
+
+    /// Name of the kind, as used in the events and the `types` filter
+    fn name(&self) -> &'static str {
+        match self {
+            EventKind::State => "state",
+            EventKind::Voltage => "voltage",
+            EventKind::Current => "current",
+            EventKind::MeasuredVoltage => "measured_voltage",
+            EventKind::MeasuredCurrent => "measured_current",
+            EventKind::Estop => "estop",
+            EventKind::Status => "status",
+            EventKind::Error => "error",
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Find a kind from its name
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Filters of a stream, given in the query string
+#[derive(Deserialize)]
+pub(super) struct EventsQuery {
+    /// Comma separated kinds of events to stream, all kinds if not set
+    types: Option<String>,
+    /// Comma separated names of the runners to stream, only for `_all`
+    runners: Option<String>,
+}
+
+/// Event sent to the stream clients
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct StreamEvent {
+    /// Name of the runner that published the event
+    runner: String,
+    /// Kind of event: `state`, `voltage`, `current`, `measured_voltage`,
+    /// `measured_current`, `estop`, `status` or `error`
+    #[serde(rename = "type")]
+    kind: String,
+    /// Reception time, in milliseconds since the Unix epoch
+    time_ms: u64,
+    /// Payload published by the runner on the MQTT topic
+    payload: serde_json::Value,
+}
+
+// ------------------------------------------------------------------------------
+
+/// Forward the payloads of a client channel to a stream, until the stream is closed
+fn forward<T: Serialize + Send + Sync + 'static>(
+    runner: String,
+    kind: EventKind,
+    mut receiver: broadcast::Receiver<Arc<T>>,
+    sender: mpsc::Sender<StreamEvent>,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = sender.closed() => break,
+                received = receiver.recv() => match received {
+                    Ok(payload) => {
+                        let event = StreamEvent {
+                            runner: runner.clone(),
+                            kind: kind.name().to_string(),
+                            time_ms: SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map(|d| d.as_millis() as u64)
+                                .unwrap_or_default(),
+                            payload: serde_json::to_value(payload.as_ref())
+                                .unwrap_or(serde_json::Value::Null),
+                        };
+                        if sender.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(count)) => {
+                        debug!("[{}] Stream client too slow, {} {} events dropped", runner, count, kind.name());
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    });
+}
+
+// ------------------------------------------------------------------------------
+
+/// Forward the selected kinds of events of a runner to a stream
+fn forward_runner(
+    runner: &str,
+    client: &PowerSupplyClient,
+    kinds: &[EventKind],
+    sender: &mpsc::Sender<StreamEvent>,
+) {
+    for kind in kinds {
+        let runner = runner.to_string();
+        let sender = sender.clone();
+        match kind {
+            EventKind::State => forward(runner, *kind, client.subscribe_state_changes(), sender),
+            EventKind::Voltage => {
+                forward(runner, *kind, client.subscribe_voltage_changes(), sender)
+            }
+            EventKind::Current => {
+                forward(runner, *kind, client.subscribe_current_changes(), sender)
+            }
+            EventKind::MeasuredVoltage => forward(
+                runner,
+                *kind,
+                client.subscribe_measured_voltage_changes(),
+                sender,
+            ),
+            EventKind::MeasuredCurrent => forward(
+                runner,
+                *kind,
+                client.subscribe_measured_current_changes(),
+                sender,
+            ),
+            EventKind::Estop => forward(runner, *kind, client.subscribe_estop_changes(), sender),
+            EventKind::Status => forward(runner, *kind, client.subscribe_status_changes(), sender),
+            EventKind::Error => forward(runner, *kind, client.subscribe_errors(), sender),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Split a comma separated list of the query string
+pub(super) fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+// ------------------------------------------------------------------------------
+
+/// Open a stream of the events of a runner, or of all the runners with `_all`
+///
+/// Unknown runners are 404 errors, unknown kinds of events 400 errors.
+fn open_stream(
+    state: &ApiState,
+    name: &str,
+    query: &EventsQuery,
+) -> Result<mpsc::Receiver<StreamEvent>, ApiError> {
+    let kinds = match &query.types {
+        Some(types) => split_list(types)
+            .map(|kind| {
+                EventKind::from_name(kind).ok_or_else(|| {
+                    ApiError::bad_request(format!(
+                        "Unknown event type '{}', expected one of: {}",
+                        kind,
+                        EventKind::ALL.map(|kind| kind.name()).join(", ")
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => EventKind::ALL.to_vec(),
+    };
+
+    let runners: Vec<String> = if name == ALL_RUNNERS_NAME {
+        match &query.runners {
+            Some(runners) => split_list(runners).map(str::to_string).collect(),
+            None => state.clients.keys().cloned().collect(),
+        }
+    } else {
+        vec![name.to_string()]
+    };
+
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+    for runner in &runners {
+        let client = state.client(runner)?;
+        forward_runner(runner, &client, &kinds, &sender);
+    }
+    info!(
+        "Event stream opened on '{}' for runners {:?}, types {:?}",
+        name,
+        runners,
+        kinds.iter().map(|kind| kind.name()).collect::<Vec<_>>()
+    );
+    Ok(receiver)
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /power-supply/{name}/events`: stream the events as Server-Sent Events
+///
+/// The SSE event name is the kind of event, its data the JSON `StreamEvent`.
+pub(super) async fn sse_events(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let receiver = open_stream(&state, &name, &query)?;
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        let sse_event = Event::default()
+            .event(event.kind.clone())
+            .json_data(&event)
+            .unwrap_or_else(|e| Event::default().comment(format!("serialization error: {}", e)));
+        Some((Ok(sse_event), receiver))
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /power-supply/{name}/ws`: stream the events over a WebSocket
+///
+/// Each event is sent as a JSON `StreamEvent` text message, the messages of
+/// the client are ignored.
+pub(super) async fn ws_events(
+    ws: WebSocketUpgrade,
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Response, ApiError> {
+    let receiver = open_stream(&state, &name, &query)?;
+    Ok(ws.on_upgrade(move |socket| stream_to_socket(socket, receiver)))
+}
+
+// ------------------------------------------------------------------------------
+
+/// Send the events to the WebSocket until one of both sides is closed
+async fn stream_to_socket(mut socket: WebSocket, mut receiver: mpsc::Receiver<StreamEvent>) {
+    loop {
+        tokio::select! {
+            event = receiver.recv() => {
+                let Some(event) = event else {
+                    break;
+                };
+                let Ok(text) = serde_json::to_string(&event) else {
+                    continue;
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    debug!("WebSocket event stream closed");
+}
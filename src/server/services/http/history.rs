@@ -0,0 +1,145 @@
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use pza_power_supply_client::client::ClientError;
+use pza_power_supply_client::payload::HistoryQueryPayload;
+use pza_power_supply_client::payload::HistoryResponsePayload;
+
+use super::api::ApiError;
+use super::api::ApiState;
+use super::events::split_list;
+use crate::server::services::history::parse_time;
+
+/// Time range and series of a history query, given in the query string
+#[derive(Deserialize)]
+pub(super) struct HistoryQuery {
+    /// Start of the range, retention window before `to` if not set
+    from: Option<String>,
+    /// End of the range, now if not set
+    to: Option<String>,
+    /// Comma separated series to return, all the series if not set
+    events: Option<String>,
+    /// Maximum number of points of each series
+    max_points: Option<usize>,
+}
+
+/// Point of a series, aggregating the samples of a time bucket
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct HistoryPointResponse {
+    /// Start of the bucket, in milliseconds since the Unix epoch
+    time_ms: u64,
+    /// Mean of the samples of the bucket
+    mean: f64,
+    /// Minimum of the samples of the bucket
+    min: f64,
+    /// Maximum of the samples of the bucket
+    max: f64,
+}
+
+/// Series of values of a power supply
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct HistorySeriesResponse {
+    /// Kind of value: `state` (1 ON, 0 OFF), `voltage`, `current`,
+    /// `measured_voltage`, `measured_current` or `estop` (1 latched)
+    event: String,
+    /// Number of samples in the range, before downsampling
+    samples: usize,
+    /// Points of the series, in chronological order
+    points: Vec<HistoryPointResponse>,
+}
+
+/// History of a power supply over a time range
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct HistoryResponse {
+    /// Name of the power supply
+    name: String,
+    /// Start of the range, in milliseconds since the Unix epoch
+    from_ms: u64,
+    /// End of the range, in milliseconds since the Unix epoch
+    to_ms: u64,
+    /// Series of the power supply
+    series: Vec<HistorySeriesResponse>,
+}
+
+impl From<HistoryResponsePayload> for HistoryResponse {
+    fn from(payload: HistoryResponsePayload) -> Self {
+        Self {
+            name: payload.runner,
+            from_ms: payload.from_ms,
+            to_ms: payload.to_ms,
+            series: payload
+                .series
+                .into_iter()
+                .map(|series| HistorySeriesResponse {
+                    event: series.event,
+                    samples: series.samples,
+                    points: series
+                        .points
+                        .into_iter()
+                        .map(|point| HistoryPointResponse {
+                            time_ms: point.time_ms,
+                            mean: point.mean,
+                            min: point.min,
+                            max: point.max,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /power-supply/{name}/history`: series of a power supply over a time range
+///
+/// Times are milliseconds since the Unix epoch, RFC 3339 dates, local dates
+/// and times, `now` or durations before now (`-5m`). The query is answered by
+/// the history service over MQTT, its rejections are 400 errors.
+pub(super) async fn get_history(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryResponse>, ApiError> {
+    state.client(&name)?;
+    let history = state.history.as_ref().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            "History is not enabled on this server".to_string(),
+        )
+    })?;
+
+    let parse = |time: &Option<String>| {
+        time.as_deref()
+            .map(parse_time)
+            .transpose()
+            .map_err(|e| ApiError::bad_request(e.to_string()))
+    };
+    let mut payload =
+        HistoryQueryPayload::from_runner(name).with_range(parse(&query.from)?, parse(&query.to)?);
+    if let Some(events) = &query.events {
+        payload = payload.with_events(split_list(events).map(String::from).collect());
+    }
+    if let Some(max_points) = query.max_points {
+        payload = payload.with_max_points(max_points);
+    }
+
+    let response = history
+        .query_wait_ack(payload, state.ack_timeout)
+        .await
+        .map_err(|e| {
+            let (status, kind) = match e.downcast_ref::<ClientError>() {
+                Some(ClientError::Rejected(_)) => (StatusCode::BAD_REQUEST, "invalid_request"),
+                Some(ClientError::Timeout(_)) => (StatusCode::GATEWAY_TIMEOUT, "timeout"),
+                _ => (StatusCode::BAD_GATEWAY, "communication"),
+            };
+            ApiError::new(status, kind, format!("Failed to query history: {}", e))
+        })?;
+    Ok(Json(response.into()))
+}
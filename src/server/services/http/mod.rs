@@ -0,0 +1,225 @@
+mod api;
+mod events;
+mod history;
+mod openapi;
+mod panel;
+mod probe;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::routing::get;
+use axum::routing::put;
+use axum::Json;
+use axum::Router;
+use pza_power_supply_client::HistoryClient;
+use pza_power_supply_client::PowerSupplyClient;
+use pza_power_supply_client::SERVER_TYPE_NAME;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+use api::ApiState;
+use api::SecurityLimits;
+use panel::PanelConfig;
+
+use crate::server::config::ServerConfig;
+use crate::server::services::mcp::auth::McpAuth;
+
+pub use probe::probe_ready;
+
+/// Maximum time given to the HTTP server to close its connections on shutdown
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Default time to wait for the runner to acknowledge a command
+const DEFAULT_ACK_TIMEOUT_MS: u64 = 5000;
+
+pub struct HttpService {
+    /// Sender of the shutdown signal of the HTTP server
+    shutdown_sender: watch::Sender<bool>,
+    /// Receiver of the stop state of the HTTP server, true once its connections are closed
+    stopped_receiver: watch::Receiver<bool>,
+}
+
+impl HttpService {
+    /// Starts the HTTP API, on top of one client per power supply
+    ///
+    /// `ready` is the ready signal of the server, exposed on `/readyz`.
+    ///
+    /// Configuration errors are returned immediately. Bind and serve errors end
+    /// the returned task with an error, so they are reported by the task monitor.
+    pub async fn start(
+        config: ServerConfig,
+        ready: watch::Receiver<bool>,
+    ) -> anyhow::Result<(Self, JoinHandle<Result<(), anyhow::Error>>)> {
+        let http_config = config
+            .http
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No HTTP API configuration"))?;
+        let bind_address = format!("{}:{}", http_config.host, http_config.port);
+        let broker = config
+            .broker
+            .tcp
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No TCP endpoint configured for the MQTT broker"))?;
+
+        // One client per power supply
+        let mut clients = HashMap::new();
+        let mut limits = HashMap::new();
+        for psu_name in config.runner_names() {
+            let client = PowerSupplyClient::builder()
+                .with_ip(broker.clone())
+                .with_power_supply_name(psu_name.clone())
+                .build()?;
+            clients.insert(psu_name.clone(), client);
+
+            if let Some(runner_config) = config
+                .runners
+                .as_ref()
+                .and_then(|runners| runners.get(&psu_name))
+            {
+                limits.insert(psu_name, SecurityLimits::from(runner_config));
+            }
+        }
+
+        let state = ApiState {
+            clients: Arc::new(clients),
+            limits: Arc::new(limits),
+            ack_timeout: Duration::from_millis(
+                http_config.ack_timeout_ms.unwrap_or(DEFAULT_ACK_TIMEOUT_MS),
+            ),
+            ready,
+            history: config
+                .history
+                .as_ref()
+                .map(|_| HistoryClient::connect(Some(broker.clone()))),
+        };
+
+        // Commands accept both PUT and POST, for clients that only send POST
+        let base = format!("/{}", SERVER_TYPE_NAME);
+        let mut commands = Router::new()
+            .route(
+                &format!("{}/{{name}}/output", base),
+                put(api::set_output).post(api::set_output),
+            )
+            .route(
+                &format!("{}/{{name}}/voltage", base),
+                put(api::set_voltage).post(api::set_voltage),
+            )
+            .route(
+                &format!("{}/{{name}}/current", base),
+                put(api::set_current).post(api::set_current),
+            )
+            .route(
+                &format!("{}/{{name}}/settings", base),
+                put(api::set_settings).post(api::set_settings),
+            );
+
+        // Authentication of the commands, the read endpoints and probes stay open
+        match &http_config.auth {
+            Some(auth_config) => {
+                let auth = Arc::new(McpAuth::from_config(auth_config)?);
+                commands = commands.route_layer(axum::middleware::from_fn_with_state(
+                    auth,
+                    api::require_control,
+                ));
+                info!("HTTP API authentication enabled for the commands");
+            }
+            None => warn!("HTTP API authentication disabled, anyone reaching the port can control the power supplies"),
+        }
+
+        let openapi = Arc::new(openapi::document());
+        let panel_config = PanelConfig {
+            power_toggle_key: config.tui.power_toggle_key.clone(),
+        };
+        let app = Router::new()
+            .merge(commands)
+            .route(&base, get(api::list_power_supplies))
+            .route(&format!("{}/{{name}}", base), get(api::get_power_supply))
+            .route(
+                &format!("{}/{{name}}/events", base),
+                get(events::sse_events),
+            )
+            .route(&format!("{}/{{name}}/ws", base), get(events::ws_events))
+            .route(
+                &format!("{}/{{name}}/history", base),
+                get(history::get_history),
+            )
+            .route(
+                "/openapi.json",
+                get(move || std::future::ready(Json(openapi.as_ref().clone()))),
+            )
+            .route("/healthz", get(api::healthz))
+            .route("/readyz", get(api::readyz))
+            .route("/metrics", get(api::metrics))
+            // Web control panel, driven by the API above
+            .route("/", get(panel::index))
+            .route("/assets/{*path}", get(panel::asset))
+            .route(
+                "/panel/config.json",
+                get(move || std::future::ready(Json(panel_config.clone()))),
+            )
+            .with_state(state);
+        info!("HTTP API listening on http://{}{}", bind_address, base);
+        info!("Web control panel available on http://{}/", bind_address);
+
+        // Bind and serve the application until the shutdown signal
+        let (shutdown_sender, mut shutdown_receiver) = watch::channel(false);
+        let (stopped_sender, stopped_receiver) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            let listener = TcpListener::bind(&bind_address).await.map_err(|e| {
+                error!("Failed to bind HTTP API on {}: {}", bind_address, e);
+                anyhow::anyhow!("Failed to bind HTTP API on {}: {}", bind_address, e)
+            })?;
+            info!("HTTP API bound on {}", bind_address);
+
+            let result = axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown_receiver.wait_for(|stop| *stop).await;
+                    info!("HTTP API shutting down");
+                })
+                .await;
+            stopped_sender.send_replace(true);
+            result.map_err(|e| {
+                error!("HTTP API failed: {}", e);
+                anyhow::anyhow!("HTTP API failed: {}", e)
+            })
+        });
+
+        Ok((
+            Self {
+                shutdown_sender,
+                stopped_receiver,
+            },
+            handle,
+        ))
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Stop the HTTP server gracefully
+    ///
+    /// Waits for the pending requests to complete, up to a timeout since a
+    /// command may wait for the acknowledgement of its runner.
+    pub async fn shutdown(&self) {
+        if self.shutdown_sender.send(true).is_err() {
+            // The server task is already stopped
+            return;
+        }
+        // A closed channel means the server task ended, it is stopped too
+        let mut stopped_receiver = self.stopped_receiver.clone();
+        if tokio::time::timeout(
+            SHUTDOWN_TIMEOUT,
+            stopped_receiver.wait_for(|stopped| *stopped),
+        )
+        .await
+        .is_err()
+        {
+            warn!("HTTP API did not stop in time, connections will be aborted");
+        }
+    }
+}
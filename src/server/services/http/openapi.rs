@@ -0,0 +1,317 @@
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use schemars::SchemaGenerator;
+use serde_json::json;
+use serde_json::Value;
+
+use super::api::CurrentRequest;
+use super::api::CurrentResponse;
+use super::api::ErrorResponse;
+use super::api::HealthResponse;
+use super::api::OutputRequest;
+use super::api::OutputResponse;
+use super::api::PowerSupplyState;
+use super::api::ReadyResponse;
+use super::api::SettingsRequest;
+use super::api::SettingsResponse;
+use super::api::VoltageRequest;
+use super::api::VoltageResponse;
+use super::events::StreamEvent;
+use super::history::HistoryResponse;
+
+/// JSON content of a request or response body, referencing the schema of `T`
+fn json_content<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    json!({
+        "content": {
+            "application/json": {
+                "schema": generator.subschema_for::<T>(),
+            }
+        }
+    })
+}
+
+// ------------------------------------------------------------------------------
+
+/// Responses of a command endpoint, the answer `T` or an error
+fn command_responses<T: JsonSchema>(generator: &mut SchemaGenerator, description: &str) -> Value {
+    let mut ok = json_content::<T>(generator);
+    ok["description"] = json!(description);
+    json!({
+        "200": ok,
+        "400": error_response(generator, "Invalid request body"),
+        "401": error_response(generator, "Missing or invalid token, when authentication is configured"),
+        "403": error_response(generator, "Read-only token, when authentication is configured"),
+        "404": error_response(generator, "Unknown power supply"),
+        "409": error_response(generator, "Command rejected by the runner"),
+        "502": error_response(generator, "Broker communication failure"),
+        "504": error_response(generator, "No acknowledgement of the runner in time"),
+    })
+}
+
+// ------------------------------------------------------------------------------
+
+/// Error response with the given description
+fn error_response(generator: &mut SchemaGenerator, description: &str) -> Value {
+    let mut response = json_content::<ErrorResponse>(generator);
+    response["description"] = json!(description);
+    response
+}
+
+// ------------------------------------------------------------------------------
+
+/// PUT and POST operations of a command endpoint, both have the same behavior
+fn command_path<Req: JsonSchema, Res: JsonSchema>(
+    generator: &mut SchemaGenerator,
+    summary: &str,
+    operation_id: &str,
+) -> Value {
+    let operation = json!({
+        "summary": summary,
+        "parameters": [name_parameter()],
+        "requestBody": json_content::<Req>(generator),
+        "responses": command_responses::<Res>(generator, "Values acknowledged by the runner"),
+    });
+    let mut put = operation.clone();
+    put["operationId"] = json!(format!("put_{}", operation_id));
+    let mut post = operation;
+    post["operationId"] = json!(format!("post_{}", operation_id));
+    json!({ "put": put, "post": post })
+}
+
+// ------------------------------------------------------------------------------
+
+/// Path parameter holding the name of the power supply
+fn name_parameter() -> Value {
+    json!({
+        "name": "name",
+        "in": "path",
+        "required": true,
+        "description": "Name of the power supply (runner)",
+        "schema": { "type": "string" },
+    })
+}
+
+// ------------------------------------------------------------------------------
+
+/// Query parameters filtering an event stream
+fn events_parameters() -> Value {
+    json!([
+        name_parameter(),
+        {
+            "name": "types",
+            "in": "query",
+            "required": false,
+            "description": "Comma separated kinds of events: state, voltage, current, measured_voltage, measured_current, estop, status, error. All kinds if not set",
+            "schema": { "type": "string" },
+        },
+        {
+            "name": "runners",
+            "in": "query",
+            "required": false,
+            "description": "Comma separated names of the runners to stream when the name is `_all`. All runners if not set",
+            "schema": { "type": "string" },
+        },
+    ])
+}
+
+// ------------------------------------------------------------------------------
+
+/// Query parameters selecting the range and the series of a history query
+fn history_parameters() -> Value {
+    let time_format = "milliseconds since the Unix epoch, RFC 3339 date, local date and time (`2026-10-18T14:00:00`), `now` or duration before now (`-5m`)";
+    json!([
+        name_parameter(),
+        {
+            "name": "from",
+            "in": "query",
+            "required": false,
+            "description": format!("Start of the range: {}. Retention window before `to` if not set", time_format),
+            "schema": { "type": "string" },
+        },
+        {
+            "name": "to",
+            "in": "query",
+            "required": false,
+            "description": format!("End of the range: {}. Now if not set", time_format),
+            "schema": { "type": "string" },
+        },
+        {
+            "name": "events",
+            "in": "query",
+            "required": false,
+            "description": "Comma separated series: state, voltage, current, measured_voltage, measured_current, estop. All series if not set",
+            "schema": { "type": "string" },
+        },
+        {
+            "name": "max_points",
+            "in": "query",
+            "required": false,
+            "description": "Maximum number of points of each series, from 1 to 1000 (500 by default). Beyond, points aggregate the mean, min and max of time buckets",
+            "schema": { "type": "integer", "minimum": 1, "maximum": 1000 },
+        },
+    ])
+}
+
+// ------------------------------------------------------------------------------
+
+/// Generate the OpenAPI document of the HTTP API
+///
+/// Schemas are generated from the request and response types of the handlers,
+/// so the document follows the code.
+pub(super) fn document() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+
+    let mut state_response = json_content::<PowerSupplyState>(&mut generator);
+    state_response["description"] = json!("State of the power supply");
+
+    let mut history_response = json_content::<HistoryResponse>(&mut generator);
+    history_response["description"] = json!("Series of the power supply over the range");
+
+    let mut health_response = json_content::<HealthResponse>(&mut generator);
+    health_response["description"] = json!("The server is alive");
+    let mut ready_response = json_content::<ReadyResponse>(&mut generator);
+    ready_response["description"] = json!("The server is ready");
+    let mut not_ready_response = json_content::<ReadyResponse>(&mut generator);
+    not_ready_response["description"] =
+        json!("The broker, a runner or the MCP server is not up yet");
+
+    let paths = json!({
+        "/power-supply": {
+            "get": {
+                "summary": "List the power supplies",
+                "operationId": "list_power_supplies",
+                "responses": {
+                    "200": {
+                        "description": "Names of the power supplies",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": { "type": "string" } }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "/power-supply/{name}": {
+            "get": {
+                "summary": "Get the full state of a power supply",
+                "operationId": "get_power_supply",
+                "parameters": [name_parameter()],
+                "responses": {
+                    "200": state_response,
+                    "404": error_response(&mut generator, "Unknown power supply"),
+                }
+            }
+        },
+        "/power-supply/{name}/events": {
+            "get": {
+                "summary": "Stream the events of a power supply, or of all with `_all`, as Server-Sent Events",
+                "operationId": "stream_events_sse",
+                "parameters": events_parameters(),
+                "responses": {
+                    "200": {
+                        "description": "Stream of events, the SSE event name is the kind of event and its data a JSON StreamEvent",
+                        "content": {
+                            "text/event-stream": {
+                                "schema": generator.subschema_for::<StreamEvent>(),
+                            }
+                        }
+                    },
+                    "400": error_response(&mut generator, "Unknown kind of event"),
+                    "404": error_response(&mut generator, "Unknown power supply"),
+                }
+            }
+        },
+        "/power-supply/{name}/ws": {
+            "get": {
+                "summary": "Stream the events of a power supply, or of all with `_all`, over a WebSocket",
+                "operationId": "stream_events_ws",
+                "parameters": events_parameters(),
+                "responses": {
+                    "101": {
+                        "description": "WebSocket opened, each event is sent as a JSON StreamEvent text message",
+                    },
+                    "400": error_response(&mut generator, "Unknown kind of event"),
+                    "404": error_response(&mut generator, "Unknown power supply"),
+                }
+            }
+        },
+        "/power-supply/{name}/history": {
+            "get": {
+                "summary": "Get the measurements and state changes of a power supply over a time range",
+                "operationId": "get_history",
+                "parameters": history_parameters(),
+                "responses": {
+                    "200": history_response,
+                    "400": error_response(&mut generator, "Invalid time, series or max_points"),
+                    "404": error_response(&mut generator, "Unknown power supply or history disabled"),
+                    "502": error_response(&mut generator, "Broker communication failure"),
+                    "504": error_response(&mut generator, "No answer of the history service in time"),
+                }
+            }
+        },
+        "/healthz": {
+            "get": {
+                "summary": "Liveness probe of the server",
+                "operationId": "healthz",
+                "responses": {
+                    "200": health_response,
+                }
+            }
+        },
+        "/readyz": {
+            "get": {
+                "summary": "Readiness probe of the server",
+                "operationId": "readyz",
+                "responses": {
+                    "200": ready_response,
+                    "503": not_ready_response,
+                }
+            }
+        },
+        "/metrics": {
+            "get": {
+                "summary": "Prometheus metrics of the runners",
+                "operationId": "metrics",
+                "responses": {
+                    "200": {
+                        "description": "Metrics in the Prometheus text format",
+                        "content": { "text/plain": { "schema": { "type": "string" } } }
+                    }
+                }
+            }
+        },
+        "/power-supply/{name}/output": command_path::<OutputRequest, OutputResponse>(
+            &mut generator,
+            "Enable or disable the output",
+            "output",
+        ),
+        "/power-supply/{name}/voltage": command_path::<VoltageRequest, VoltageResponse>(
+            &mut generator,
+            "Set the voltage",
+            "voltage",
+        ),
+        "/power-supply/{name}/current": command_path::<CurrentRequest, CurrentResponse>(
+            &mut generator,
+            "Set the current limit",
+            "current",
+        ),
+        "/power-supply/{name}/settings": command_path::<SettingsRequest, SettingsResponse>(
+            &mut generator,
+            "Apply the output state, voltage and current limit at once",
+            "settings",
+        ),
+    });
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Panduza power supply HTTP API",
+            "version": env!("PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": generator.take_definitions(true),
+        },
+    })
+}
@@ -0,0 +1,61 @@
+use axum::extract::Path;
+use axum::http::header;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use include_dir::include_dir;
+use include_dir::Dir;
+use serde::Serialize;
+
+/// Files of the web control panel, embedded in the binary
+static PANEL_FILES: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/src/server/services/http/web");
+
+/// Settings of the web control panel, taken from the server configuration
+#[derive(Clone, Serialize)]
+pub(super) struct PanelConfig {
+    /// Keyboard shortcut to toggle the output of the selected power supply
+    pub(super) power_toggle_key: Option<String>,
+}
+
+// ------------------------------------------------------------------------------
+
+/// Content type of a panel file, from its extension
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Serve an embedded file of the panel
+fn serve_file(path: &str) -> Response {
+    match PANEL_FILES.get_file(path) {
+        Some(file) => (
+            [(header::CONTENT_TYPE, content_type(path))],
+            file.contents(),
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /`: page of the panel
+pub(super) async fn index() -> Response {
+    serve_file("index.html")
+}
+
+// ------------------------------------------------------------------------------
+
+/// `GET /assets/{*path}`: scripts and styles of the panel
+pub(super) async fn asset(Path(path): Path<String>) -> Response {
+    serve_file(&path)
+}
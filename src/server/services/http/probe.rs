@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+/// Maximum time given to one probe, connection included
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Probe the readiness endpoint of a server listening on `address` (`host:port`)
+///
+/// Returns true when `/readyz` answers 200, false when it answers another
+/// status. Connection errors are returned, e.g. while the server starts up.
+/// A plain HTTP/1.0 request is enough, the answer is a small JSON body.
+pub async fn probe_ready(address: &str) -> anyhow::Result<bool> {
+    let probe = async {
+        let mut stream = TcpStream::connect(address).await?;
+        let request = format!(
+            "GET /readyz HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            address
+        );
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        let response = String::from_utf8_lossy(&response);
+
+        // Status line: HTTP/1.x <code> <reason>
+        let code = response
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .ok_or_else(|| anyhow::anyhow!("Invalid HTTP response from {}", address))?;
+        Ok(code == "200")
+    };
+
+    tokio::time::timeout(PROBE_TIMEOUT, probe)
+        .await
+        .map_err(|_| anyhow::anyhow!("No answer from {} in time", address))?
+}
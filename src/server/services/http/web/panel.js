@@ -0,0 +1,324 @@
+// Web control panel of the power supplies
+//
+// Driven by the HTTP API of the server: the state is read on device selection,
+// then kept up to date by the event stream of the selected power supply.
+
+"use strict";
+
+// Slider ranges used when the runner has no security limits
+const DEFAULT_MAX_VOLTAGE = 30;
+const DEFAULT_MAX_CURRENT = 5;
+
+const panel = {
+  // Name of the selected power supply
+  name: null,
+  // Event stream of the selected power supply
+  events: null,
+  // Output state of the selected power supply
+  output: false,
+  // True while a command waits for the acknowledgement of the runner
+  busy: false,
+  // Key toggling the output, from the server configuration
+  toggleKey: null,
+};
+
+const $ = (id) => document.getElementById(id);
+
+// ----------------------------------------------------------------------------
+
+// Key of the API token in the local storage of the browser
+const TOKEN_KEY = "pza-power-supply-token";
+
+// Call the HTTP API, errors are thrown with the message of the server
+//
+// A command refused for lack of token asks for one and is sent again.
+async function api(method, path, body, retry = true) {
+  const options = { method, headers: {} };
+  const token = localStorage.getItem(TOKEN_KEY);
+  if (token) {
+    options.headers["Authorization"] = `Bearer ${token}`;
+  }
+  if (body !== undefined) {
+    options.headers["Content-Type"] = "application/json";
+    options.body = JSON.stringify(body);
+  }
+  const response = await fetch(path, options);
+  const data = await response.json().catch(() => ({}));
+  if (response.status === 401 && retry) {
+    const entered = window.prompt("This command requires an API token:");
+    if (entered) {
+      localStorage.setItem(TOKEN_KEY, entered.trim());
+      return api(method, path, body, false);
+    }
+  }
+  if (!response.ok) {
+    throw new Error(data.message || response.statusText);
+  }
+  return data;
+}
+
+// ----------------------------------------------------------------------------
+
+// Path of the API of the selected power supply
+function psuPath(suffix = "") {
+  return `/power-supply/${encodeURIComponent(panel.name)}${suffix}`;
+}
+
+// ----------------------------------------------------------------------------
+
+// Show a message below the controls, in red for errors
+function showMessage(text, isError = false) {
+  const message = $("message");
+  message.textContent = text;
+  message.classList.toggle("error", isError);
+}
+
+// ----------------------------------------------------------------------------
+
+function setConnection(text, cls) {
+  const badge = $("connection");
+  badge.textContent = text;
+  badge.className = `badge ${cls}`;
+}
+
+// ----------------------------------------------------------------------------
+
+function renderOutput(on) {
+  panel.output = on;
+  const button = $("power");
+  button.textContent = on ? "ON" : "OFF";
+  button.classList.toggle("on", on);
+}
+
+// ----------------------------------------------------------------------------
+
+function renderEstop(latched) {
+  const estop = $("estop");
+  estop.textContent = latched ? "LATCHED" : "OK";
+  estop.classList.toggle("latched", latched);
+}
+
+// ----------------------------------------------------------------------------
+
+function renderStatus(status, panicMessage) {
+  const element = $("status");
+  element.textContent = panicMessage ? `${status}: ${panicMessage}` : status || "-";
+  element.classList.toggle("panicking", status === "Panicking");
+}
+
+// ----------------------------------------------------------------------------
+
+// Show a setpoint read back from the device, unless the user is moving the slider
+function renderSetpoint(kind, value) {
+  $(`${kind}-value`).textContent = value;
+  const slider = $(`${kind}-slider`);
+  if (document.activeElement !== slider) {
+    slider.value = value;
+  }
+  const input = $(`${kind}-input`);
+  if (document.activeElement !== input) {
+    input.value = value;
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+// Apply the security limits of the runner to the controls of a setpoint
+function applyLimits(kind, min, max, defaultMax, unit) {
+  const low = min ?? 0;
+  const high = max ?? defaultMax;
+  for (const id of [`${kind}-slider`, `${kind}-input`]) {
+    $(id).min = low;
+    $(id).max = high;
+  }
+  $(`${kind}-range`).textContent =
+    min != null || max != null ? `Security limits: ${low} to ${high} ${unit}` : "";
+}
+
+// ----------------------------------------------------------------------------
+
+// Enable or disable the controls while a command is pending
+function setBusy(busy) {
+  panel.busy = busy;
+  for (const id of ["power", "voltage-slider", "voltage-input", "current-slider", "current-input"]) {
+    $(id).disabled = busy;
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+// Send a command and show its result, one command at a time
+async function command(suffix, body, describe) {
+  if (panel.busy || !panel.name) {
+    return;
+  }
+  setBusy(true);
+  try {
+    const result = await api("PUT", psuPath(suffix), body);
+    showMessage(describe(result));
+  } catch (error) {
+    showMessage(error.message, true);
+  } finally {
+    setBusy(false);
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+function togglePower() {
+  command("/output", { enable: !panel.output }, (result) => {
+    renderOutput(result.output);
+    return `Output ${result.output ? "enabled" : "disabled"}`;
+  });
+}
+
+// ----------------------------------------------------------------------------
+
+function setVoltage(value) {
+  command("/voltage", { voltage: value }, (result) => {
+    renderSetpoint("voltage", result.voltage);
+    return `Voltage set to ${result.voltage} V`;
+  });
+}
+
+// ----------------------------------------------------------------------------
+
+function setCurrent(value) {
+  command("/current", { current: value }, (result) => {
+    renderSetpoint("current", result.current);
+    return `Current set to ${result.current} A`;
+  });
+}
+
+// ----------------------------------------------------------------------------
+
+// Follow the events of the selected power supply
+function openEvents() {
+  if (panel.events) {
+    panel.events.close();
+  }
+  const events = new EventSource(psuPath("/events"));
+  panel.events = events;
+
+  const on = (type, handler) =>
+    events.addEventListener(type, (e) => handler(JSON.parse(e.data).payload));
+
+  on("state", (payload) => renderOutput(payload.state === "ON"));
+  on("voltage", (payload) => renderSetpoint("voltage", payload.voltage));
+  on("current", (payload) => renderSetpoint("current", payload.current));
+  on("measured_voltage", (payload) => ($("measured-voltage").textContent = `${payload.voltage} V`));
+  on("measured_current", (payload) => ($("measured-current").textContent = `${payload.current} A`));
+  on("estop", (payload) => renderEstop(payload.latched));
+  on("status", (payload) => renderStatus(payload.status, payload.panic_message));
+
+  // Errors of the runner and connection errors share the "error" event name
+  events.addEventListener("error", (e) => {
+    if (e.data) {
+      showMessage(JSON.parse(e.data).payload.message, true);
+    } else {
+      setConnection("Connection lost", "lost");
+    }
+  });
+  events.addEventListener("open", () => setConnection("Connected", "connected"));
+}
+
+// ----------------------------------------------------------------------------
+
+// Select a power supply, load its state and follow its events
+async function selectDevice(name) {
+  panel.name = name;
+  showMessage("");
+  try {
+    const state = await api("GET", psuPath());
+    renderOutput(state.output);
+    renderEstop(state.estop_latched);
+    renderStatus(state.status, state.panic_message);
+    applyLimits("voltage", state.limits.min_voltage, state.limits.max_voltage, DEFAULT_MAX_VOLTAGE, "V");
+    applyLimits("current", state.limits.min_current, state.limits.max_current, DEFAULT_MAX_CURRENT, "A");
+    renderSetpoint("voltage", state.voltage);
+    renderSetpoint("current", state.current);
+    $("measured-voltage").textContent = `${state.measured_voltage} V`;
+    $("measured-current").textContent = `${state.measured_current} A`;
+    if (state.stale) {
+      showMessage("The state may be outdated, the runner did not answer yet", true);
+    }
+  } catch (error) {
+    showMessage(error.message, true);
+  }
+  openEvents();
+}
+
+// ----------------------------------------------------------------------------
+
+// Keyboard name of the configured toggle key, e.g. "space" for " "
+function keyName(key) {
+  const names = { space: " ", enter: "Enter" };
+  return names[key.toLowerCase()] ?? key.toLowerCase();
+}
+
+// ----------------------------------------------------------------------------
+
+function bindControls() {
+  $("device").addEventListener("change", (e) => selectDevice(e.target.value));
+  $("power").addEventListener("click", togglePower);
+
+  for (const [kind, send] of [["voltage", setVoltage], ["current", setCurrent]]) {
+    const slider = $(`${kind}-slider`);
+    const input = $(`${kind}-input`);
+    // Moving the slider only updates the display, releasing it sends the command
+    slider.addEventListener("input", () => (input.value = slider.value));
+    slider.addEventListener("change", () => send(slider.value));
+    input.addEventListener("change", () => send(input.value));
+  }
+
+  document.addEventListener("keydown", (e) => {
+    const typing = ["INPUT", "SELECT", "TEXTAREA"].includes(e.target.tagName);
+    if (!typing && panel.toggleKey && e.key.toLowerCase() === panel.toggleKey.toLowerCase()) {
+      e.preventDefault();
+      togglePower();
+    }
+  });
+}
+
+// ----------------------------------------------------------------------------
+
+async function start() {
+  bindControls();
+
+  try {
+    const config = await api("GET", "/panel/config.json");
+    if (config.power_toggle_key) {
+      panel.toggleKey = keyName(config.power_toggle_key);
+      $("shortcut").textContent = `Press "${config.power_toggle_key}" to toggle the output`;
+    }
+  } catch (error) {
+    // The panel works without its configuration, only the shortcut is missing
+  }
+
+  let names = [];
+  try {
+    names = await api("GET", "/power-supply");
+  } catch (error) {
+    setConnection("Server unreachable", "lost");
+    return;
+  }
+
+  if (names.length === 0) {
+    $("empty").hidden = false;
+    setConnection("No device", "");
+    return;
+  }
+
+  const select = $("device");
+  for (const name of names) {
+    select.add(new Option(name, name));
+  }
+  // A device can be preselected with ?device=<name>
+  const wanted = new URLSearchParams(window.location.search).get("device");
+  select.value = names.includes(wanted) ? wanted : names[0];
+
+  $("panel").hidden = false;
+  selectDevice(select.value);
+}
+
+start();
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+
+use rmcp::handler::server::router::prompt::PromptRouter;
+use rmcp::handler::server::router::tool::ToolRouter;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::*;
+use rmcp::prompt;
+use rmcp::prompt_handler;
+use rmcp::prompt_router;
+use rmcp::service::RequestContext;
+use rmcp::tool;
+use rmcp::tool_handler;
+use rmcp::tool_router;
+use rmcp::ErrorData as McpError;
+use rmcp::RoleServer;
+use rmcp::ServerHandler;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::prompts::CurrentLimitCharacterizationArgs;
+use super::prompts::MinimumOperatingVoltageArgs;
+use super::prompts::SafePowerUpArgs;
+use super::resources::PsuResources;
+use super::tools::CurrentParams;
+use super::tools::HistoryParams;
+use super::tools::PowerSupplyService;
+use super::tools::VoltageParams;
+use crate::server::config::ServerConfig;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct PsuParams {
+    /// Name of the power supply, as returned by `list_power_supplies`
+    psu_name: String,
+}
+
+/// Parameters of a per-device tool or prompt, preceded by the name of the power supply
+///
+/// The parameters are flattened so that both endpoints share the same schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct PsuArgs<T> {
+    /// Name of the power supply, as returned by `list_power_supplies`
+    psu_name: String,
+    #[serde(flatten)]
+    inner: T,
+}
+
+/// Service exposing the tools of all the power supplies on a single MCP endpoint
+///
+/// Each tool takes the name of the target power supply and forwards the call
+/// to the per-device service, so both endpoints behave the same way.
+#[derive(Clone)]
+pub struct AggregatedPowerSupplyService {
+    /// Server configuration, source of the power supplies descriptions
+    config: ServerConfig,
+
+    /// Per-device services, keyed by power supply name
+    services: HashMap<String, PowerSupplyService>,
+
+    /// MCP resources of all the power supplies
+    resources: PsuResources,
+
+    /// Tool router for MCP tools
+    tool_router: ToolRouter<AggregatedPowerSupplyService>,
+    /// Prompt router for MCP prompts
+    prompt_router: PromptRouter<AggregatedPowerSupplyService>,
+}
+
+impl AggregatedPowerSupplyService {
+    //--------------------------------------------------------------------------
+
+    /// Create the service from the per-device services, keyed by power supply name
+    pub fn new(config: ServerConfig, services: HashMap<String, PowerSupplyService>) -> Self {
+        let resources = services
+            .values()
+            .fold(PsuResources::default(), |resources, service| {
+                resources.with_all(service.resources())
+            });
+
+        Self {
+            config,
+            services,
+            resources,
+            tool_router: Self::tool_router(),
+            prompt_router: Self::prompt_router(),
+        }
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get a copy of the service for a new MCP session
+    ///
+    /// Tools are shared, resource subscriptions belong to the session.
+    pub fn new_session(&self) -> Self {
+        let mut service = self.clone();
+        service.resources = self.resources.new_session();
+        service
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the service of the given power supply
+    fn service(&self, psu_name: &str) -> Result<&PowerSupplyService, McpError> {
+        self.services.get(psu_name).ok_or_else(|| {
+            let mut names: Vec<&String> = self.services.keys().collect();
+            names.sort();
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Unknown power supply '{}', available: {:?}",
+                    psu_name, names
+                ),
+                None,
+            )
+        })
+    }
+}
+
+#[tool_router]
+impl AggregatedPowerSupplyService {
+    //--------------------------------------------------------------------------
+
+    /// List the power supplies managed by this server
+    #[tool(
+        description = "List the power supplies managed by this server with their names, models, descriptions and security limits. Use the names as psu_name in the other tools"
+    )]
+    async fn list_power_supplies(&self) -> Result<CallToolResult, McpError> {
+        let mut power_supplies: Vec<serde_json::Value> = self
+            .config
+            .runners
+            .iter()
+            .flatten()
+            .map(|(name, config)| {
+                serde_json::json!({
+                    "name": name,
+                    "model": config.model,
+                    "description": config.description,
+                    "security_min_voltage": config.security_min_voltage,
+                    "security_max_voltage": config.security_max_voltage,
+                    "security_min_current": config.security_min_current,
+                    "security_max_current": config.security_max_current,
+                })
+            })
+            .collect();
+        power_supplies.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+        Ok(CallToolResult::structured(serde_json::json!({
+            "power_supplies": power_supplies,
+        })))
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the output state of a power supply
+    #[tool(
+        description = "Get the output state of a power supply (ON or OFF) and whether its emergency stop is latched"
+    )]
+    async fn get_output_state(
+        &self,
+        params: Parameters<PsuParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.service(&params.0.psu_name)?.get_output_state().await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the voltage and current setpoints of a power supply
+    #[tool(
+        description = "Get the voltage (V) and current limit (A) setpoints of a power supply, as read back from the device"
+    )]
+    async fn get_setpoints(
+        &self,
+        params: Parameters<PsuParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.service(&params.0.psu_name)?.get_setpoints().await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the measured output voltage and current of a power supply
+    #[tool(
+        description = "Get the last measured output voltage (V) and current (A) of a power supply"
+    )]
+    async fn get_measurements(
+        &self,
+        params: Parameters<PsuParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.service(&params.0.psu_name)?.get_measurements().await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the status of a power supply runner
+    #[tool(
+        description = "Get the status of a power supply runner (Initializing, Running or Panicking) with the panic message if any"
+    )]
+    async fn get_status(&self, params: Parameters<PsuParams>) -> Result<CallToolResult, McpError> {
+        self.service(&params.0.psu_name)?.get_status().await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the security limits of a power supply
+    #[tool(
+        description = "Get the security limits of a power supply, voltage (V) and current (A) setpoints outside these limits are rejected"
+    )]
+    async fn get_security_limits(
+        &self,
+        params: Parameters<PsuParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.service(&params.0.psu_name)?
+            .get_security_limits()
+            .await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the measurements and state changes of a power supply over a time range
+    #[tool(
+        description = "Get the history of a power supply over a time range: output state (1 ON, 0 OFF), voltage and current setpoints, measured voltage (V) and current (A), emergency stop (1 latched). Long ranges are downsampled, each point holding the mean, min and max of its time bucket"
+    )]
+    async fn get_history(
+        &self,
+        params: Parameters<PsuArgs<HistoryParams>>,
+    ) -> Result<CallToolResult, McpError> {
+        let PsuArgs { psu_name, inner } = params.0;
+        self.service(&psu_name)?
+            .get_history(Parameters(inner))
+            .await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Enable the output of a power supply
+    #[tool(description = "Enable the output of a power supply (turn on power)")]
+    async fn output_enable(
+        &self,
+        params: Parameters<PsuParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.service(&params.0.psu_name)?
+            .output_enable(context)
+            .await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Disable the output of a power supply
+    #[tool(description = "Disable the output of a power supply (turn off power)")]
+    async fn output_disable(
+        &self,
+        params: Parameters<PsuParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.service(&params.0.psu_name)?
+            .output_disable(context)
+            .await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Set the output voltage of a power supply
+    #[tool(
+        description = "Set the output voltage of a power supply. Takes voltage as a string, e.g., '5.0'. Returns the voltage actually applied by the device"
+    )]
+    async fn set_voltage(
+        &self,
+        params: Parameters<PsuArgs<VoltageParams>>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let PsuArgs { psu_name, inner } = params.0;
+        self.service(&psu_name)?
+            .set_voltage(Parameters(inner), context)
+            .await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Set the output current limit of a power supply
+    #[tool(
+        description = "Set the output current limit of a power supply. Takes current as a string, e.g., '1.0'. Returns the current limit actually applied by the device"
+    )]
+    async fn set_current(
+        &self,
+        params: Parameters<PsuArgs<CurrentParams>>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let PsuArgs { psu_name, inner } = params.0;
+        self.service(&psu_name)?
+            .set_current(Parameters(inner), context)
+            .await
+    }
+}
+
+#[prompt_router]
+impl AggregatedPowerSupplyService {
+    //--------------------------------------------------------------------------
+
+    /// Guide the agent through a safe power-up of a DUT
+    #[prompt(
+        name = "safe_power_up",
+        description = "Safely power up a DUT on a power supply: apply the setpoints with the output OFF, enable it and check the measurements"
+    )]
+    async fn safe_power_up(
+        &self,
+        params: Parameters<PsuArgs<SafePowerUpArgs>>,
+    ) -> Result<GetPromptResult, McpError> {
+        let PsuArgs { psu_name, inner } = params.0;
+        self.service(&psu_name)?
+            .safe_power_up(Parameters(inner))
+            .await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Guide the agent through a current-limit characterization of a DUT
+    #[prompt(
+        name = "current_limit_characterization",
+        description = "Sweep the current limit of a power supply at a fixed voltage and record how the DUT behaves"
+    )]
+    async fn current_limit_characterization(
+        &self,
+        params: Parameters<PsuArgs<CurrentLimitCharacterizationArgs>>,
+    ) -> Result<GetPromptResult, McpError> {
+        let PsuArgs { psu_name, inner } = params.0;
+        self.service(&psu_name)?
+            .current_limit_characterization(Parameters(inner))
+            .await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Guide the agent through the search of the minimum operating voltage of a DUT
+    #[prompt(
+        name = "find_minimum_operating_voltage",
+        description = "Sweep the voltage of a power supply down until the DUT stops operating to find its minimum operating voltage"
+    )]
+    async fn find_minimum_operating_voltage(
+        &self,
+        params: Parameters<PsuArgs<MinimumOperatingVoltageArgs>>,
+    ) -> Result<GetPromptResult, McpError> {
+        let PsuArgs { psu_name, inner } = params.0;
+        self.service(&psu_name)?
+            .find_minimum_operating_voltage(Parameters(inner))
+            .await
+    }
+}
+
+#[tool_handler]
+#[prompt_handler]
+impl ServerHandler for AggregatedPowerSupplyService {
+    //--------------------------------------------------------------------------
+
+    /// Get server information and capabilities
+    fn get_info(&self) -> ServerInfo {
+        debug!("MCP get_info called");
+
+        let mut names = self.config.runner_names();
+        names.sort();
+
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .build(),
+            server_info: Implementation::from_build_env(),
+            instructions: Some(format!(
+                r#"""This server provides access to several power supplies: {}.
+Every tool takes the name of the target power supply as "psu_name", use "list_power_supplies" to discover them.
+The live data of each power supply is exposed as resources "psu://<name>/state", "psu://<name>/limits" and "psu://<name>/measurements".
+            """#,
+                names.join(", ")
+            )),
+        }
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// List the resources of all the power supplies
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(self.resources.list())
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Read a resource of a power supply
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.resources.read(&request.uri).await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Subscribe to the updates of a resource
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources.subscribe(request.uri, context).await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Unsubscribe from the updates of a resource
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources.unsubscribe(request.uri).await
+    }
+}
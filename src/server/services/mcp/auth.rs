@@ -0,0 +1,296 @@
+use std::sync::Arc;
+
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::header::AUTHORIZATION;
+use axum::http::header::WWW_AUTHENTICATE;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use rmcp::model::ErrorCode;
+use rmcp::service::RequestContext;
+use rmcp::ErrorData as McpError;
+use rmcp::RoleServer;
+use tower_http::cors::AllowOrigin;
+use tower_http::cors::CorsLayer;
+use tracing::debug;
+use tracing::warn;
+
+use crate::server::config::path::resolve_config_path;
+use crate::server::config::McpAuthConfig;
+use crate::server::config::McpConfig;
+use crate::server::config::McpScope;
+use crate::server::config::McpTokenConfig;
+
+/// Header carrying the token when the bearer scheme is not used
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Tokens accepted by the MCP server, also used by the commands of the HTTP API
+pub struct McpAuth {
+    tokens: Vec<McpTokenConfig>,
+}
+
+impl McpAuth {
+    // ------------------------------------------------------------------------------
+
+    /// Load the tokens from the configuration and the secrets file
+    pub fn from_config(config: &McpAuthConfig) -> anyhow::Result<Self> {
+        let mut tokens = config.tokens.clone().unwrap_or_default();
+
+        if let Some(secrets_file) = &config.secrets_file {
+            let path = resolve_config_path(secrets_file).ok_or_else(|| {
+                anyhow::anyhow!("Failed to resolve secrets file '{}'", secrets_file)
+            })?;
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read secrets file {:?}: {}", path, e))?;
+            let secrets: Vec<McpTokenConfig> = serde_json5::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid secrets file {:?}: {}", path, e))?;
+            tokens.extend(secrets);
+        }
+
+        // An empty token list would lock every client out
+        if tokens.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Authentication is enabled but no token is configured"
+            ));
+        }
+        if tokens.iter().any(|t| t.token.is_empty()) {
+            return Err(anyhow::anyhow!("Tokens must not be empty"));
+        }
+
+        Ok(Self { tokens })
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Find the scope of the token sent in the request headers
+    pub fn scope_of(&self, headers: &HeaderMap) -> Option<McpScope> {
+        // The authentication scheme is case-insensitive (RFC 7235)
+        let bearer = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim_start().split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, token)| token);
+        let api_key = headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let candidate = bearer.or(api_key)?.trim();
+
+        let token = self
+            .tokens
+            .iter()
+            .find(|token| constant_time_eq(token.token.as_bytes(), candidate.as_bytes()))?;
+        debug!(
+            "Request authenticated as '{}'",
+            token.name.as_deref().unwrap_or("unnamed")
+        );
+        Some(token.scope)
+    }
+}
+
+/// Compare two secrets without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Middleware rejecting the requests without a valid token
+///
+/// The scope of the token is stored in the request extensions, rmcp forwards
+/// them to the tools which check it with `require_control_scope`.
+pub async fn authenticate(
+    State(auth): State<Arc<McpAuth>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    match auth.scope_of(request.headers()) {
+        Some(scope) => {
+            request.extensions_mut().insert(scope);
+            next.run(request).await
+        }
+        None => {
+            warn!("MCP request rejected: missing or invalid token");
+            (
+                StatusCode::UNAUTHORIZED,
+                [(WWW_AUTHENTICATE, "Bearer")],
+                "Missing or invalid MCP token",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Middleware granting the control scope to every request, used when authentication is disabled
+///
+/// HTTP requests always carry a scope, so that a request missing it is refused.
+pub async fn grant_control(mut request: Request, next: Next) -> Response {
+    request.extensions_mut().insert(McpScope::Control);
+    next.run(request).await
+}
+
+/// Refuse the command unless the request was authenticated with a control token
+pub fn require_control_scope(context: &RequestContext<RoleServer>) -> Result<(), McpError> {
+    if has_control_scope(context.extensions.get::<Parts>()) {
+        return Ok(());
+    }
+    Err(McpError::new(
+        ErrorCode::INVALID_REQUEST,
+        "Commands changing the power supply require a control token",
+        None,
+    ))
+}
+
+/// Check the scope stored by the middlewares in the HTTP request parts
+///
+/// Requests without HTTP parts come from the stdio transport and are allowed.
+/// HTTP requests without scope did not go through a middleware and are refused.
+fn has_control_scope(parts: Option<&Parts>) -> bool {
+    match parts {
+        None => true,
+        Some(parts) => matches!(parts.extensions.get::<McpScope>(), Some(McpScope::Control)),
+    }
+}
+
+/// Build the CORS layer of the MCP server
+///
+/// Permissive unless a list of allowed origins is configured.
+pub fn cors_layer(config: &McpConfig) -> anyhow::Result<CorsLayer> {
+    match &config.cors_allowed_origins {
+        None => Ok(CorsLayer::permissive()),
+        Some(origins) => {
+            let origins = origins
+                .iter()
+                .map(|origin| {
+                    HeaderValue::from_str(origin)
+                        .map_err(|e| anyhow::anyhow!("Invalid CORS origin '{}': {}", origin, e))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(CorsLayer::new()
+                .allow_origin(AllowOrigin::list(origins))
+                .allow_methods(tower_http::cors::Any)
+                .allow_headers(tower_http::cors::Any)
+                .expose_headers(tower_http::cors::Any))
+        }
+    }
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ------------------------------------------------------------------------------
+
+    fn auth() -> McpAuth {
+        McpAuth {
+            tokens: vec![
+                McpTokenConfig {
+                    name: Some("dashboard".to_string()),
+                    token: "read-secret".to_string(),
+                    scope: McpScope::ReadOnly,
+                },
+                McpTokenConfig {
+                    name: None,
+                    token: "control-secret".to_string(),
+                    scope: McpScope::Control,
+                },
+            ],
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    fn headers(name: &'static str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    // ------------------------------------------------------------------------------
+
+    fn parts(scope: Option<McpScope>) -> Parts {
+        let (mut parts, _) = Request::new(()).into_parts();
+        if let Some(scope) = scope {
+            parts.extensions.insert(scope);
+        }
+        parts
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn constant_time_eq_compares_content_and_length() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"secret", b""));
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn scope_of_accepts_bearer_in_any_case() {
+        let auth = auth();
+        for value in [
+            "Bearer control-secret",
+            "bearer control-secret",
+            "BEARER control-secret",
+        ] {
+            assert_eq!(
+                auth.scope_of(&headers("authorization", value)),
+                Some(McpScope::Control)
+            );
+        }
+        assert_eq!(
+            auth.scope_of(&headers("authorization", "Bearer read-secret")),
+            Some(McpScope::ReadOnly)
+        );
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn scope_of_accepts_api_key() {
+        assert_eq!(
+            auth().scope_of(&headers(API_KEY_HEADER, "read-secret")),
+            Some(McpScope::ReadOnly)
+        );
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn scope_of_rejects_invalid_tokens() {
+        let auth = auth();
+        assert_eq!(auth.scope_of(&HeaderMap::new()), None);
+        assert_eq!(
+            auth.scope_of(&headers("authorization", "Bearer wrong-secret")),
+            None
+        );
+        assert_eq!(
+            auth.scope_of(&headers("authorization", "Basic control-secret")),
+            None
+        );
+        assert_eq!(
+            auth.scope_of(&headers("authorization", "control-secret")),
+            None
+        );
+        assert_eq!(auth.scope_of(&headers(API_KEY_HEADER, "")), None);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn control_scope_is_required_on_http() {
+        assert!(has_control_scope(None));
+        assert!(has_control_scope(Some(&parts(Some(McpScope::Control)))));
+        assert!(!has_control_scope(Some(&parts(Some(McpScope::ReadOnly)))));
+        assert!(!has_control_scope(Some(&parts(None))));
+    }
+}
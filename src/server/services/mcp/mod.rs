@@ -1,48 +1,69 @@
+mod aggregated;
+pub(super) mod auth;
+mod prompts;
+mod resources;
 mod tools;
 
 use axum::Router;
+use pza_power_supply_client::ALL_RUNNERS_NAME;
 use pza_power_supply_client::SERVER_TYPE_NAME;
+use rmcp::transport::stdio;
 use rmcp::transport::{
     streamable_http_server::session::local::LocalSessionManager, StreamableHttpService,
 };
+use rmcp::ServiceExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tokio::signal;
-use tokio::sync::oneshot;
-use tower_http::cors::CorsLayer;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::error;
 use tracing::info;
+use tracing::warn;
 
+use aggregated::AggregatedPowerSupplyService;
+use auth::McpAuth;
 use tools::PowerSupplyService;
 
 use crate::server::config::ServerConfig;
 
-pub struct McpService {}
+/// Maximum time given to the HTTP server to close its connections on shutdown
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
 
-impl McpService {
-    //
-    // Must take a list of psu names to manage
-    // for each name
-    //  create an endpoint with the name and a service
-    //
+pub struct McpService {
+    /// Sender of the shutdown signal of the HTTP server
+    shutdown_sender: watch::Sender<bool>,
+    /// Receiver of the bind state of the HTTP server, true once listening
+    bound_receiver: watch::Receiver<bool>,
+    /// Receiver of the stop state of the HTTP server, true once its connections are closed
+    stopped_receiver: watch::Receiver<bool>,
+}
 
-    /// Starts the server with the given service
+impl McpService {
+    /// Starts the HTTP server, with one endpoint per power supply and an aggregated one
     ///
-    pub async fn start(config: ServerConfig) -> anyhow::Result<()> {
-        // Bind and serve the application
+    /// Configuration errors are returned immediately. Bind and serve errors end
+    /// the returned task with an error, so they are reported by the task monitor.
+    pub async fn start(
+        config: ServerConfig,
+    ) -> anyhow::Result<(Self, JoinHandle<Result<(), anyhow::Error>>)> {
         let bind_address = format!("{}:{}", config.mcp.host, config.mcp.port);
-        let listener = TcpListener::bind(&bind_address).await?;
 
         //
-        let mut app = Router::new().layer(CorsLayer::permissive());
+        let mut app = Router::new();
 
         let psu_names = config.runner_names();
+        let mut psu_services = HashMap::new();
 
         //
         for psu_name in psu_names {
             let service_tools = PowerSupplyService::new(config.clone(), psu_name.clone())?;
+            psu_services.insert(psu_name.clone(), service_tools.clone());
 
             // Create the streamable HTTP service for MCP protocol handling
             let mcp_service = StreamableHttpService::new(
-                move || Ok(service_tools.clone()),
+                move || Ok(service_tools.new_session()),
                 LocalSessionManager::default().into(),
                 Default::default(),
             );
@@ -61,45 +82,125 @@ impl McpService {
             );
         }
 
-        // Set up shutdown signal handling
-        let (shutdown_tx, _shutdown_rx) = oneshot::channel();
+        // Aggregated endpoint, tools take the power supply name as argument
+        let aggregated_tools = AggregatedPowerSupplyService::new(config.clone(), psu_services);
+        let mcp_service = StreamableHttpService::new(
+            move || Ok(aggregated_tools.new_session()),
+            LocalSessionManager::default().into(),
+            Default::default(),
+        );
+        let aggregated_path = format!("/{}/{}", SERVER_TYPE_NAME, ALL_RUNNERS_NAME);
+        app = app.nest_service(aggregated_path.as_str(), mcp_service);
+        info!(
+            "MCP aggregated server listening on http://{}{}",
+            bind_address, aggregated_path
+        );
+
+        // Authentication, applied to every endpoint
+        match &config.mcp.auth {
+            Some(auth_config) => {
+                let auth = Arc::new(McpAuth::from_config(auth_config)?);
+                app = app.layer(axum::middleware::from_fn_with_state(
+                    auth,
+                    auth::authenticate,
+                ));
+                info!("MCP server authentication enabled");
+            }
+            None => {
+                app = app.layer(axum::middleware::from_fn(auth::grant_control));
+                warn!("MCP server authentication disabled, anyone reaching the port can control the power supplies");
+            }
+        }
 
-        // Spawn a task to listen for shutdown signals
-        tokio::spawn(async move {
-            let _ = signal::ctrl_c().await;
-            tracing::info!("Received shutdown signal");
-            let _ = shutdown_tx.send(());
+        // CORS must be the outer layer so that preflight requests are not authenticated
+        app = app.layer(auth::cors_layer(&config.mcp)?);
+
+        // Bind and serve the application until the shutdown signal
+        let (shutdown_sender, mut shutdown_receiver) = watch::channel(false);
+        let (bound_sender, bound_receiver) = watch::channel(false);
+        let (stopped_sender, stopped_receiver) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            let listener = TcpListener::bind(&bind_address).await.map_err(|e| {
+                error!("Failed to bind MCP server on {}: {}", bind_address, e);
+                anyhow::anyhow!("Failed to bind MCP server on {}: {}", bind_address, e)
+            })?;
+            info!("MCP server bound on {}", bind_address);
+            bound_sender.send_replace(true);
+
+            let result = axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown_receiver.wait_for(|stop| *stop).await;
+                    info!("MCP server shutting down");
+                })
+                .await;
+            stopped_sender.send_replace(true);
+            result.map_err(|e| {
+                error!("MCP server failed: {}", e);
+                anyhow::anyhow!("MCP server failed: {}", e)
+            })
         });
 
-        // Start the server with graceful shutdown
-        let server = axum::serve(listener, app);
-
-        // Démarrer le serveur dans une tâche séparée
-        let _server_handle = tokio::spawn(async move { server.await });
-
-        // Attendre soit l'arrêt du serveur soit le signal d'arrêt
-        // tokio::select! {
-        //     result = server_handle => {
-        //         match result {
-        //             Ok(server_result) => server_result?,
-        //             Err(e) => return Err(IoError::new(std::io::ErrorKind::Other, e)),
-        //         }
-        //     }
-        //     _ = shutdown_signal.take().unwrap() => {
-        //         tracing::info!("Graceful shutdown initiated");
-        //     }
-        // }
-
-        // if let Some(shutdown_rx) = shutdown_signal.take() {
-        //     server
-        //         .with_graceful_shutdown(async move {
-        //             let _ = shutdown_rx.await;
-        //         })
-        //         .await?;
-        // } else {
-        //     server.await?;
-        // }
+        Ok((
+            Self {
+                shutdown_sender,
+                bound_receiver,
+                stopped_receiver,
+            },
+            handle,
+        ))
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Get a receiver of the bind state of the HTTP server
+    ///
+    /// Used by the readiness monitor, the value turns true once the server listens.
+    pub fn bound_receiver(&self) -> watch::Receiver<bool> {
+        self.bound_receiver.clone()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Stop the HTTP server gracefully
+    ///
+    /// Waits until the server has closed its connections and returned, up to
+    /// a timeout since MCP sessions may keep streams open.
+    pub async fn shutdown(&self) {
+        if self.shutdown_sender.send(true).is_err() {
+            // The server task is already stopped
+            return;
+        }
+        // A closed channel means the server task ended, it is stopped too
+        let mut stopped_receiver = self.stopped_receiver.clone();
+        if tokio::time::timeout(
+            SHUTDOWN_TIMEOUT,
+            stopped_receiver.wait_for(|stopped| *stopped),
+        )
+        .await
+        .is_err()
+        {
+            warn!("MCP server did not stop in time, connections will be aborted");
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Serve the tools of one power supply over stdin/stdout until the client disconnects
+    ///
+    pub async fn serve_stdio(config: ServerConfig, psu_name: String) -> anyhow::Result<()> {
+        if !config.runner_names().contains(&psu_name) {
+            return Err(anyhow::anyhow!(
+                "Unknown power supply '{}', available: {:?}",
+                psu_name,
+                config.runner_names()
+            ));
+        }
+
+        let service_tools = PowerSupplyService::new(config, psu_name.clone())?;
+        info!("MCP server serving '{}' on stdio", psu_name);
 
+        let server = service_tools.serve(stdio()).await?;
+        server.waiting().await?;
         Ok(())
     }
 }
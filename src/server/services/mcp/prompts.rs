@@ -0,0 +1,423 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::server::config::PowerSupplyConfig;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct SafePowerUpArgs {
+    /// Target output voltage in Volts, e.g. '3.3'
+    pub(super) voltage: String,
+    /// Current limit in Amperes, e.g. '0.5'
+    pub(super) current_limit: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct CurrentLimitCharacterizationArgs {
+    /// Output voltage applied during the characterization, in Volts
+    pub(super) voltage: String,
+    /// First current limit of the sweep, in Amperes
+    pub(super) start_current: String,
+    /// Last current limit of the sweep, in Amperes
+    pub(super) stop_current: String,
+    /// Increment between two current limits, in Amperes
+    pub(super) step_current: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct MinimumOperatingVoltageArgs {
+    /// Voltage at which the DUT is known to operate, start of the sweep, in Volts
+    pub(super) start_voltage: String,
+    /// Lowest voltage allowed during the sweep, in Volts
+    pub(super) stop_voltage: String,
+    /// Decrement between two voltages, in Volts
+    pub(super) step_voltage: String,
+    /// Current limit during the sweep, in Amperes
+    pub(super) current_limit: String,
+    /// Measured current (A) above which the DUT is considered operating
+    pub(super) operating_current: String,
+}
+
+impl SafePowerUpArgs {
+    /// Check the arguments against the security limits of the power supply
+    pub(super) fn validate(&self, config: Option<&PowerSupplyConfig>) -> anyhow::Result<()> {
+        voltage(config, "voltage", &self.voltage)?;
+        current(config, "current_limit", &self.current_limit)?;
+        Ok(())
+    }
+}
+
+impl CurrentLimitCharacterizationArgs {
+    /// Check the arguments against the security limits of the power supply
+    pub(super) fn validate(&self, config: Option<&PowerSupplyConfig>) -> anyhow::Result<()> {
+        voltage(config, "voltage", &self.voltage)?;
+        let start = current(config, "start_current", &self.start_current)?;
+        let stop = current(config, "stop_current", &self.stop_current)?;
+        positive("step_current", &self.step_current)?;
+        if start > stop {
+            return Err(anyhow::anyhow!(
+                "'start_current' ({} A) must not be above 'stop_current' ({} A)",
+                start,
+                stop
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl MinimumOperatingVoltageArgs {
+    /// Check the arguments against the security limits of the power supply
+    pub(super) fn validate(&self, config: Option<&PowerSupplyConfig>) -> anyhow::Result<()> {
+        let start = voltage(config, "start_voltage", &self.start_voltage)?;
+        let stop = voltage(config, "stop_voltage", &self.stop_voltage)?;
+        positive("step_voltage", &self.step_voltage)?;
+        if stop > start {
+            return Err(anyhow::anyhow!(
+                "'stop_voltage' ({} V) must not be above 'start_voltage' ({} V)",
+                stop,
+                start
+            ));
+        }
+        let limit = current(config, "current_limit", &self.current_limit)?;
+        let operating = number("operating_current", &self.operating_current)?;
+        if operating < 0.0 || operating >= limit {
+            return Err(anyhow::anyhow!(
+                "'operating_current' ({} A) must be between 0 A and 'current_limit' ({} A)",
+                operating,
+                limit
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Parse a numeric argument of a prompt
+fn number(name: &str, value: &str) -> anyhow::Result<f32> {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| anyhow::anyhow!("'{}' must be a number, got '{}'", name, value))
+}
+
+/// Parse a strictly positive increment of a sweep
+fn positive(name: &str, value: &str) -> anyhow::Result<f32> {
+    let number = number(name, value)?;
+    if number <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "'{}' must be above 0, got {}",
+            name,
+            number
+        ));
+    }
+    Ok(number)
+}
+
+/// Parse a setpoint argument and check it against the given security limits
+fn within(
+    name: &str,
+    value: &str,
+    (min, max): (Option<f32>, Option<f32>),
+    unit: &str,
+) -> anyhow::Result<f32> {
+    let number = number(name, value)?;
+    if let Some(min) = min.filter(|min| number < *min) {
+        return Err(anyhow::anyhow!(
+            "'{}' ({} {}) is below the security limit of {} {}",
+            name,
+            number,
+            unit,
+            min,
+            unit
+        ));
+    }
+    if let Some(max) = max.filter(|max| number > *max) {
+        return Err(anyhow::anyhow!(
+            "'{}' ({} {}) is above the security limit of {} {}",
+            name,
+            number,
+            unit,
+            max,
+            unit
+        ));
+    }
+    Ok(number)
+}
+
+/// Parse a voltage argument and check it against the security limits
+fn voltage(config: Option<&PowerSupplyConfig>, name: &str, value: &str) -> anyhow::Result<f32> {
+    let limits = (
+        config.and_then(|c| c.security_min_voltage),
+        config.and_then(|c| c.security_max_voltage),
+    );
+    within(name, value, limits, "V")
+}
+
+/// Parse a current argument and check it against the security limits
+fn current(config: Option<&PowerSupplyConfig>, name: &str, value: &str) -> anyhow::Result<f32> {
+    let limits = (
+        config.and_then(|c| c.security_min_current),
+        config.and_then(|c| c.security_max_current),
+    );
+    within(name, value, limits, "A")
+}
+
+/// Describe the security limits of the power supply for the agent
+fn limits_text(config: Option<&PowerSupplyConfig>) -> String {
+    let limit = |value: Option<f32>| match value {
+        Some(value) => value.to_string(),
+        None => "not configured".to_string(),
+    };
+    format!(
+        "voltage from {} V to {} V, current from {} A to {} A",
+        limit(config.and_then(|c| c.security_min_voltage)),
+        limit(config.and_then(|c| c.security_max_voltage)),
+        limit(config.and_then(|c| c.security_min_current)),
+        limit(config.and_then(|c| c.security_max_current)),
+    )
+}
+
+/// Checks shared by all the procedures, run before touching the output
+fn preamble(psu_name: &str, config: Option<&PowerSupplyConfig>) -> String {
+    format!(
+        r#"You are operating the bench power supply "{psu_name}" through its MCP tools.
+Its security limits are: {limits}.
+Pass "{psu_name}" as `psu_name` to the tools that take one.
+
+Before anything else:
+1. Call `get_status` and stop if the status is not `Running`, report the panic message if any.
+2. Call `get_output_state` and stop if `estop_latched` is true, an operator must reset the emergency stop.
+3. Check that every value of the procedure is inside the security limits, otherwise stop and explain why.
+
+Safety rules for the whole procedure:
+- Never change the voltage or current limit of an enabled output unless the step says so.
+- After each command, compare the value read back in the tool result with the requested value.
+- If any tool returns an error, call `output_disable` and stop.
+- If `get_measurements` shows a current close to the limit (above 90%) when it should not, call `output_disable` and stop: the DUT may be shorted.
+"#,
+        psu_name = psu_name,
+        limits = limits_text(config),
+    )
+}
+
+/// Power up a DUT safely
+pub(super) fn safe_power_up(
+    psu_name: &str,
+    config: Option<&PowerSupplyConfig>,
+    args: &SafePowerUpArgs,
+) -> String {
+    format!(
+        r#"{preamble}
+Procedure: safe power-up of a DUT at {voltage} V with a current limit of {current} A.
+1. Call `output_disable` so the setpoints are applied with the output OFF.
+2. Call `set_current` with "{current}", then `set_voltage` with "{voltage}".
+3. Call `get_setpoints` and check both values.
+4. Call `output_enable`.
+5. Wait one second, then call `get_measurements`:
+   - the measured voltage must be close to {voltage} V,
+   - the measured current must stay below the limit of {current} A.
+   If not, call `output_disable` immediately and report the measurements.
+6. Report the final setpoints and measurements."#,
+        preamble = preamble(psu_name, config),
+        voltage = args.voltage,
+        current = args.current_limit,
+    )
+}
+
+/// Characterize the behavior of a DUT against the current limit
+pub(super) fn current_limit_characterization(
+    psu_name: &str,
+    config: Option<&PowerSupplyConfig>,
+    args: &CurrentLimitCharacterizationArgs,
+) -> String {
+    format!(
+        r#"{preamble}
+Procedure: characterize the DUT at {voltage} V with current limits from {start} A to {stop} A by steps of {step} A.
+1. Call `output_disable`, then `set_current` with "{start}" and `set_voltage` with "{voltage}".
+2. Call `output_enable`.
+3. For each current limit of the sweep:
+   a. Call `set_current` with the limit (this step allows changing the limit of the enabled output).
+   b. Wait one second, call `get_measurements` and record the limit, the measured voltage and the measured current.
+   c. The DUT is current limited when the measured voltage drops noticeably below {voltage} V, note it.
+4. Call `output_disable` at the end of the sweep, even if a step failed.
+5. Report the results as a table (limit, measured voltage, measured current, limited yes/no)
+   and the lowest current limit at which the DUT is not limited."#,
+        preamble = preamble(psu_name, config),
+        voltage = args.voltage,
+        start = args.start_current,
+        stop = args.stop_current,
+        step = args.step_current,
+    )
+}
+
+/// Find the minimum voltage at which a DUT operates
+pub(super) fn find_minimum_operating_voltage(
+    psu_name: &str,
+    config: Option<&PowerSupplyConfig>,
+    args: &MinimumOperatingVoltageArgs,
+) -> String {
+    format!(
+        r#"{preamble}
+Procedure: find the minimum operating voltage of the DUT, sweeping down from {start} V to {stop} V by steps of {step} V with a current limit of {current} A.
+The DUT is considered operating while its measured current is above {operating} A.
+1. Call `output_disable`, then `set_current` with "{current}" and `set_voltage` with "{start}".
+2. Call `output_enable`, wait one second and call `get_measurements`.
+   If the DUT is not operating at {start} V, call `output_disable` and stop.
+3. Decrease the voltage by {step} V with `set_voltage` (this step allows changing the voltage of the enabled output),
+   never going below {stop} V. After each step, wait one second, call `get_measurements` and record the values.
+4. Stop the sweep at the first voltage where the DUT is no longer operating.
+5. Call `output_disable`.
+6. Report the measurements as a table and the last voltage at which the DUT was operating."#,
+        preamble = preamble(psu_name, config),
+        start = args.start_voltage,
+        stop = args.stop_voltage,
+        step = args.step_voltage,
+        current = args.current_limit,
+        operating = args.operating_current,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Power supply limited to 0-5 V and 0-1 A
+    fn config() -> PowerSupplyConfig {
+        serde_json::from_str(
+            r#"{
+                "model": "emulator",
+                "security_min_voltage": 0.0,
+                "security_max_voltage": 5.0,
+                "security_min_current": 0.0,
+                "security_max_current": 1.0
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn safe_power_up_args(voltage: &str, current_limit: &str) -> SafePowerUpArgs {
+        SafePowerUpArgs {
+            voltage: voltage.to_string(),
+            current_limit: current_limit.to_string(),
+        }
+    }
+
+    fn characterization_args(
+        start: &str,
+        stop: &str,
+        step: &str,
+    ) -> CurrentLimitCharacterizationArgs {
+        CurrentLimitCharacterizationArgs {
+            voltage: "3.3".to_string(),
+            start_current: start.to_string(),
+            stop_current: stop.to_string(),
+            step_current: step.to_string(),
+        }
+    }
+
+    fn minimum_voltage_args(
+        start: &str,
+        stop: &str,
+        operating: &str,
+    ) -> MinimumOperatingVoltageArgs {
+        MinimumOperatingVoltageArgs {
+            start_voltage: start.to_string(),
+            stop_voltage: stop.to_string(),
+            step_voltage: "0.1".to_string(),
+            current_limit: "0.5".to_string(),
+            operating_current: operating.to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_arguments_within_the_limits() {
+        let config = config();
+        assert!(safe_power_up_args("3.3", "0.5")
+            .validate(Some(&config))
+            .is_ok());
+        assert!(safe_power_up_args(" 5 ", "1")
+            .validate(Some(&config))
+            .is_ok());
+        assert!(characterization_args("0.1", "1.0", "0.1")
+            .validate(Some(&config))
+            .is_ok());
+        assert!(minimum_voltage_args("3.3", "1.0", "0.01")
+            .validate(Some(&config))
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_arguments_that_are_not_numbers() {
+        let error = safe_power_up_args("3.3V", "0.5")
+            .validate(None)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "'voltage' must be a number, got '3.3V'");
+        assert!(safe_power_up_args("3.3", "NaN").validate(None).is_err());
+        assert!(safe_power_up_args("3.3", "").validate(None).is_err());
+    }
+
+    #[test]
+    fn rejects_setpoints_outside_the_security_limits() {
+        let config = config();
+        let error = safe_power_up_args("12", "0.5")
+            .validate(Some(&config))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "'voltage' (12 V) is above the security limit of 5 V"
+        );
+        assert!(safe_power_up_args("-1", "0.5")
+            .validate(Some(&config))
+            .is_err());
+        assert!(characterization_args("0.1", "2", "0.1")
+            .validate(Some(&config))
+            .is_err());
+
+        // Without configuration, only the format is checked
+        assert!(safe_power_up_args("12", "0.5").validate(None).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_sweeps() {
+        let config = config();
+        assert!(characterization_args("0.1", "1.0", "0")
+            .validate(Some(&config))
+            .is_err());
+        assert!(characterization_args("0.1", "1.0", "-0.1")
+            .validate(Some(&config))
+            .is_err());
+        assert!(characterization_args("1.0", "0.1", "0.1")
+            .validate(Some(&config))
+            .is_err());
+        assert!(minimum_voltage_args("1.0", "3.3", "0.01")
+            .validate(Some(&config))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_an_operating_current_above_the_current_limit() {
+        let config = config();
+        let error = minimum_voltage_args("3.3", "1.0", "0.5")
+            .validate(Some(&config))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "'operating_current' (0.5 A) must be between 0 A and 'current_limit' (0.5 A)"
+        );
+        assert!(minimum_voltage_args("3.3", "1.0", "-0.1")
+            .validate(Some(&config))
+            .is_err());
+    }
+
+    #[test]
+    fn procedures_include_the_arguments_and_the_limits() {
+        let config = config();
+        let text = safe_power_up("rail_3v3", Some(&config), &safe_power_up_args("3.3", "0.5"));
+        assert!(text.contains(r#"power supply "rail_3v3""#));
+        assert!(text.contains("voltage from 0 V to 5 V, current from 0 A to 1 A"));
+        assert!(text.contains("at 3.3 V with a current limit of 0.5 A"));
+
+        let text = safe_power_up("rail_3v3", None, &safe_power_up_args("3.3", "0.5"));
+        assert!(text.contains("voltage from not configured V"));
+    }
+}
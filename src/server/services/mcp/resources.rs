@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rmcp::model::*;
+use rmcp::service::Peer;
+use rmcp::service::RequestContext;
+use rmcp::ErrorData as McpError;
+use rmcp::RoleServer;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::trace;
+
+use pza_power_supply_client::PowerSupplyClient;
+
+use crate::server::config::PowerSupplyConfig;
+
+/// Scheme of the power supply resources URIs
+const RESOURCE_SCHEME: &str = "psu";
+
+/// Kind of resource exposed for each power supply
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ResourceKind {
+    /// Output state, setpoints, emergency stop latch and runner status
+    State,
+    /// Security limits from the configuration
+    Limits,
+    /// Measured output voltage and current
+    Measurements,
+}
+
+impl ResourceKind {
+    const ALL: [ResourceKind; 3] = [Self::State, Self::Limits, Self::Measurements];
+
+    fn path(&self) -> &'static str {
+        match self {
+            Self::State => "state",
+            Self::Limits => "limits",
+            Self::Measurements => "measurements",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::State => {
+                "Output state, voltage and current setpoints, emergency stop latch and runner status"
+            }
+            Self::Limits => "Security limits, setpoints outside these limits are rejected",
+            Self::Measurements => "Last measured output voltage and current",
+        }
+    }
+}
+
+/// Build the URI of a power supply resource
+fn resource_uri(psu_name: &str, kind: ResourceKind) -> String {
+    format!("{}://{}/{}", RESOURCE_SCHEME, psu_name, kind.path())
+}
+
+/// Notification tasks of a session, keyed by resource URI
+///
+/// The tasks are aborted when the session ends and drops its resources, so that
+/// a client disconnecting without unsubscribing does not leave them running.
+#[derive(Default)]
+struct Subscriptions(HashMap<String, JoinHandle<()>>);
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        for handle in self.0.values() {
+            handle.abort();
+        }
+    }
+}
+
+/// Power supply known by the resources
+#[derive(Clone)]
+struct PsuEntry {
+    /// Client of the power supply, source of the resources content
+    client: PowerSupplyClient,
+
+    /// Configuration of the power supply, source of the security limits
+    config: Option<PowerSupplyConfig>,
+}
+
+/// MCP resources of the power supplies (`psu://<name>/state|limits|measurements`)
+///
+/// Subscriptions are scoped to an MCP session: each session must get its own
+/// instance through `new_session`. A subscribed resource spawns a task sending
+/// `resources/updated` notifications when the client channels report a change.
+#[derive(Clone, Default)]
+pub struct PsuResources {
+    /// Power supplies, keyed by name
+    psus: HashMap<String, PsuEntry>,
+
+    /// Notification tasks of the session, keyed by resource URI
+    subscriptions: Arc<Mutex<Subscriptions>>,
+}
+
+impl PsuResources {
+    // ------------------------------------------------------------------------------
+
+    /// Add a power supply to the resources
+    pub fn with_psu(
+        mut self,
+        psu_name: String,
+        client: PowerSupplyClient,
+        config: Option<PowerSupplyConfig>,
+    ) -> Self {
+        self.psus.insert(psu_name, PsuEntry { client, config });
+        self
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Add all the power supplies of other resources
+    pub fn with_all(mut self, other: &PsuResources) -> Self {
+        self.psus
+            .extend(other.psus.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Get a copy of the resources for a new MCP session, without subscriptions
+    pub fn new_session(&self) -> Self {
+        Self {
+            psus: self.psus.clone(),
+            subscriptions: Default::default(),
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// List the resources of all the power supplies
+    pub fn list(&self) -> ListResourcesResult {
+        let mut names: Vec<&String> = self.psus.keys().collect();
+        names.sort();
+
+        let resources = names
+            .into_iter()
+            .flat_map(|name| {
+                ResourceKind::ALL.iter().map(move |kind| {
+                    let mut raw = RawResource::new(
+                        resource_uri(name, *kind),
+                        format!("{} {}", name, kind.path()),
+                    );
+                    raw.description = Some(kind.description().to_string());
+                    raw.mime_type = Some("application/json".to_string());
+                    raw.no_annotation()
+                })
+            })
+            .collect();
+
+        ListResourcesResult {
+            resources,
+            next_cursor: None,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Read the content of a resource
+    pub async fn read(&self, uri: &str) -> Result<ReadResourceResult, McpError> {
+        let (entry, kind) = self.parse_uri(uri)?;
+
+        let content = match kind {
+            ResourceKind::State => serde_json::json!({
+                "state": if entry.client.get_oe().await { "ON" } else { "OFF" },
+                "voltage": entry.client.get_voltage().await,
+                "current": entry.client.get_current().await,
+                "estop_latched": entry.client.get_estop_latched().await,
+                "status": entry.client.get_status().await,
+                "panic_message": entry.client.get_panic_message().await,
+            }),
+            ResourceKind::Limits => {
+                let config = entry.config.as_ref();
+                serde_json::json!({
+                    "security_min_voltage": config.and_then(|c| c.security_min_voltage),
+                    "security_max_voltage": config.and_then(|c| c.security_max_voltage),
+                    "security_min_current": config.and_then(|c| c.security_min_current),
+                    "security_max_current": config.and_then(|c| c.security_max_current),
+                })
+            }
+            ResourceKind::Measurements => serde_json::json!({
+                "measured_voltage": entry.client.get_measured_voltage().await,
+                "measured_current": entry.client.get_measured_current().await,
+            }),
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(content.to_string(), uri)],
+        })
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Subscribe the session to the updates of a resource
+    pub async fn subscribe(
+        &self,
+        uri: String,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let (entry, kind) = self.parse_uri(&uri)?;
+
+        // Limits come from the configuration and never change, nothing to notify
+        if kind == ResourceKind::Limits {
+            return Ok(());
+        }
+
+        let mut subscriptions = self.subscriptions.lock().await;
+        if subscriptions.0.contains_key(&uri) {
+            return Ok(());
+        }
+        debug!("MCP subscription to '{}'", uri);
+
+        let handle = tokio::spawn(Self::notify_loop(
+            entry.client.clone(),
+            kind,
+            uri.clone(),
+            context.peer,
+        ));
+        subscriptions.0.insert(uri, handle);
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Unsubscribe the session from the updates of a resource
+    pub async fn unsubscribe(&self, uri: String) -> Result<(), McpError> {
+        if let Some(handle) = self.subscriptions.lock().await.0.remove(&uri) {
+            debug!("MCP unsubscription from '{}'", uri);
+            handle.abort();
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Find the power supply and the kind of resource targeted by an URI
+    fn parse_uri(&self, uri: &str) -> Result<(&PsuEntry, ResourceKind), McpError> {
+        let not_found = || {
+            McpError::resource_not_found(
+                format!("Unknown resource '{}'", uri),
+                Some(serde_json::json!({ "uri": uri })),
+            )
+        };
+
+        let path = uri
+            .strip_prefix(&format!("{}://", RESOURCE_SCHEME))
+            .ok_or_else(not_found)?;
+        let (psu_name, kind_path) = path.rsplit_once('/').ok_or_else(not_found)?;
+        let entry = self.psus.get(psu_name).ok_or_else(not_found)?;
+        let kind = ResourceKind::ALL
+            .into_iter()
+            .find(|kind| kind.path() == kind_path)
+            .ok_or_else(not_found)?;
+        Ok((entry, kind))
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Send a notification to the peer each time the resource content changes
+    ///
+    /// The loop stops when the peer can no longer be notified, or is aborted
+    /// on unsubscription and at the end of the session.
+    async fn notify_loop(
+        client: PowerSupplyClient,
+        kind: ResourceKind,
+        uri: String,
+        peer: Peer<RoleServer>,
+    ) {
+        let mut state_rx = client.subscribe_state_changes();
+        let mut voltage_rx = client.subscribe_voltage_changes();
+        let mut current_rx = client.subscribe_current_changes();
+        let mut estop_rx = client.subscribe_estop_changes();
+        let mut status_rx = client.subscribe_status_changes();
+        let mut measured_voltage_rx = client.subscribe_measured_voltage_changes();
+        let mut measured_current_rx = client.subscribe_measured_current_changes();
+
+        loop {
+            // Wait for a change of the data exposed by the resource
+            match kind {
+                ResourceKind::State => {
+                    tokio::select! {
+                        _ = state_rx.recv() => {}
+                        _ = voltage_rx.recv() => {}
+                        _ = current_rx.recv() => {}
+                        _ = estop_rx.recv() => {}
+                        _ = status_rx.recv() => {}
+                    }
+                }
+                ResourceKind::Measurements => {
+                    tokio::select! {
+                        _ = measured_voltage_rx.recv() => {}
+                        _ = measured_current_rx.recv() => {}
+                    }
+                }
+                // Never subscribed, see `subscribe`
+                ResourceKind::Limits => return,
+            }
+
+            trace!("MCP resource '{}' updated", uri);
+            let param = ResourceUpdatedNotificationParam { uri: uri.clone() };
+            if let Err(e) = peer.notify_resource_updated(param).await {
+                debug!("Stop notifying '{}': {}", uri, e);
+                return;
+            }
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 use rmcp::handler::server::router::prompt::PromptRouter;
 use rmcp::handler::server::router::tool::ToolRouter;
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::model::*;
+use rmcp::prompt;
 use rmcp::prompt_handler;
 use rmcp::prompt_router;
+use rmcp::service::Peer;
 use rmcp::service::RequestContext;
 use rmcp::tool;
 use rmcp::tool_handler;
@@ -18,21 +21,71 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 use tracing::info;
+use tracing::warn;
 
+use pza_power_supply_client::client::ClientError;
+use pza_power_supply_client::payload::HistoryQueryPayload;
+use pza_power_supply_client::payload::PowerState;
+use pza_power_supply_client::HistoryClient;
 use pza_power_supply_client::PowerSupplyClient;
 
+use super::auth::require_control_scope;
+use super::prompts;
+use super::prompts::CurrentLimitCharacterizationArgs;
+use super::prompts::MinimumOperatingVoltageArgs;
+use super::prompts::SafePowerUpArgs;
+use super::resources::PsuResources;
+use crate::server::config::PowerSupplyConfig;
 use crate::server::config::ServerConfig;
+use crate::server::services::history::format_time;
+use crate::server::services::history::parse_time;
+
+/// Age after which the client cache is refreshed before answering a read tool
+const CACHE_MAX_AGE: Duration = Duration::from_secs(3);
+
+/// Maximum time to wait for the cache to be refreshed
+const CACHE_REFRESH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default time to wait for the runner to acknowledge a command
+const DEFAULT_ACK_TIMEOUT_MS: u64 = 5000;
+
+/// Default maximum number of points of a series returned by the history tool,
+/// kept low so the answer fits in the context of the agent
+const DEFAULT_HISTORY_MAX_POINTS: usize = 100;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct VoltageParams {
+    pub(super) voltage: String,
+}
 
 #[derive(Serialize, Deserialize, JsonSchema)]
-struct VoltageParams {
-    voltage: String,
+pub(super) struct CurrentParams {
+    pub(super) current: String,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema)]
-struct CurrentParams {
-    current: String,
+pub(super) struct HistoryParams {
+    /// Start of the range: RFC 3339 date, local date and time of the server
+    /// (e.g. '2026-10-18T14:00:00'), milliseconds since the Unix epoch, or a
+    /// duration before now (e.g. '-5m'). Whole retained history if not set
+    pub(super) from: Option<String>,
+    /// End of the range, same formats as `from`. Now if not set
+    pub(super) to: Option<String>,
+    /// Series to return: state, voltage, current, measured_voltage,
+    /// measured_current, estop. All series if not set
+    pub(super) events: Option<Vec<String>>,
+    /// Maximum number of points of each series, from 1 to 1000 (100 by default)
+    pub(super) max_points: Option<usize>,
 }
 
+/// Answer expected from the user when a command must be confirmed
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct Confirmation {
+    /// Set to true to execute the command
+    confirm: bool,
+}
+rmcp::elicit_safe!(Confirmation);
+
 #[derive(Clone)]
 struct PowerSupplyState {
     client: PowerSupplyClient,
@@ -45,6 +98,18 @@ pub struct PowerSupplyService {
     /// Power Supply Name provided by the user
     psu_name: String,
 
+    /// Configuration of the power supply, source of the security limits
+    psu_config: Option<PowerSupplyConfig>,
+
+    /// Time to wait for the runner to acknowledge a command
+    ack_timeout: Duration,
+
+    /// MCP resources of the power supply
+    resources: PsuResources,
+
+    /// Client of the history service, None if the history is disabled
+    history: Option<HistoryClient>,
+
     /// Tool router for MCP tools
     tool_router: ToolRouter<PowerSupplyService>,
     /// Prompt router for MCP prompts
@@ -58,149 +123,601 @@ impl PowerSupplyService {
 
     pub fn new(config: ServerConfig, psu_name: String) -> anyhow::Result<Self> {
         let client = PowerSupplyClient::builder()
-            .with_ip(config.broker.tcp.unwrap().clone())
+            .with_ip(config.broker.tcp.clone().unwrap())
             .with_power_supply_name(psu_name.clone())
             .build()?;
         debug!("Client initialized");
 
+        let history = config
+            .history
+            .as_ref()
+            .map(|_| HistoryClient::connect(config.broker.tcp.clone()));
+
+        let psu_config = config
+            .runners
+            .as_ref()
+            .and_then(|runners| runners.get(&psu_name))
+            .cloned();
+
+        let ack_timeout =
+            Duration::from_millis(config.mcp.ack_timeout_ms.unwrap_or(DEFAULT_ACK_TIMEOUT_MS));
+
+        let resources =
+            PsuResources::default().with_psu(psu_name.clone(), client.clone(), psu_config.clone());
+
         Ok(Self {
             psu_name,
+            psu_config,
+            ack_timeout,
+            resources,
+            history,
             tool_router: Self::tool_router(),
             prompt_router: Self::prompt_router(),
             state: Arc::new(Mutex::new(PowerSupplyState { client })),
         })
     }
-}
 
-#[tool_router]
-impl PowerSupplyService {
     //--------------------------------------------------------------------------
 
-    // /// Get the current output enable status of the power supply
-    // #[tool(description = "Get the current output enable status of the power supply")]
-    // async fn output_enable_get(&self) -> Result<CallToolResult, McpError> {
-    //     debug!("MCP tool 'output_enable_get' called");
+    /// Get a copy of the service for a new MCP session
+    ///
+    /// Tools are shared, resource subscriptions belong to the session.
+    pub fn new_session(&self) -> Self {
+        let mut service = self.clone();
+        service.resources = self.resources.new_session();
+        service
+    }
+
+    //--------------------------------------------------------------------------
 
-    //     info!("Retrieved output enable status: {}", self.output_enable);
-    //     Ok(CallToolResult::success(vec![Content::text(format!(
-    //         "Output enable status: {}",
-    //         self.output_enable
-    //     ))]))
-    // }
+    /// Get the MCP resources of the power supply
+    pub(super) fn resources(&self) -> &PsuResources {
+        &self.resources
+    }
 
     //--------------------------------------------------------------------------
 
-    /// Enable the power supply output
-    #[tool(description = "Enable the power supply output (turn on power)")]
-    async fn output_enable(&self) -> Result<CallToolResult, McpError> {
+    /// Get the client, refreshing its cache first if it is stale
+    ///
+    /// A failed refresh is not an error, the cached values are returned and
+    /// the `stale` flag of the result tells the agent they may be outdated.
+    async fn fresh_client(&self) -> PowerSupplyClient {
         let client = {
             let psu_state = self.state.lock().await;
             psu_state.client.clone()
         };
 
-        client.enable_output().await.map_err(|_e| {
+        if client.is_cache_stale(CACHE_MAX_AGE).await {
+            if let Err(e) = client.refresh(CACHE_REFRESH_TIMEOUT).await {
+                warn!("[{}] Failed to refresh client cache: {}", self.psu_name, e);
+            }
+        }
+        client
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Build a structured tool result, adding the cache freshness information
+    async fn read_result(
+        &self,
+        client: &PowerSupplyClient,
+        mut value: serde_json::Value,
+    ) -> CallToolResult {
+        let age = client.cache_age().await;
+        value["psu_name"] = serde_json::json!(self.psu_name);
+        value["cache_age_ms"] = serde_json::json!(age.map(|age| age.as_millis() as u64));
+        value["stale"] = serde_json::json!(age.map_or(true, |age| age > CACHE_MAX_AGE));
+        CallToolResult::structured(value)
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Ask the user to confirm a command through an MCP elicitation request
+    ///
+    /// The command is refused if the user declines or cancels, and also if the
+    /// client does not support elicitation since nobody can confirm it.
+    async fn confirm(&self, peer: &Peer<RoleServer>, action: String) -> Result<(), McpError> {
+        info!("[{}] Asking user confirmation: {}", self.psu_name, action);
+        let refused = |reason: String| {
+            warn!("[{}] Command refused: {}", self.psu_name, reason);
+            McpError::new(
+                ErrorCode::INVALID_REQUEST,
+                format!("Command refused, {}: {}", reason, action),
+                None,
+            )
+        };
+
+        match peer
+            .elicit::<Confirmation>(format!("Power supply '{}': {}", self.psu_name, action))
+            .await
+        {
+            Ok(Some(Confirmation { confirm: true })) => Ok(()),
+            Ok(_) => Err(refused("declined by the user".to_string())),
+            Err(e) => Err(refused(format!("confirmation unavailable ({})", e))),
+        }
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Confirmation threshold of the output voltage, if configured
+    fn confirm_voltage_threshold(&self) -> Option<f32> {
+        self.psu_config
+            .as_ref()
+            .and_then(|config| config.confirm.as_ref())
+            .and_then(|confirm| confirm.enable_above_voltage)
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Confirmation threshold of the current limit, if configured
+    fn confirm_current_threshold(&self) -> Option<f32> {
+        self.psu_config
+            .as_ref()
+            .and_then(|config| config.confirm.as_ref())
+            .and_then(|confirm| confirm.current_above)
+    }
+}
+
+#[tool_router]
+impl PowerSupplyService {
+    //--------------------------------------------------------------------------
+
+    /// Get the output state of the power supply
+    #[tool(
+        description = "Get the output state of the power supply (ON or OFF) and whether its emergency stop is latched"
+    )]
+    pub(super) async fn get_output_state(&self) -> Result<CallToolResult, McpError> {
+        let client = self.fresh_client().await;
+
+        let enabled = client.get_oe().await;
+        let estop_latched = client.get_estop_latched().await;
+        debug!("Retrieved output state: {}", enabled);
+
+        Ok(self
+            .read_result(
+                &client,
+                serde_json::json!({
+                    "state": if enabled { "ON" } else { "OFF" },
+                    "estop_latched": estop_latched,
+                }),
+            )
+            .await)
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the voltage and current setpoints of the power supply
+    #[tool(
+        description = "Get the voltage (V) and current limit (A) setpoints of the power supply, as read back from the device"
+    )]
+    pub(super) async fn get_setpoints(&self) -> Result<CallToolResult, McpError> {
+        let client = self.fresh_client().await;
+
+        let voltage = client.get_voltage().await;
+        let current = client.get_current().await;
+
+        Ok(self
+            .read_result(
+                &client,
+                serde_json::json!({
+                    "voltage": voltage,
+                    "current": current,
+                }),
+            )
+            .await)
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the measured output voltage and current of the power supply
+    #[tool(
+        description = "Get the last measured output voltage (V) and current (A) of the power supply"
+    )]
+    pub(super) async fn get_measurements(&self) -> Result<CallToolResult, McpError> {
+        let client = self.fresh_client().await;
+
+        let voltage = client.get_measured_voltage().await;
+        let current = client.get_measured_current().await;
+
+        Ok(self
+            .read_result(
+                &client,
+                serde_json::json!({
+                    "measured_voltage": voltage,
+                    "measured_current": current,
+                }),
+            )
+            .await)
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the status of the power supply runner
+    #[tool(
+        description = "Get the status of the power supply runner (Initializing, Running or Panicking) with the panic message if any"
+    )]
+    pub(super) async fn get_status(&self) -> Result<CallToolResult, McpError> {
+        let client = self.fresh_client().await;
+
+        let status = client.get_status().await;
+        let panic_message = client.get_panic_message().await;
+
+        Ok(self
+            .read_result(
+                &client,
+                serde_json::json!({
+                    "status": status,
+                    "panic_message": panic_message,
+                }),
+            )
+            .await)
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the security limits of the power supply
+    #[tool(
+        description = "Get the security limits of the power supply, voltage (V) and current (A) setpoints outside these limits are rejected"
+    )]
+    pub(super) async fn get_security_limits(&self) -> Result<CallToolResult, McpError> {
+        let config = self.psu_config.as_ref().ok_or_else(|| {
             McpError::new(
                 ErrorCode::INTERNAL_ERROR,
-                "Failed to enable power supply output",
+                "Power supply configuration not found",
+                None,
+            )
+        })?;
+
+        Ok(CallToolResult::structured(serde_json::json!({
+            "psu_name": self.psu_name,
+            "security_min_voltage": config.security_min_voltage,
+            "security_max_voltage": config.security_max_voltage,
+            "security_min_current": config.security_min_current,
+            "security_max_current": config.security_max_current,
+        })))
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Get the measurements and state changes of the power supply over a time range
+    #[tool(
+        description = "Get the history of the power supply over a time range: output state (1 ON, 0 OFF), voltage and current setpoints, measured voltage (V) and current (A), emergency stop (1 latched). Long ranges are downsampled, each point holding the mean, min and max of its time bucket"
+    )]
+    pub(super) async fn get_history(
+        &self,
+        params: Parameters<HistoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let HistoryParams {
+            from,
+            to,
+            events,
+            max_points,
+        } = params.0;
+        let history = self.history.as_ref().ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_REQUEST,
+                "History is not enabled on this server",
                 None,
             )
         })?;
 
+        let parse = |time: Option<String>| {
+            time.as_deref()
+                .map(parse_time)
+                .transpose()
+                .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+        };
+        let mut query = HistoryQueryPayload::from_runner(self.psu_name.clone())
+            .with_range(parse(from)?, parse(to)?)
+            .with_max_points(max_points.unwrap_or(DEFAULT_HISTORY_MAX_POINTS));
+        if let Some(events) = events {
+            query = query.with_events(events);
+        }
+
+        let response = history
+            .query_wait_ack(query, self.ack_timeout)
+            .await
+            .map_err(|e| command_error("Failed to query the history", e))?;
+
+        // Dates are easier to read for the agent than epoch milliseconds
+        let series: Vec<serde_json::Value> = response
+            .series
+            .iter()
+            .map(|series| {
+                let points: Vec<serde_json::Value> = series
+                    .points
+                    .iter()
+                    .map(|point| {
+                        serde_json::json!({
+                            "time": format_time(point.time_ms),
+                            "mean": point.mean,
+                            "min": point.min,
+                            "max": point.max,
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "event": series.event,
+                    "samples": series.samples,
+                    "points": points,
+                })
+            })
+            .collect();
+
+        Ok(CallToolResult::structured(serde_json::json!({
+            "psu_name": self.psu_name,
+            "from": format_time(response.from_ms),
+            "to": format_time(response.to_ms),
+            "series": series,
+        })))
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Enable the power supply output
+    #[tool(description = "Enable the power supply output (turn on power)")]
+    pub(super) async fn output_enable(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        require_control_scope(&context)?;
+        let client = self.fresh_client().await;
+
+        // Powering a high voltage must be confirmed by the user
+        if let Some(threshold) = self.confirm_voltage_threshold() {
+            let voltage = client.get_voltage().await;
+            if voltage.parse::<f32>().map_or(false, |v| v > threshold) {
+                self.confirm(
+                    &context.peer,
+                    format!(
+                        "enable the output at {} V (above {} V)?",
+                        voltage, threshold
+                    ),
+                )
+                .await?;
+            }
+        }
+
+        let state = client
+            .enable_output_wait_ack(self.ack_timeout)
+            .await
+            .map_err(|e| command_error("Failed to enable power supply output", e))?;
+
         info!("Successfully enabled power supply output");
-        Ok(CallToolResult::success(vec![Content::text(
-            "Power supply output enabled".to_string(),
-        )]))
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Power supply output enabled, state read back: {}",
+            state_label(&state)
+        ))]))
     }
 
     //--------------------------------------------------------------------------
 
     /// Disable the power supply output
     #[tool(description = "Disable the power supply output (turn off power)")]
-    async fn output_disable(&self) -> Result<CallToolResult, McpError> {
+    pub(super) async fn output_disable(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        require_control_scope(&context)?;
         let client = {
             let psu_state = self.state.lock().await;
             psu_state.client.clone()
         };
 
-        client.disable_output().await.map_err(|_e| {
-            McpError::new(
-                ErrorCode::INTERNAL_ERROR,
-                "Failed to disable power supply output",
-                None,
-            )
-        })?;
+        let state = client
+            .disable_output_wait_ack(self.ack_timeout)
+            .await
+            .map_err(|e| command_error("Failed to disable power supply output", e))?;
 
         info!("Successfully disabled power supply output");
-        Ok(CallToolResult::success(vec![Content::text(
-            "Power supply output disabled".to_string(),
-        )]))
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Power supply output disabled, state read back: {}",
+            state_label(&state)
+        ))]))
     }
 
     //--------------------------------------------------------------------------
 
     /// Set the output voltage of the power supply
     #[tool(
-        description = "Set the output voltage of the power supply. Takes voltage as a string, e.g., '5.0'"
+        description = "Set the output voltage of the power supply. Takes voltage as a string, e.g., '5.0'. Returns the voltage actually applied by the device"
     )]
-    async fn set_voltage(
+    pub(super) async fn set_voltage(
         &self,
         params: Parameters<VoltageParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
+        require_control_scope(&context)?;
         let voltage = &params.0.voltage;
-        let client = {
-            let psu_state = self.state.lock().await;
-            psu_state.client.clone()
-        };
+        let client = self.fresh_client().await;
+
+        // Raising the voltage of an enabled output must be confirmed by the user
+        if let Some(threshold) = self.confirm_voltage_threshold() {
+            let above = voltage.parse::<f32>().map_or(false, |v| v > threshold);
+            if above && client.get_oe().await {
+                self.confirm(
+                    &context.peer,
+                    format!(
+                        "set the voltage to {} V (above {} V) while the output is ON?",
+                        voltage, threshold
+                    ),
+                )
+                .await?;
+            }
+        }
 
-        client.set_voltage(voltage.clone()).await.map_err(|_e| {
-            McpError::new(
-                ErrorCode::INTERNAL_ERROR,
-                "Failed to set power supply voltage",
-                None,
-            )
-        })?;
+        let applied = client
+            .set_voltage_wait_ack(voltage.clone(), self.ack_timeout)
+            .await
+            .map_err(|e| command_error("Failed to set power supply voltage", e))?;
 
-        info!("Successfully set power supply voltage to {}", voltage);
+        info!("Successfully set power supply voltage to {}", applied);
         Ok(CallToolResult::success(vec![Content::text(format!(
-            "Power supply voltage set to {}",
-            voltage
+            "Power supply voltage set to {} (requested {})",
+            applied, voltage
         ))]))
     }
 
+    //--------------------------------------------------------------------------
+
     /// Set the output current limit of the power supply
     #[tool(
-        description = "Set the output current limit of the power supply. Takes current as a string, e.g., '1.0'"
+        description = "Set the output current limit of the power supply. Takes current as a string, e.g., '1.0'. Returns the current limit actually applied by the device"
     )]
-    async fn set_current(
+    pub(super) async fn set_current(
         &self,
         params: Parameters<CurrentParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
+        require_control_scope(&context)?;
         let current = &params.0.current;
         let client = {
             let psu_state = self.state.lock().await;
             psu_state.client.clone()
         };
 
-        client.set_current(current.clone()).await.map_err(|_e| {
-            McpError::new(
-                ErrorCode::INTERNAL_ERROR,
-                "Failed to set power supply current limit",
-                None,
-            )
-        })?;
+        // Raising the current limit must be confirmed by the user
+        if let Some(threshold) = self.confirm_current_threshold() {
+            if current.parse::<f32>().map_or(false, |c| c > threshold) {
+                self.confirm(
+                    &context.peer,
+                    format!(
+                        "set the current limit to {} A (above {} A)?",
+                        current, threshold
+                    ),
+                )
+                .await?;
+            }
+        }
 
-        info!("Successfully set power supply current limit to {}", current);
+        let applied = client
+            .set_current_wait_ack(current.clone(), self.ack_timeout)
+            .await
+            .map_err(|e| command_error("Failed to set power supply current limit", e))?;
+
+        info!("Successfully set power supply current limit to {}", applied);
         Ok(CallToolResult::success(vec![Content::text(format!(
-            "Power supply current limit set to {}",
-            current
+            "Power supply current limit set to {} (requested {})",
+            applied, current
         ))]))
     }
 }
 
+/// Build the MCP error of a command rejected by the runner or not acknowledged in time
+///
+/// The message of the runner (or the timeout) is kept so the agent knows why it failed.
+/// A rejection (security limits, emergency stop, interlock...) is an invalid request
+/// of the agent, the other failures are internal errors.
+fn command_error(context: &str, error: anyhow::Error) -> McpError {
+    warn!("{}: {}", context, error);
+    let code = match error.downcast_ref::<ClientError>() {
+        Some(ClientError::Rejected(_)) => ErrorCode::INVALID_REQUEST,
+        _ => ErrorCode::INTERNAL_ERROR,
+    };
+    McpError::new(code, format!("{}: {}", context, error), None)
+}
+
+/// Build the MCP error of prompt arguments that are not numbers or not within the limits
+fn prompt_error(error: anyhow::Error) -> McpError {
+    McpError::new(
+        ErrorCode::INVALID_PARAMS,
+        format!("Invalid prompt arguments: {}", error),
+        None,
+    )
+}
+
+/// Label of an output state, as published on the MQTT topics
+fn state_label(state: &PowerState) -> &'static str {
+    match state {
+        PowerState::On => "ON",
+        PowerState::Off => "OFF",
+    }
+}
+
 #[prompt_router]
 impl PowerSupplyService {
-    // No prompts specified in requirements, but trait requires this implementation
-    // Implementation block is needed for the macro to work properly
+    //--------------------------------------------------------------------------
+
+    /// Guide the agent through a safe power-up of a DUT
+    #[prompt(
+        name = "safe_power_up",
+        description = "Safely power up a DUT: apply the setpoints with the output OFF, enable it and check the measurements"
+    )]
+    pub(super) async fn safe_power_up(
+        &self,
+        params: Parameters<SafePowerUpArgs>,
+    ) -> Result<GetPromptResult, McpError> {
+        params
+            .0
+            .validate(self.psu_config.as_ref())
+            .map_err(prompt_error)?;
+        Ok(self.prompt_result(
+            "Safe power-up of a DUT",
+            prompts::safe_power_up(&self.psu_name, self.psu_config.as_ref(), &params.0),
+        ))
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Guide the agent through a current-limit characterization of a DUT
+    #[prompt(
+        name = "current_limit_characterization",
+        description = "Sweep the current limit at a fixed voltage and record how the DUT behaves"
+    )]
+    pub(super) async fn current_limit_characterization(
+        &self,
+        params: Parameters<CurrentLimitCharacterizationArgs>,
+    ) -> Result<GetPromptResult, McpError> {
+        params
+            .0
+            .validate(self.psu_config.as_ref())
+            .map_err(prompt_error)?;
+        Ok(self.prompt_result(
+            "Current-limit characterization",
+            prompts::current_limit_characterization(
+                &self.psu_name,
+                self.psu_config.as_ref(),
+                &params.0,
+            ),
+        ))
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Guide the agent through the search of the minimum operating voltage of a DUT
+    #[prompt(
+        name = "find_minimum_operating_voltage",
+        description = "Sweep the voltage down until the DUT stops operating to find its minimum operating voltage"
+    )]
+    pub(super) async fn find_minimum_operating_voltage(
+        &self,
+        params: Parameters<MinimumOperatingVoltageArgs>,
+    ) -> Result<GetPromptResult, McpError> {
+        params
+            .0
+            .validate(self.psu_config.as_ref())
+            .map_err(prompt_error)?;
+        Ok(self.prompt_result(
+            "Find minimum operating voltage",
+            prompts::find_minimum_operating_voltage(
+                &self.psu_name,
+                self.psu_config.as_ref(),
+                &params.0,
+            ),
+        ))
+    }
+}
+
+impl PowerSupplyService {
+    //--------------------------------------------------------------------------
+
+    /// Build a prompt result made of a single user message
+    fn prompt_result(&self, title: &str, text: String) -> GetPromptResult {
+        GetPromptResult {
+            description: Some(format!("{} on power supply '{}'", title, self.psu_name)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        }
+    }
 }
 
 #[tool_handler]
@@ -217,14 +734,61 @@ impl ServerHandler for PowerSupplyService {
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
                 .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
                 .build(),
             server_info: Implementation::from_build_env(),
             instructions: Some(format!(
                 r#"""This server provides access to a power supply.
 The name of this power supply is "{}" and can be used by the user to request actions.
+Its live data is exposed as resources "psu://{}/state", "psu://{}/limits" and "psu://{}/measurements".
             """#,
-                self.psu_name
+                self.psu_name, self.psu_name, self.psu_name, self.psu_name
             )),
         }
     }
+
+    //--------------------------------------------------------------------------
+
+    /// List the resources of the power supply
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(self.resources.list())
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Read a resource of the power supply
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.resources.read(&request.uri).await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Subscribe to the updates of a resource
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources.subscribe(request.uri, context).await
+    }
+
+    //--------------------------------------------------------------------------
+
+    /// Unsubscribe from the updates of a resource
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources.unsubscribe(request.uri).await
+    }
 }
@@ -1,9 +1,19 @@
+mod data_logger;
+mod history;
+mod http;
 mod mcp;
+mod readiness;
 mod runners;
+mod sequences;
 mod tui;
 use crate::server::cli::Args as CliArgs;
 use crate::server::config::ServerConfig;
+use crate::server::services::data_logger::DataLoggerService;
+use crate::server::services::history::HistoryService;
+use crate::server::services::http::HttpService;
+use crate::server::services::readiness::ReadinessMonitor;
 use crate::server::services::runners::RunnersService;
+use crate::server::services::sequences::SequencesService;
 use crate::server::services::tui::TuiService;
 // use crate::server::factory::Factory;
 // use crate::server::mcp::McpServer;
@@ -14,13 +24,16 @@ use pza_toolkit::rumqtt::broker::start_broker_in_thread;
 use pza_toolkit::task_monitor::TaskMonitor;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::signal;
 use tokio::sync::watch;
 use tokio::sync::Mutex;
 use tracing::error;
 use tracing::info;
 
-use mcp::McpService;
+pub use http::probe_ready;
+pub use mcp::McpService;
+pub use tui::TuiService;
 
 // Global state for sharing data between background services and GUI
 #[derive(Clone)]
@@ -34,6 +47,12 @@ pub struct Services {
     /// Runners service instance
     runners: Option<Arc<Mutex<RunnersService>>>,
 
+    /// MCP service instance
+    mcp: Option<Arc<Mutex<McpService>>>,
+
+    /// HTTP API service instance
+    http: Option<Arc<Mutex<HttpService>>>,
+
     /// Watch channel sender for ready signal
     ready_sender: Arc<Mutex<Option<watch::Sender<bool>>>>,
 
@@ -69,6 +88,8 @@ impl Services {
             server_config,
             drivers_factory,
             runners: None,
+            mcp: None,
+            http: None,
             ready_sender: Arc::new(Mutex::new(Some(ready_sender))),
             ready_receiver,
         }
@@ -85,6 +106,9 @@ impl Services {
 
     /// Start background runtime services
     pub async fn start(&mut self) -> anyhow::Result<()> {
+        // Statuses retained by a previous run are older than this
+        let started = SystemTime::now();
+
         // Monitoring
         let (mut task_monitor, mut runner_tasks_event_receiver) = TaskMonitor::new("services");
 
@@ -120,17 +144,89 @@ impl Services {
             }
         }
 
-        // // Start MCP server only if not disabled
+        // Start Sequences service only if configured
         {
-            McpService::start(self.server_config.clone()).await?;
-            info!("Started MCP server");
+            if self.server_config.sequences.is_some() && self.server_config.runners.is_some() {
+                info!("Starting Sequences service...");
+                let handle = SequencesService::start(self.server_config.clone()).await?;
+                task_monitor
+                    .handle_sender()
+                    .send(("sequences".to_string(), handle))
+                    .await?;
+            } else {
+                info!("Sequences service is disabled in configuration");
+            }
+        }
+
+        // Start Data logger service only if configured
+        {
+            if self.server_config.data_logger.is_some() && self.server_config.runners.is_some() {
+                info!("Starting Data logger service...");
+                let handle = DataLoggerService::start(self.server_config.clone()).await?;
+                task_monitor
+                    .handle_sender()
+                    .send(("data_logger".to_string(), handle))
+                    .await?;
+            } else {
+                info!("Data logger service is disabled in configuration");
+            }
+        }
+
+        // Start History service only if configured
+        {
+            if self.server_config.history.is_some() && self.server_config.runners.is_some() {
+                info!("Starting History service...");
+                let handle = HistoryService::start(self.server_config.clone()).await?;
+                task_monitor
+                    .handle_sender()
+                    .send(("history".to_string(), handle))
+                    .await?;
+            } else {
+                info!("History service is disabled in configuration");
+            }
+        }
+
+        // Start MCP server only if not disabled
+        {
+            if self.server_config.mcp.enable {
+                info!("Starting MCP service...");
+                let (mcp, handle) = McpService::start(self.server_config.clone()).await?;
+                self.mcp = Some(Arc::new(Mutex::new(mcp)));
+                task_monitor
+                    .handle_sender()
+                    .send(("mcp".to_string(), handle))
+                    .await?;
+            } else {
+                info!("MCP service is disabled in configuration");
+            }
+        }
+
+        // Start HTTP API only if enabled
+        {
+            let http_enabled = self
+                .server_config
+                .http
+                .as_ref()
+                .is_some_and(|http| http.enable);
+            if http_enabled {
+                info!("Starting HTTP API service...");
+                let (http, handle) =
+                    HttpService::start(self.server_config.clone(), self.ready_receiver()).await?;
+                self.http = Some(Arc::new(Mutex::new(http)));
+                task_monitor
+                    .handle_sender()
+                    .send(("http".to_string(), handle))
+                    .await?;
+            } else {
+                info!("HTTP API service is disabled in configuration");
+            }
         }
 
         {
             // Start TUI service only if not disabled
             if self.server_config.tui.enable.unwrap_or(true) {
                 info!("Starting TUI service...");
-                let tui_handle = TuiService::start();
+                let tui_handle = TuiService::start(self.server_config.clone());
                 task_monitor
                     .handle_sender()
                     .send(("tui".to_string(), tui_handle))
@@ -140,14 +236,22 @@ impl Services {
             }
         }
 
-        // // Emit ready signal after all services are initialized
-        // {
-        //     let mut sender = self.ready_sender.lock().await;
-        //     if let Some(tx) = sender.take() {
-        //         let _ = tx.send(true);
-        //         info!("Server state is ready - signal emitted");
-        //     }
-        // }
+        // Emit ready signal once the broker, the runners and the MCP server are up
+        {
+            let mut sender = self.ready_sender.lock().await;
+            if let Some(tx) = sender.take() {
+                let runners = self.server_config.runner_names();
+                let mcp_bound = match &self.mcp {
+                    Some(mcp) => Some(mcp.lock().await.bound_receiver()),
+                    None => None,
+                };
+                let handle = ReadinessMonitor::start(started, runners, mcp_bound, tx);
+                task_monitor
+                    .handle_sender()
+                    .send(("readiness".to_string(), handle))
+                    .await?;
+            }
+        }
 
         // Setup Ctrl+C signal handler
         let mut ctrl_c = Box::pin(signal::ctrl_c());
@@ -159,8 +263,8 @@ impl Services {
                 _ = ctrl_c.as_mut() => {
                     info!("Received Ctrl+C signal, shutting down gracefully...");
 
-                    // Cancel all running tasks
-                    task_monitor.cancel_all_monitored_tasks().await;
+                    // Stop the services gracefully, then cancel the remaining tasks
+                    self.shutdown(&mut task_monitor).await;
                     info!("All tasks have been cancelled");
 
                     return Ok(());
@@ -179,16 +283,16 @@ impl Services {
                                     if event_body.task_name == "tui" {
                                         // TUI stopped, shut down other services gracefully
                                         info!("TUI service stopped, shutting down other services...");
-                                        task_monitor.cancel_all_monitored_tasks().await;
+                                        self.shutdown(&mut task_monitor).await;
                                         return Ok(());
                                     }
                                 }
                                 pza_toolkit::task_monitor::Event::TaskStopWithPain(event_body) => {
-                                    // error!("Task '{}' stopped with error: {:?}", event_body.task_name, event_body.error);
+                                    error!("Task '{}' stopped with error: {:?}", event_body.task_name, event_body);
                                     // Continue monitoring other tasks
                                 }
                                 pza_toolkit::task_monitor::Event::TaskPanicOMG(event_body) => {
-                                    // error!("Task '{}' panicked: {}", event_body.task_name, event_body.panic_info);
+                                    error!("Task '{}' panicked: {:?}", event_body.task_name, event_body);
                                     // Decide whether to restart the task or continue
                                 }
                                 _ => {}
@@ -204,6 +308,22 @@ impl Services {
         }
     }
 
+    // ------------------------------------------------------------------------------
+
+    /// Stop the services gracefully, then cancel all the monitored tasks
+    async fn shutdown(&self, task_monitor: &mut TaskMonitor) {
+        if let Some(mcp) = &self.mcp {
+            mcp.lock().await.shutdown().await;
+        }
+        if let Some(http) = &self.http {
+            http.lock().await.shutdown().await;
+        }
+        if let Some(runners) = &self.runners {
+            runners.lock().await.shutdown().await;
+        }
+        task_monitor.cancel_all_monitored_tasks().await;
+    }
+
     // // ------------------------------------------------------------------------------
 
     // pub async fn instances_names(&self) -> Vec<String> {
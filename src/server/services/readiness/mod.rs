@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use pza_power_supply_client::payload::Status;
+use pza_power_supply_client::payload::StatusPayload;
+use pza_power_supply_client::SERVER_TYPE_NAME;
+use pza_toolkit::rumqtt::client::init_client;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+/// Period of the readiness evaluation when no MQTT event is received
+const CHECK_PERIOD: Duration = Duration::from_millis(500);
+
+/// Delay before polling the broker again after a connection error
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Conditions of the readiness of the server
+struct Conditions {
+    /// Start time of the server, in milliseconds since the Unix epoch
+    started_ms: u64,
+    /// True while the monitor is connected to the broker
+    broker_connected: bool,
+    /// Runners that must report a running status
+    runners: Vec<String>,
+    /// Runners that reported a running status
+    running: HashSet<String>,
+    /// Bind state of the MCP server, None if the MCP server is disabled
+    mcp_bound: Option<watch::Receiver<bool>>,
+}
+
+impl Conditions {
+    // ------------------------------------------------------------------------------
+
+    /// Runners that did not report a running status yet
+    fn pending_runners(&self) -> Vec<&str> {
+        self.runners
+            .iter()
+            .filter(|runner| !self.running.contains(*runner))
+            .map(String::as_str)
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Check whether every condition is met
+    fn is_ready(&self) -> bool {
+        self.broker_connected
+            && self.pending_runners().is_empty()
+            && self.mcp_bound.as_ref().is_none_or(|bound| *bound.borrow())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Record the status published by a runner
+    ///
+    /// Statuses published before the start of the server were retained by a
+    /// previous run and are ignored, the runner publishes its own once started.
+    fn update_status(&mut self, topic: &str, payload: bytes::Bytes) {
+        let Some(runner) = topic
+            .strip_prefix(&format!("{}/", SERVER_TYPE_NAME))
+            .and_then(|rest| rest.strip_suffix("/status"))
+        else {
+            return;
+        };
+        match StatusPayload::from_json_bytes(payload) {
+            Ok(payload) if payload.time_ms.is_none_or(|time| time < self.started_ms) => {
+                debug!("Ignoring status of '{}' from a previous run", runner);
+            }
+            Ok(payload) if matches!(payload.status, Status::Running) => {
+                self.running.insert(runner.to_string());
+            }
+            Ok(_) => {
+                self.running.remove(runner);
+            }
+            Err(e) => debug!("Invalid status payload of '{}': {}", runner, e),
+        }
+    }
+}
+
+/// Monitor of the readiness of the server
+///
+/// The server is ready once the broker accepts connections, every configured
+/// runner reports a running status and the MCP server is bound. The readiness
+/// follows the conditions afterwards, e.g. it is lost while a runner restarts.
+pub struct ReadinessMonitor;
+
+impl ReadinessMonitor {
+    /// Start the monitor, the readiness is published on `ready_sender`
+    ///
+    /// `started` is the start time of the server, the runners statuses
+    /// published before it are ignored.
+    pub fn start(
+        started: SystemTime,
+        runners: Vec<String>,
+        mcp_bound: Option<watch::Receiver<bool>>,
+        ready_sender: watch::Sender<bool>,
+    ) -> JoinHandle<Result<(), anyhow::Error>> {
+        let conditions = Conditions {
+            started_ms: started
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            broker_connected: false,
+            runners,
+            running: HashSet::new(),
+            mcp_bound,
+        };
+        tokio::spawn(Self::monitor_loop(conditions, ready_sender))
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Follow the broker connection and the runners statuses, then publish the readiness
+    async fn monitor_loop(
+        mut conditions: Conditions,
+        ready_sender: watch::Sender<bool>,
+    ) -> anyhow::Result<()> {
+        let (client, mut event_loop) = init_client("readiness");
+        let status_filter = format!("{}/+/status", SERVER_TYPE_NAME);
+        let mut check_interval = tokio::time::interval(CHECK_PERIOD);
+        let mut subscribed = false;
+        let mut signal_emitted = false;
+        let mut was_ready = false;
+        let mut last_pending: Vec<String> = Vec::new();
+
+        loop {
+            tokio::select! {
+                event = event_loop.poll() => match event {
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                        conditions.broker_connected = true;
+                        // Subscriptions are lost with the connection, the status messages are retained
+                        subscribed = subscribe(&client, &status_filter);
+                    }
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(packet))) => {
+                        conditions.update_status(&packet.topic, packet.payload);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        if conditions.broker_connected {
+                            info!("Readiness monitor lost the broker: {}", e);
+                        }
+                        conditions.broker_connected = false;
+                        conditions.running.clear();
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                },
+                _ = check_interval.tick() => {
+                    // A failed subscription is retried, the statuses would never be received
+                    if conditions.broker_connected && !subscribed {
+                        subscribed = subscribe(&client, &status_filter);
+                    }
+                }
+            }
+
+            // Log the runners the server is waiting for when they change
+            let pending: Vec<String> = conditions
+                .pending_runners()
+                .into_iter()
+                .map(str::to_string)
+                .collect();
+            if pending != last_pending {
+                if !pending.is_empty() {
+                    info!("Waiting for runners to be running: {:?}", pending);
+                }
+                last_pending = pending;
+            }
+
+            let ready = conditions.is_ready();
+            if ready != was_ready {
+                was_ready = ready;
+                ready_sender.send_replace(ready);
+                if ready && !signal_emitted {
+                    signal_emitted = true;
+                    info!("Server state is ready - signal emitted");
+                } else if ready {
+                    info!("Server state is ready again");
+                } else {
+                    warn!("Server state is no longer ready");
+                }
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+
+/// Subscribe to the runners statuses, returns false if the request could not be queued
+fn subscribe(client: &rumqttc::AsyncClient, filter: &str) -> bool {
+    match client.try_subscribe(filter, rumqttc::QoS::AtMostOnce) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("Readiness monitor failed to subscribe to {}: {}", filter, e);
+            false
+        }
+    }
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ------------------------------------------------------------------------------
+
+    fn conditions() -> Conditions {
+        Conditions {
+            started_ms: 1000,
+            broker_connected: true,
+            runners: vec!["psu".to_string()],
+            running: HashSet::new(),
+            mcp_bound: None,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    fn status(status: Status, time_ms: Option<u64>) -> bytes::Bytes {
+        let mut payload = StatusPayload::from_status(status);
+        payload.time_ms = time_ms;
+        payload.to_json_bytes().unwrap()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn follows_the_statuses_of_the_run() {
+        let mut conditions = conditions();
+        assert!(!conditions.is_ready());
+
+        conditions.update_status(
+            "power-supply/psu/status",
+            status(Status::Running, Some(1500)),
+        );
+        assert!(conditions.is_ready());
+
+        conditions.update_status(
+            "power-supply/psu/status",
+            status(Status::Panicking, Some(1600)),
+        );
+        assert_eq!(conditions.pending_runners(), vec!["psu"]);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn ignores_the_statuses_of_a_previous_run() {
+        let mut conditions = conditions();
+
+        conditions.update_status(
+            "power-supply/psu/status",
+            status(Status::Running, Some(500)),
+        );
+        assert!(!conditions.is_ready());
+
+        // Statuses of servers without publication time cannot be trusted either
+        conditions.update_status("power-supply/psu/status", status(Status::Running, None));
+        assert!(!conditions.is_ready());
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn ignores_other_topics() {
+        let mut conditions = conditions();
+        conditions.update_status(
+            "power-supply/psu/state",
+            status(Status::Running, Some(1500)),
+        );
+        assert!(!conditions.is_ready());
+    }
+}
@@ -1,5 +1,12 @@
+mod persistence;
 mod runner;
 use core::task;
+use pza_power_supply_client::payload::Status;
+use pza_power_supply_client::payload::StatusPayload;
+use pza_power_supply_client::Topics;
+use pza_power_supply_client::SERVER_TYPE_NAME;
+use pza_toolkit::rumqtt::client::init_client;
+use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
 use pza_toolkit::task_monitor::TaskMonitor;
 use std::collections::HashMap;
 use std::fmt::Debug;
@@ -13,11 +20,16 @@ use tracing::info;
 
 use super::drivers::Factory as DriverFactory;
 use crate::server::config::ServerConfig;
+use crate::server::metrics::Metrics;
+use persistence::StateStore;
 use runner::Runner;
 
 pub struct RunnersService {
     /// Just to keep the monitor alive
     _task_monitor: Arc<Mutex<Option<TaskMonitor>>>,
+
+    /// Persisted runners states, flushed on shutdown
+    state_store: Arc<Mutex<StateStore>>,
 }
 
 impl RunnersService {
@@ -29,6 +41,9 @@ impl RunnersService {
         // Monitoring
         let (task_monitor, mut runner_tasks_event_receiver) = TaskMonitor::new("runners");
 
+        // Persisted runners states, shared by all runners
+        let state_store = StateStore::start();
+
         // Start MQTT runners for each configured device
         let factory = drivers_factory.lock().await;
         info!("Starting server runtime services...");
@@ -39,7 +54,13 @@ impl RunnersService {
                 let instance = factory.instanciate_driver(device_config.clone())?;
 
                 // Start the runner
-                let task_handle = Runner::start(name.clone(), instance).await?;
+                let task_handle = Runner::start(
+                    name.clone(),
+                    instance,
+                    server_config.clone(),
+                    state_store.clone(),
+                )
+                .await?;
 
                 // Register the task with the monitor
                 task_monitor
@@ -53,6 +74,21 @@ impl RunnersService {
         let monitor_sender = task_monitor.handle_sender();
         let drivers_factory_clone = drivers_factory.clone();
         let monitor_config = server_config.clone();
+        let monitor_state_store = state_store.clone();
+
+        // MQTT client to publish the status of crashed runners on their behalf
+        let (client, mut event_loop) = init_client("runners");
+        let status_client = RumqttCustomAsyncClient::new(
+            client,
+            rumqttc::QoS::AtMostOnce,
+            true,
+            SERVER_TYPE_NAME.to_string(),
+        );
+        tokio::spawn(async move {
+            loop {
+                while let Ok(_event) = event_loop.poll().await {}
+            }
+        });
 
         // Spawn a task to handle TaskMonitor events and perform restarts
         let handle = tokio::spawn(async move {
@@ -76,6 +112,12 @@ impl RunnersService {
                                 // If the task corresponds to a configured runner, attempt restart
                                 if let Some(runners_map) = &monitor_config.runners {
                                     if let Some(device_cfg) = runners_map.get(&task_name) {
+                                        // Let interlocked runners know this runner is down
+                                        Self::publish_panicking(&status_client, &task_name).await;
+                                        Metrics::global()
+                                            .runner(&task_name)
+                                            .set_status(&Status::Panicking);
+
                                         let attempts =
                                             restart_attempts.entry(task_name.clone()).or_insert(0);
                                         if *attempts >= MAX_RETRIES {
@@ -102,8 +144,13 @@ impl RunnersService {
                                             .instanciate_driver(device_cfg.clone())
                                         {
                                             Ok(instance) => {
-                                                match Runner::start(task_name.clone(), instance)
-                                                    .await
+                                                match Runner::start(
+                                                    task_name.clone(),
+                                                    instance,
+                                                    monitor_config.clone(),
+                                                    monitor_state_store.clone(),
+                                                )
+                                                .await
                                                 {
                                                     Ok(task_handle) => {
                                                         // Register replacement task with the monitor
@@ -112,8 +159,12 @@ impl RunnersService {
                                                             .await
                                                         {
                                                             error!("Failed to register restarted task '{}': {:?}", task_name, e);
+                                                            Metrics::global()
+                                                                .record_restart(&task_name, false);
                                                         } else {
                                                             info!("Successfully restarted runner '{}'", task_name);
+                                                            Metrics::global()
+                                                                .record_restart(&task_name, true);
                                                             // Reset attempts counter on success
                                                             restart_attempts
                                                                 .insert(task_name.clone(), 0);
@@ -121,11 +172,14 @@ impl RunnersService {
                                                     }
                                                     Err(e) => {
                                                         error!("Failed to start restarted runner '{}': {:?}", task_name, e);
+                                                        Metrics::global()
+                                                            .record_restart(&task_name, false);
                                                     }
                                                 }
                                             }
                                             Err(e) => {
                                                 error!("Failed to instantiate driver for '{}' during restart: {:?}", task_name, e);
+                                                Metrics::global().record_restart(&task_name, false);
                                             }
                                         }
                                     }
@@ -147,8 +201,37 @@ impl RunnersService {
         Ok((
             Self {
                 _task_monitor: Arc::new(Mutex::new(Some(task_monitor))),
+                state_store,
             },
             handle,
         ))
     }
+
+    // ------------------------------------------------------------------------------
+
+    /// Save the persisted states still waiting for the writer task
+    ///
+    /// Called on shutdown, before the runners tasks are cancelled.
+    pub async fn shutdown(&self) {
+        StateStore::flush(&self.state_store).await;
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Publish a panicking status on behalf of a crashed runner
+    async fn publish_panicking(client: &RumqttCustomAsyncClient, name: &str) {
+        let payload = StatusPayload::from_status(Status::Panicking)
+            .with_panic_message(format!("Runner '{}' stopped unexpectedly", name));
+        match payload.to_json_bytes() {
+            Ok(bytes) => {
+                if let Err(e) = client.pubsh(&Topics::new(name).status, bytes).await {
+                    error!("Failed to publish panicking status of '{}': {:?}", name, e);
+                }
+            }
+            Err(e) => error!(
+                "Failed to serialize panicking status of '{}': {:?}",
+                name, e
+            ),
+        }
+    }
 }
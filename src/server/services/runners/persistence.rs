@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::sync::Notify;
+use tracing::debug;
+use tracing::warn;
+
+use crate::server::config::path::server_state_file;
+
+/// Delay gathering the updates of the store before it is written
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Last known setpoints and output state of a runner
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PersistedState {
+    /// Last voltage setpoint read back from the device
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voltage: Option<String>,
+    /// Last current setpoint read back from the device
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    /// Last output enable state read back from the device
+    pub enabled: bool,
+    /// True while the emergency stop is latched, restored whatever the restore policy
+    #[serde(default)]
+    pub estop_latched: bool,
+}
+
+/// Store of the runners states, saved in a file under the Panduza user directory
+///
+/// Updates only change the store in memory, a writer task saves it to the file
+/// shortly after, so that the commands never wait for the disk. The pending
+/// updates are saved by `flush` on shutdown.
+pub struct StateStore {
+    /// States keyed by runner name
+    states: HashMap<String, PersistedState>,
+
+    /// True while updates are not saved to the file
+    dirty: bool,
+
+    /// Path of the state file, locked while the file is written so that saves never overlap
+    file: Arc<Mutex<Option<PathBuf>>>,
+
+    /// Wakes up the writer task when the states change
+    changed: Arc<Notify>,
+}
+
+impl StateStore {
+    // --------------------------------------------------------------------------------
+
+    /// Load the store from the state file and start its writer task
+    ///
+    /// The store starts empty if the file is missing or invalid.
+    pub fn start() -> Arc<Mutex<Self>> {
+        let path = server_state_file();
+        let states = match &path {
+            Some(path) if path.exists() => std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str(&content)?))
+                .unwrap_or_else(|e| {
+                    warn!("Ignoring invalid state file {:?}: {}", path, e);
+                    HashMap::new()
+                }),
+            _ => HashMap::new(),
+        };
+        debug!("Loaded persisted states: {:?}", states);
+
+        let changed = Arc::new(Notify::new());
+        let store = Arc::new(Mutex::new(Self {
+            states,
+            dirty: false,
+            file: Arc::new(Mutex::new(path)),
+            changed: changed.clone(),
+        }));
+        tokio::spawn(Self::writer_loop(store.clone(), changed));
+        store
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Get the persisted state of a runner
+    pub fn get(&self, name: &str) -> Option<PersistedState> {
+        self.states.get(name).cloned()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Update the persisted state of a runner, the file is saved in the background
+    pub fn update<F: FnOnce(&mut PersistedState)>(&mut self, name: &str, update: F) {
+        update(self.states.entry(name.to_string()).or_default());
+        self.dirty = true;
+        self.changed.notify_one();
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Save the pending updates now, without waiting for the writer task
+    ///
+    /// Called on shutdown, so that the updates of the last `SAVE_DEBOUNCE` are not lost.
+    pub async fn flush(store: &Arc<Mutex<Self>>) {
+        let file = store.lock().await.file.clone();
+        Self::save_pending(store, &file).await;
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Save the store each time it changes, the updates of `SAVE_DEBOUNCE` are gathered
+    async fn writer_loop(store: Arc<Mutex<Self>>, changed: Arc<Notify>) {
+        let file = store.lock().await.file.clone();
+        loop {
+            changed.notified().await;
+            tokio::time::sleep(SAVE_DEBOUNCE).await;
+            Self::save_pending(&store, &file).await;
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Save the store to the state file if it has pending updates
+    ///
+    /// The file lock is held from the snapshot to the end of the write, so a
+    /// save never overwrites a newer one.
+    async fn save_pending(store: &Arc<Mutex<Self>>, file: &Mutex<Option<PathBuf>>) {
+        let path = file.lock().await;
+
+        // Only hold the store lock to take a snapshot of the states
+        let content = {
+            let mut store = store.lock().await;
+            if !store.dirty {
+                return;
+            }
+            store.dirty = false;
+            serde_json::to_string_pretty(&store.states)
+        };
+        let result = match content {
+            Ok(content) => Self::save(path.as_ref(), content).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            warn!("Failed to save persisted states: {}", e);
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Write the content of the store to the state file
+    ///
+    /// The file is written next to its destination and then renamed, so that
+    /// a crash never leaves a truncated state file behind.
+    async fn save(path: Option<&PathBuf>, content: String) -> anyhow::Result<()> {
+        let path =
+            path.ok_or_else(|| anyhow::anyhow!("Failed to determine server state file path"))?;
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+}
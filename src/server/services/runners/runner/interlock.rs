@@ -0,0 +1,192 @@
+use bytes::Bytes;
+use pza_power_supply_client::payload::PowerState;
+use pza_power_supply_client::payload::PowerStatePayload;
+use pza_power_supply_client::payload::Status;
+use pza_power_supply_client::payload::StatusPayload;
+use pza_power_supply_client::Topics;
+
+/// Runner that must be ON before an interlocked runner can be enabled
+pub struct Prerequisite {
+    /// Name of the prerequisite runner
+    pub name: String,
+
+    /// MQTT topics of the prerequisite runner
+    topics: Topics,
+
+    /// Last known output state of the prerequisite runner
+    output_on: bool,
+
+    /// True once an output state of the prerequisite runner has been received
+    state_received: bool,
+
+    /// True if the prerequisite runner reported a panic
+    panicking: bool,
+}
+
+impl Prerequisite {
+    // --------------------------------------------------------------------------------
+
+    /// Create a prerequisite, considered OFF until its state is received
+    pub fn new(name: String) -> Self {
+        Self {
+            topics: Topics::new(&name),
+            name,
+            output_on: false,
+            state_received: false,
+            panicking: false,
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Topics to subscribe to in order to follow the prerequisite runner
+    pub fn vec_sub(&self) -> Vec<String> {
+        vec![self.topics.state.clone(), self.topics.status.clone()]
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Check if the topic belongs to the prerequisite runner
+    pub fn owns_topic(&self, topic: &str) -> bool {
+        topic == self.topics.state || topic == self.topics.status
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Check if an output state of the prerequisite runner has been received
+    pub fn state_received(&self) -> bool {
+        self.state_received
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Update the prerequisite from a message received on one of its topics
+    pub fn update(&mut self, topic: &str, payload: Bytes) -> anyhow::Result<()> {
+        if topic == self.topics.state {
+            let state = PowerStatePayload::from_json_bytes(payload)?;
+            self.output_on = state.state == PowerState::On;
+            self.state_received = true;
+        } else if topic == self.topics.status {
+            let status = StatusPayload::from_json_bytes(payload)?;
+            self.panicking = matches!(status.status, Status::Panicking);
+        }
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Describe why the prerequisite is not satisfied, None if it is satisfied
+    pub fn violation(&self) -> Option<String> {
+        if self.panicking {
+            Some(format!("prerequisite '{}' is panicking", self.name))
+        } else if !self.output_on {
+            Some(format!("prerequisite '{}' is OFF", self.name))
+        } else {
+            None
+        }
+    }
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --------------------------------------------------------------------------------
+
+    fn state(state: PowerState) -> Bytes {
+        PowerStatePayload::from_state(state)
+            .to_json_bytes()
+            .unwrap()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    fn status(status: Status) -> Bytes {
+        StatusPayload::from_status(status).to_json_bytes().unwrap()
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn is_off_until_its_state_is_received() {
+        let prerequisite = Prerequisite::new("core".to_string());
+        assert!(!prerequisite.state_received());
+        assert_eq!(
+            prerequisite.violation(),
+            Some("prerequisite 'core' is OFF".to_string())
+        );
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn follows_the_output_state() {
+        let mut prerequisite = Prerequisite::new("core".to_string());
+        let topics = Topics::new("core");
+
+        prerequisite
+            .update(&topics.state, state(PowerState::On))
+            .unwrap();
+        assert!(prerequisite.state_received());
+        assert_eq!(prerequisite.violation(), None);
+
+        prerequisite
+            .update(&topics.state, state(PowerState::Off))
+            .unwrap();
+        assert!(prerequisite.violation().is_some());
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn is_violated_while_panicking() {
+        let mut prerequisite = Prerequisite::new("core".to_string());
+        let topics = Topics::new("core");
+        prerequisite
+            .update(&topics.state, state(PowerState::On))
+            .unwrap();
+
+        prerequisite
+            .update(&topics.status, status(Status::Panicking))
+            .unwrap();
+        assert_eq!(
+            prerequisite.violation(),
+            Some("prerequisite 'core' is panicking".to_string())
+        );
+
+        prerequisite
+            .update(&topics.status, status(Status::Running))
+            .unwrap();
+        assert_eq!(prerequisite.violation(), None);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn only_owns_its_state_and_status_topics() {
+        let prerequisite = Prerequisite::new("core".to_string());
+        let topics = Topics::new("core");
+        let other = Topics::new("io");
+
+        assert!(prerequisite.owns_topic(&topics.state));
+        assert!(prerequisite.owns_topic(&topics.status));
+        assert!(!prerequisite.owns_topic(&topics.state_cmd));
+        assert!(!prerequisite.owns_topic(&other.state));
+        assert_eq!(prerequisite.vec_sub(), vec![topics.state, topics.status]);
+    }
+
+    // --------------------------------------------------------------------------------
+
+    #[test]
+    fn rejects_invalid_payloads() {
+        let mut prerequisite = Prerequisite::new("core".to_string());
+        let topics = Topics::new("core");
+
+        assert!(prerequisite
+            .update(&topics.state, Bytes::from_static(b"not json"))
+            .is_err());
+        assert!(!prerequisite.state_received());
+    }
+}
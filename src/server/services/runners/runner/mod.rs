@@ -1,6 +1,17 @@
+mod interlock;
+
+use super::persistence::PersistedState;
+use super::persistence::StateStore;
+use crate::server::config::RestorePolicy;
+use crate::server::config::ServerConfig;
 use crate::server::drivers::PowerSupplyDriver;
+use crate::server::metrics::Metrics;
+use crate::server::metrics::RunnerMetrics;
 use bytes::Bytes;
+use interlock::Prerequisite;
 use pza_power_supply_client::payload::CurrentPayload;
+use pza_power_supply_client::payload::ErrorPayload;
+use pza_power_supply_client::payload::EstopPayload;
 use pza_power_supply_client::payload::PowerState;
 use pza_power_supply_client::payload::PowerStatePayload;
 use pza_power_supply_client::payload::Status;
@@ -12,10 +23,19 @@ use pza_power_supply_client::SERVER_TYPE_NAME;
 use pza_toolkit::rumqtt::client::init_client;
 use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tracing::error;
+use tracing::info;
 use tracing::trace;
+use tracing::warn;
+
+/// Default period between two measurements of the output
+const DEFAULT_MEASURE_PERIOD_MS: u64 = 1000;
+
+/// Maximum time to wait for the state of the prerequisites before restoring the output
+const PREREQUISITES_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// MQTT Runner for handling power supply commands and measurements
 pub struct Runner {
@@ -26,11 +46,29 @@ pub struct Runner {
 
     status: Option<Status>,
 
+    /// True while the emergency stop is latched, ON commands are rejected
+    estop_latched: bool,
+
     /// Driver Runner
     driver: Arc<Mutex<dyn PowerSupplyDriver + Send + Sync>>,
 
     /// MQTT topics used by the runner
     topics: Topics,
+
+    /// Period between two measurements of the output
+    measure_period: Duration,
+
+    /// Runners that must be ON for this runner to be enabled
+    prerequisites: Vec<Prerequisite>,
+
+    /// Policy applied to the persisted state on startup
+    restore_policy: RestorePolicy,
+
+    /// Store of the persisted runners states
+    state_store: Arc<Mutex<StateStore>>,
+
+    /// Prometheus metrics of the runner
+    metrics: RunnerMetrics,
 }
 
 impl Runner {
@@ -41,6 +79,10 @@ impl Runner {
         name: String,
 
         driver: Arc<Mutex<dyn PowerSupplyDriver + Send + Sync>>,
+
+        server_config: ServerConfig,
+
+        state_store: Arc<Mutex<StateStore>>,
     ) -> anyhow::Result<JoinHandle<Result<(), anyhow::Error>>> {
         let (client, event_loop) = init_client("tttt");
 
@@ -51,13 +93,57 @@ impl Runner {
             format!("{}/{}", SERVER_TYPE_NAME, name),
         );
 
+        // Get the runner specific configuration
+        let runner_config = server_config
+            .runners
+            .as_ref()
+            .and_then(|runners| runners.get(&name));
+        let measure_period_ms = runner_config
+            .and_then(|config| config.measure_period_ms)
+            .unwrap_or(DEFAULT_MEASURE_PERIOD_MS);
+        let restore_policy = runner_config
+            .and_then(|config| config.restore.clone())
+            .unwrap_or_default();
+        let prerequisites = server_config
+            .interlocks
+            .as_ref()
+            .and_then(|interlocks| interlocks.get(&name))
+            .map(|interlock| {
+                interlock
+                    .requires
+                    .iter()
+                    .cloned()
+                    .map(Prerequisite::new)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // The emergency stop latch survives restarts, it is only released by a reset command
+        let estop_latched = state_store
+            .lock()
+            .await
+            .get(&name)
+            .is_some_and(|state| state.estop_latched);
+        if estop_latched {
+            warn!(
+                "[{}] Emergency stop still latched from the persisted state",
+                name
+            );
+        }
+
         // Create runner object
         let runner = Runner {
             status: None,
+            estop_latched,
             topics: Topics::new(&name),
             name: name.clone(),
             driver,
             client: custom_client,
+            measure_period: Duration::from_millis(measure_period_ms),
+            prerequisites,
+            restore_policy,
+            state_store,
+            metrics: Metrics::global().runner(&name),
         };
 
         Ok(tokio::spawn(Self::task_loop(event_loop, runner)))
@@ -68,6 +154,7 @@ impl Runner {
     /// Move to a new status and publish the update
     async fn move_to_status(&mut self, status: Status, panic_message: Option<String>) {
         // Update internal status
+        self.metrics.set_status(&status);
         self.status = Some(status);
 
         // Prepare status payload
@@ -93,33 +180,145 @@ impl Runner {
         // Move to initializing status
         runner.move_to_status(Status::Initializing, None).await;
 
-        // Subscribe to all relevant topics
-        runner
-            .client
-            .subscribe_to_all(runner.topics.vec_sub_server())
-            .await;
+        // Subscribe to all relevant topics, including the prerequisites of the interlock
+        let mut topics = runner.topics.vec_sub_server();
+        for prerequisite in &runner.prerequisites {
+            topics.extend(prerequisite.vec_sub());
+        }
+        runner.client.subscribe_to_all(topics).await;
+
+        // The interlock must know its prerequisites before an output is restored ON
+        let pending = runner.wait_prerequisites(&mut event_loop).await;
 
         runner.initialize().await.unwrap();
 
         // Move to initializing status
         runner.move_to_status(Status::Running, None).await;
 
+        // Handle the commands received while waiting for the prerequisites
+        for packet in pending {
+            runner
+                .handle_incoming_message(&packet.topic, packet.payload, packet.retain)
+                .await;
+        }
+
+        // Periodic measurement of the output
+        let mut measure_interval = tokio::time::interval(runner.measure_period);
+
         loop {
-            while let Ok(event) = event_loop.poll().await {
-                match event {
-                    rumqttc::Event::Incoming(incoming) => match incoming {
-                        rumqttc::Packet::Publish(packet) => {
-                            let topic = packet.topic;
-                            let payload = packet.payload;
-                            trace!("[{}] Received message on topic: {}", runner.name, topic);
-                            runner.handle_incoming_message(&topic, payload).await;
+            tokio::select! {
+                event = event_loop.poll() => {
+                    match event {
+                        Ok(rumqttc::Event::Incoming(incoming)) => match incoming {
+                            rumqttc::Packet::Publish(packet) => {
+                                let topic = packet.topic;
+                                let payload = packet.payload;
+                                trace!("[{}] Received message on topic: {}", runner.name, topic);
+                                runner.handle_incoming_message(&topic, payload, packet.retain).await;
+                            }
+                            _ => {}
+                        },
+                        Ok(rumqttc::Event::Outgoing(_outgoing)) => {}
+                        Err(_) => {}
+                    }
+                }
+                _ = measure_interval.tick() => {
+                    if let Err(e) = runner.publish_measurements().await {
+                        error!("[{}] Failed to measure output: {}", runner.name, e);
+                    }
+                }
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Wait for the output state of the prerequisites of the interlock
+    ///
+    /// Only needed when the `restore` policy may enable the output on startup:
+    /// the retained states, or the ones published by the prerequisite runners
+    /// while they initialize, are received up to `PREREQUISITES_TIMEOUT`.
+    /// Other messages received meanwhile are returned to be handled later.
+    async fn wait_prerequisites(
+        &mut self,
+        event_loop: &mut rumqttc::EventLoop,
+    ) -> Vec<rumqttc::Publish> {
+        let mut pending = Vec::new();
+        let restores_on = self.restore_policy == RestorePolicy::Restore
+            && self
+                .state_store
+                .lock()
+                .await
+                .get(&self.name)
+                .is_some_and(|state| state.enabled);
+        if !restores_on || self.prerequisites.is_empty() {
+            return pending;
+        }
+
+        let deadline = tokio::time::Instant::now() + PREREQUISITES_TIMEOUT;
+        while !self.prerequisites.iter().all(|p| p.state_received()) {
+            match tokio::time::timeout_at(deadline, event_loop.poll()).await {
+                Ok(Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(packet)))) => {
+                    match self
+                        .prerequisites
+                        .iter_mut()
+                        .find(|p| p.owns_topic(&packet.topic))
+                    {
+                        Some(prerequisite) => {
+                            if let Err(e) = prerequisite.update(&packet.topic, packet.payload) {
+                                warn!(
+                                    "[{}] Invalid update from prerequisite '{}': {}",
+                                    self.name, prerequisite.name, e
+                                );
+                            }
                         }
-                        _ => {}
-                    },
-                    rumqttc::Event::Outgoing(_outgoing) => {}
+                        None => pending.push(packet),
+                    }
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => {
+                    warn!(
+                        "[{}] MQTT error while waiting for the interlock: {}",
+                        self.name, e
+                    );
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                Err(_) => {
+                    warn!(
+                        "[{}] Prerequisites state not received, the output is restored OFF",
+                        self.name
+                    );
+                    break;
                 }
             }
         }
+        pending
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Measure the output voltage and current and publish them
+    async fn publish_measurements(&self) -> anyhow::Result<()> {
+        let (voltage, current) = {
+            let mut driver = self.driver.lock().await;
+            (
+                driver.measure_voltage().await?,
+                driver.measure_current().await?,
+            )
+        };
+        self.metrics.set_measurements(&voltage, &current);
+
+        let voltage_payload = VoltagePayload::from_string(voltage).to_json_bytes()?;
+        self.client
+            .pubsh(&self.topics.measure_voltage, voltage_payload)
+            .await?;
+
+        let current_payload = CurrentPayload::from_string(current).to_json_bytes()?;
+        self.client
+            .pubsh(&self.topics.measure_current, current_payload)
+            .await?;
+
+        Ok(())
     }
 
     // --------------------------------------------------------------------------------
@@ -130,15 +329,8 @@ impl Runner {
         let mut driver = self.driver.lock().await;
         driver.initialize().await?;
 
-        // Publish initial output enable state
-        let oe_value = driver.output_enabled().await?;
-        let state_payload = PowerStatePayload::from_state(if oe_value {
-            PowerState::On
-        } else {
-            PowerState::Off
-        })
-        .to_json_bytes()?;
-        self.client.pubsh(&self.topics.state, state_payload).await?;
+        // Restore the persisted setpoints according to the runner policy
+        let restore_output = self.restore_persisted_setpoints(&mut *driver).await;
 
         // Get and check initial voltage setting
         let mut voltage = driver.get_voltage().await?;
@@ -166,7 +358,7 @@ impl Runner {
             }
         }
 
-        let voltage_payload = VoltagePayload::from_string(voltage).to_json_bytes()?;
+        let voltage_payload = VoltagePayload::from_string(voltage.clone()).to_json_bytes()?;
         self.client
             .pubsh(&self.topics.voltage, voltage_payload)
             .await?;
@@ -197,22 +389,178 @@ impl Runner {
             }
         }
 
-        let current_payload = CurrentPayload::from_string(current).to_json_bytes()?;
+        let current_payload = CurrentPayload::from_string(current.clone()).to_json_bytes()?;
         self.client
             .pubsh(&self.topics.current, current_payload)
             .await?;
 
+        // The output is only restored once the setpoints are known to be within the limits
+        if let Some(enable) = restore_output {
+            self.restore_persisted_output(&mut *driver, enable).await;
+        }
+
+        // A latched emergency stop keeps the output OFF whatever the driver defaults
+        if self.estop_latched {
+            driver.disable_output().await?;
+        }
+
+        // Publish initial output enable state
+        let oe_value = driver.output_enabled().await?;
+        let state_payload = PowerStatePayload::from_state(if oe_value {
+            PowerState::On
+        } else {
+            PowerState::Off
+        })
+        .to_json_bytes()?;
+        self.client.pubsh(&self.topics.state, state_payload).await?;
+
+        // Record the initial state in the metrics
+        self.metrics.set_output(oe_value);
+        self.metrics.set_voltage(&voltage);
+        self.metrics.set_current(&current);
+        self.metrics.set_estop(self.estop_latched);
+
+        // Publish initial emergency stop latch state
+        let estop_payload = EstopPayload::from_latched(self.estop_latched).to_json_bytes()?;
+        self.client.pubsh(&self.topics.estop, estop_payload).await?;
+
+        // Persist the state actually applied on the device
+        self.persist(|state| {
+            state.enabled = oe_value;
+            state.voltage = Some(voltage);
+            state.current = Some(current);
+        })
+        .await;
+
         Ok(())
     }
 
     // --------------------------------------------------------------------------------
 
+    /// Apply the persisted setpoints according to the restore policy
+    ///
+    /// A setpoint outside the security limits is not applied. Returns the output
+    /// state to restore, None if the output must be left as it is. The output is
+    /// only restored ON if every persisted setpoint has been applied. Failures
+    /// are only logged, the runner then keeps the driver defaults.
+    async fn restore_persisted_setpoints(
+        &self,
+        driver: &mut (dyn PowerSupplyDriver + Send + Sync),
+    ) -> Option<bool> {
+        if self.restore_policy == RestorePolicy::Defaults {
+            return None;
+        }
+        let state = self.state_store.lock().await.get(&self.name)?;
+        info!("[{}] Restoring persisted state: {:?}", self.name, state);
+
+        // Setpoints first, the output must never be enabled with stale values
+        let mut restored = true;
+        if let Some(voltage) = state.voltage {
+            let limits = (driver.security_min_voltage(), driver.security_max_voltage());
+            let result = if within_limits(&voltage, limits) {
+                driver.set_voltage(voltage).await
+            } else {
+                Err(anyhow::anyhow!(
+                    "{} V is outside the security limits",
+                    voltage
+                ))
+            };
+            if let Err(e) = result {
+                warn!("[{}] Failed to restore voltage: {}", self.name, e);
+                restored = false;
+            }
+        }
+        if let Some(current) = state.current {
+            let limits = (driver.security_min_current(), driver.security_max_current());
+            let result = if within_limits(&current, limits) {
+                driver.set_current(current).await
+            } else {
+                Err(anyhow::anyhow!(
+                    "{} A is outside the security limits",
+                    current
+                ))
+            };
+            if let Err(e) = result {
+                warn!("[{}] Failed to restore current: {}", self.name, e);
+                restored = false;
+            }
+        }
+
+        let enable = self.restore_policy == RestorePolicy::Restore && state.enabled;
+        if enable && !restored {
+            warn!(
+                "[{}] Output not restored ON, the setpoints were not restored",
+                self.name
+            );
+        }
+        Some(enable && restored)
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Apply the persisted output state
+    ///
+    /// The output is never enabled while the emergency stop is latched or the
+    /// interlock is not satisfied. Failures are only logged.
+    async fn restore_persisted_output(
+        &self,
+        driver: &mut (dyn PowerSupplyDriver + Send + Sync),
+        enable: bool,
+    ) {
+        let result = if enable && self.estop_latched {
+            warn!(
+                "[{}] Output not restored ON, emergency stop latched",
+                self.name
+            );
+            driver.disable_output().await
+        } else if enable {
+            match self.interlock_violation() {
+                Some(violation) => {
+                    warn!(
+                        "[{}] Output not restored ON, interlock: {}",
+                        self.name, violation
+                    );
+                    driver.disable_output().await
+                }
+                None => driver.enable_output().await,
+            }
+        } else {
+            driver.disable_output().await
+        };
+        if let Err(e) = result {
+            warn!("[{}] Failed to restore output state: {}", self.name, e);
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Update the persisted state of the runner, saved to the file in the background
+    async fn persist<F: FnOnce(&mut PersistedState)>(&self, update: F) {
+        self.state_store.lock().await.update(&self.name, update);
+    }
+
+    // --------------------------------------------------------------------------------
+
     /// Handle output enable/disable commands
     async fn handle_state_command(&self, payload: Bytes) -> anyhow::Result<()> {
         // Deserialize the command payload
         let cmd = PowerStatePayload::from_json_bytes(payload)?;
         trace!("[{}] Handling state command: {:?}", self.name, cmd.state);
 
+        // Refuse to power up while the emergency stop is latched
+        if cmd.state == PowerState::On && self.estop_latched {
+            return Err(anyhow::anyhow!(
+                "Emergency stop is latched, send a reset command before enabling the output"
+            ));
+        }
+
+        // Refuse to power up while a prerequisite of the interlock is not satisfied
+        if cmd.state == PowerState::On {
+            if let Some(violation) = self.interlock_violation() {
+                return Err(anyhow::anyhow!("Interlock violation: {}", violation));
+            }
+        }
+
         // Handle ON/OFF payload
         let mut driver = self.driver.lock().await;
         if cmd.state == PowerState::On {
@@ -223,6 +571,8 @@ impl Runner {
 
         // Read back the actual output enable state to confirm
         let oe_value = driver.output_enabled().await?;
+        self.metrics.set_output(oe_value);
+        self.persist(|state| state.enabled = oe_value).await;
         let payload_back = PowerStatePayload::from_state_as_response(
             if oe_value {
                 PowerState::On
@@ -255,6 +605,9 @@ impl Runner {
 
         // Read back the actual set voltage to confirm
         let voltage = driver.get_voltage().await?;
+        self.metrics.set_voltage(&voltage);
+        self.persist(|state| state.voltage = Some(voltage.clone()))
+            .await;
         let payload_back =
             VoltagePayload::from_voltage_as_response(voltage, cmd.pza_id).to_json_bytes()?;
 
@@ -280,6 +633,9 @@ impl Runner {
 
         // Read back the actual set current to confirm
         let current = driver.get_current().await?;
+        self.metrics.set_current(&current);
+        self.persist(|state| state.current = Some(current.clone()))
+            .await;
         let payload_back =
             CurrentPayload::from_current_as_response(current, cmd.pza_id).to_json_bytes()?;
 
@@ -293,6 +649,107 @@ impl Runner {
 
     // --------------------------------------------------------------------------------
 
+    /// Handle emergency stop commands (runner specific or server-wide)
+    ///
+    /// A latched command disables the output immediately and locks the runner,
+    /// an unlatched command resets the lock. The latch state is always published.
+    async fn handle_estop_command(&mut self, payload: Bytes) -> anyhow::Result<()> {
+        // Deserialize the command payload
+        let cmd = EstopPayload::from_json_bytes(payload)?;
+        trace!("[{}] Handling estop command: {}", self.name, cmd.latched);
+
+        if cmd.latched {
+            // Latch first so the lock holds even if the driver fails or the server restarts
+            self.estop_latched = true;
+            self.persist(|state| state.estop_latched = true).await;
+            self.metrics.set_estop(true);
+            self.metrics.protection_trip("estop");
+            error!("[{}] Emergency stop latched", self.name);
+
+            // Kill the output and publish the state read back from the device
+            let mut driver = self.driver.lock().await;
+            driver.disable_output().await?;
+            let oe_value = driver.output_enabled().await?;
+            self.metrics.set_output(oe_value);
+            self.persist(|state| state.enabled = oe_value).await;
+            let state_payload = PowerStatePayload::from_state(if oe_value {
+                PowerState::On
+            } else {
+                PowerState::Off
+            })
+            .to_json_bytes()?;
+            self.client.pubsh(&self.topics.state, state_payload).await?;
+        } else {
+            self.estop_latched = false;
+            self.persist(|state| state.estop_latched = false).await;
+            self.metrics.set_estop(false);
+            info!("[{}] Emergency stop reset", self.name);
+        }
+
+        // Confirm the latch state by publishing it
+        let payload_back = EstopPayload::from_latched_as_response(self.estop_latched, cmd.pza_id)
+            .to_json_bytes()?;
+        self.client.pubsh(&self.topics.estop, payload_back).await?;
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Describe the first unsatisfied prerequisite of the interlock, None if all are satisfied
+    fn interlock_violation(&self) -> Option<String> {
+        self.prerequisites.iter().find_map(|p| p.violation())
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Handle state and status updates of a prerequisite runner
+    ///
+    /// If a prerequisite is no longer satisfied while the output is enabled,
+    /// the output is disabled and the violation is published on the error topic.
+    async fn handle_prerequisite_update(
+        &mut self,
+        index: usize,
+        topic: &str,
+        payload: Bytes,
+    ) -> anyhow::Result<()> {
+        self.prerequisites[index].update(topic, payload)?;
+
+        let violation = match self.prerequisites[index].violation() {
+            Some(violation) => violation,
+            None => return Ok(()),
+        };
+
+        // Nothing to do if the output is already disabled
+        let mut driver = self.driver.lock().await;
+        if !driver.output_enabled().await? {
+            return Ok(());
+        }
+
+        // Shut down the output and publish the state read back from the device
+        driver.disable_output().await?;
+        let oe_value = driver.output_enabled().await?;
+        self.metrics.set_output(oe_value);
+        self.metrics.protection_trip("interlock");
+        self.persist(|state| state.enabled = oe_value).await;
+        let state_payload = PowerStatePayload::from_state(if oe_value {
+            PowerState::On
+        } else {
+            PowerState::Off
+        })
+        .to_json_bytes()?;
+        self.client.pubsh(&self.topics.state, state_payload).await?;
+
+        // Report the violation
+        let message = format!("Interlock violation: {}, output disabled", violation);
+        error!("[{}] {}", self.name, message);
+        let error_payload = ErrorPayload::from_message(message).to_json_bytes()?;
+        self.client.pubsh(&self.topics.error, error_payload).await?;
+        self.metrics.error("interlock");
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+
     /// Handle error and send error response via MQTT
     async fn handle_command_error(
         &self,
@@ -311,6 +768,7 @@ impl Runner {
         };
 
         // Prepare and send error response
+        self.metrics.error(command_type);
         let error_payload =
             pza_power_supply_client::payload::ErrorPayload::from_message_as_response(
                 format!("Invalid {} command payload: {}", command_type, error),
@@ -333,24 +791,61 @@ impl Runner {
     // --------------------------------------------------------------------------------
 
     /// Handle incoming MQTT messages
-    async fn handle_incoming_message(&self, topic: &String, payload: Bytes) {
+    ///
+    /// `retained` is true for the messages replayed by the broker on subscription.
+    async fn handle_incoming_message(&mut self, topic: &String, payload: Bytes, retained: bool) {
+        // Updates from the prerequisites of the interlock
+        if let Some(index) = self.prerequisites.iter().position(|p| p.owns_topic(topic)) {
+            if let Err(e) = self.handle_prerequisite_update(index, topic, payload).await {
+                error!(
+                    "[{}] Error handling interlock update from '{}': {}",
+                    self.name, self.prerequisites[index].name, e
+                );
+            }
+            return;
+        }
+
         let id = self.topics.topic_to_id(topic);
 
+        // The latency of a command is measured until its acknowledgement or rejection
         match id {
+            Some(TopicId::StateCmd)
+            | Some(TopicId::VoltageCmd)
+            | Some(TopicId::CurrentCmd)
+            | Some(TopicId::EstopCmd)
+            | Some(TopicId::AllEstop)
+                if retained =>
+            {
+                // A stale command must not override the restore policy nor the persisted latch
+                warn!("[{}] Ignoring retained command on {}", self.name, topic);
+            }
             Some(TopicId::StateCmd) => {
+                let latency = self.metrics.command("state");
                 if let Err(e) = self.handle_state_command(payload.clone()).await {
                     self.handle_command_error(e, &payload, "state").await;
                 }
+                latency.observe_duration();
             }
             Some(TopicId::VoltageCmd) => {
+                let latency = self.metrics.command("voltage");
                 if let Err(e) = self.handle_voltage_command(payload.clone()).await {
                     self.handle_command_error(e, &payload, "voltage").await;
                 }
+                latency.observe_duration();
             }
             Some(TopicId::CurrentCmd) => {
+                let latency = self.metrics.command("current");
                 if let Err(e) = self.handle_current_command(payload.clone()).await {
                     self.handle_command_error(e, &payload, "current").await;
                 }
+                latency.observe_duration();
+            }
+            Some(TopicId::EstopCmd) | Some(TopicId::AllEstop) => {
+                let latency = self.metrics.command("estop");
+                if let Err(e) = self.handle_estop_command(payload.clone()).await {
+                    self.handle_command_error(e, &payload, "estop").await;
+                }
+                latency.observe_duration();
             }
             _ => {
                 // Unknown or unhandled topic
@@ -363,3 +858,13 @@ impl Runner {
         }
     }
 }
+
+// ================
+
+/// Check that a setpoint is a number within the given (min, max) security limits
+fn within_limits(value: &str, (min, max): (Option<f32>, Option<f32>)) -> bool {
+    match value.parse::<f32>() {
+        Ok(value) => min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max),
+        Err(_) => false,
+    }
+}
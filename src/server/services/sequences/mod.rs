@@ -0,0 +1,512 @@
+use bytes::Bytes;
+use pza_power_supply_client::payload::ErrorPayload;
+use pza_power_supply_client::payload::SequenceAction;
+use pza_power_supply_client::payload::SequencePayload;
+use pza_power_supply_client::payload::SequenceState;
+use pza_power_supply_client::payload::SequenceStatusPayload;
+use pza_power_supply_client::PowerSupplyClient;
+use pza_power_supply_client::SequenceTopics;
+use pza_power_supply_client::SERVER_TYPE_NAME;
+use pza_toolkit::rumqtt::client::init_client;
+use pza_toolkit::rumqtt::client::RumqttCustomAsyncClient;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::Mutex;
+use tokio::task::AbortHandle;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::error;
+use tracing::info;
+use tracing::trace;
+use tracing::warn;
+
+use crate::server::config::SequenceConfig;
+use crate::server::config::SequenceStepConfig;
+use crate::server::config::ServerConfig;
+
+/// Maximum time to wait for a runner to acknowledge a command
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default maximum time to wait for a measured voltage condition
+const DEFAULT_WAIT_TIMEOUT_MS: u64 = 5000;
+
+/// Execution of a sequence command in progress
+struct RunningSequence {
+    /// Action being executed
+    action: SequenceAction,
+
+    /// Identifier of the command being executed
+    pza_id: String,
+
+    /// Handle to cancel the execution task
+    abort_handle: AbortHandle,
+}
+
+/// Service executing power sequences across multiple runners
+#[derive(Clone)]
+pub struct SequencesService {
+    /// MQTT client used to publish sequence status and errors
+    client: RumqttCustomAsyncClient,
+
+    /// Sequence configurations, keyed by their names
+    sequences: HashMap<String, SequenceConfig>,
+
+    /// MQTT topics of each sequence, keyed by their names
+    topics: HashMap<String, SequenceTopics>,
+
+    /// Power supply clients of the runners involved in sequences
+    psu_clients: HashMap<String, PowerSupplyClient>,
+
+    /// Sequences currently executing, keyed by their names
+    running: Arc<Mutex<HashMap<String, RunningSequence>>>,
+}
+
+impl SequencesService {
+    // --------------------------------------------------------------------------------
+
+    /// Start the sequences service
+    pub async fn start(
+        server_config: ServerConfig,
+    ) -> anyhow::Result<JoinHandle<Result<(), anyhow::Error>>> {
+        let sequences = server_config.sequences.clone().unwrap_or_default();
+        let runner_names = server_config.runner_names();
+
+        // Create one client per runner involved in a sequence
+        let mut psu_clients = HashMap::new();
+        for (name, sequence) in &sequences {
+            for step in &sequence.steps {
+                if !runner_names.contains(&step.runner) {
+                    return Err(anyhow::anyhow!(
+                        "Sequence '{}' references unknown runner '{}'",
+                        name,
+                        step.runner
+                    ));
+                }
+                if !psu_clients.contains_key(&step.runner) {
+                    let psu_client = PowerSupplyClient::builder()
+                        .with_ip(server_config.broker.tcp.clone().unwrap())
+                        .with_power_supply_name(step.runner.clone())
+                        .build()?;
+                    psu_clients.insert(step.runner.clone(), psu_client);
+                }
+            }
+        }
+
+        let (client, event_loop) = init_client("sequences");
+
+        let custom_client = RumqttCustomAsyncClient::new(
+            client,
+            rumqttc::QoS::AtMostOnce,
+            true,
+            format!("{}/sequence", SERVER_TYPE_NAME),
+        );
+
+        // Create service object
+        let service = SequencesService {
+            client: custom_client,
+            topics: sequences
+                .keys()
+                .map(|name| (name.clone(), SequenceTopics::new(name)))
+                .collect(),
+            sequences,
+            psu_clients,
+            running: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        Ok(tokio::spawn(Self::task_loop(event_loop, service)))
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// The main async task loop for the sequences service
+    async fn task_loop(
+        mut event_loop: rumqttc::EventLoop,
+        service: SequencesService,
+    ) -> anyhow::Result<()> {
+        // Publish initial state of each sequence
+        for name in service.sequences.keys() {
+            service
+                .publish_status(
+                    name,
+                    SequenceStatusPayload::from_state_as_response(
+                        SequenceState::Idle,
+                        pza_power_supply_client::payload::generate_pza_id(),
+                    ),
+                )
+                .await;
+        }
+
+        // Subscribe to all command topics
+        service
+            .client
+            .subscribe_to_all(service.topics.values().map(|t| t.cmd.clone()).collect())
+            .await;
+
+        loop {
+            while let Ok(event) = event_loop.poll().await {
+                match event {
+                    rumqttc::Event::Incoming(incoming) => match incoming {
+                        rumqttc::Packet::Publish(packet) => {
+                            let topic = packet.topic;
+                            let payload = packet.payload;
+                            trace!("[sequences] Received message on topic: {}", topic);
+                            if packet.retain {
+                                // A stale command replayed by the broker must not run a sequence
+                                warn!("[sequences] Ignoring retained command on {}", topic);
+                                continue;
+                            }
+                            service.handle_incoming_message(&topic, payload).await;
+                        }
+                        _ => {}
+                    },
+                    rumqttc::Event::Outgoing(_outgoing) => {}
+                }
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Handle incoming MQTT messages
+    async fn handle_incoming_message(&self, topic: &String, payload: Bytes) {
+        let name = self
+            .topics
+            .iter()
+            .find(|(_, topics)| &topics.cmd == topic)
+            .map(|(name, _)| name.clone());
+
+        match name {
+            Some(name) => {
+                if let Err(e) = self.handle_sequence_command(&name, payload.clone()).await {
+                    self.handle_command_error(&name, e, &payload).await;
+                }
+            }
+            None => {
+                // Unknown or unhandled topic
+                trace!("[sequences] Received message on unhandled topic: {}", topic);
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Handle a sequence command by spawning its execution
+    ///
+    /// A DOWN command preempts a running UP: the power-up is cancelled and reported
+    /// as aborted, then the group is powered down. Any other command is refused
+    /// while the sequence is running.
+    async fn handle_sequence_command(&self, name: &str, payload: Bytes) -> anyhow::Result<()> {
+        // Deserialize the command payload
+        let cmd = SequencePayload::from_json_bytes(payload)?;
+        trace!("[sequences] Handling '{}' command: {:?}", name, cmd.action);
+
+        // Register the execution before spawning it, under the same lock
+        let mut running = self.running.lock().await;
+        if let Some(current) = running.get(name) {
+            if current.action == SequenceAction::Up && cmd.action == SequenceAction::Down {
+                current.abort_handle.abort();
+                info!(
+                    "[sequences] Power-up of '{}' preempted by a power-down",
+                    name
+                );
+                let message = "Power-up preempted by a power-down".to_string();
+                self.publish_status(
+                    name,
+                    SequenceStatusPayload::from_state_as_response(
+                        SequenceState::Aborted,
+                        current.pza_id.clone(),
+                    )
+                    .with_message(message.clone()),
+                )
+                .await;
+                self.publish_error(name, message, current.pza_id.clone())
+                    .await;
+            } else {
+                return Err(anyhow::anyhow!("Sequence '{}' is already running", name));
+            }
+        }
+
+        let service = self.clone();
+        let task_name = name.to_string();
+        let action = cmd.action.clone();
+        let pza_id = cmd.pza_id.clone();
+        let handle = tokio::spawn(async move {
+            let pza_id = cmd.pza_id.clone();
+            service.execute(&task_name, cmd).await;
+
+            // Only forget the execution if it has not been replaced meanwhile
+            let mut running = service.running.lock().await;
+            if running.get(&task_name).is_some_and(|r| r.pza_id == pza_id) {
+                running.remove(&task_name);
+            }
+        });
+        running.insert(
+            name.to_string(),
+            RunningSequence {
+                action,
+                pza_id,
+                abort_handle: handle.abort_handle(),
+            },
+        );
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Execute a sequence command and publish its progress
+    ///
+    /// If any step fails, the whole group is powered down and the sequence is aborted.
+    async fn execute(&self, name: &str, cmd: SequencePayload) {
+        let sequence = match self.sequences.get(name) {
+            Some(sequence) => sequence,
+            None => return,
+        };
+
+        let (running_state, done_state) = match cmd.action {
+            SequenceAction::Up => (SequenceState::PoweringUp, SequenceState::Up),
+            SequenceAction::Down => (SequenceState::PoweringDown, SequenceState::Down),
+        };
+        self.publish_status(
+            name,
+            SequenceStatusPayload::from_state_as_response(running_state, cmd.pza_id.clone()),
+        )
+        .await;
+
+        info!("[sequences] Executing '{}' {:?}", name, cmd.action);
+        let result = match cmd.action {
+            SequenceAction::Up => self.power_up(sequence).await,
+            SequenceAction::Down => self.power_down(sequence).await,
+        };
+
+        match result {
+            Ok(()) => {
+                info!("[sequences] Sequence '{}' is {:?}", name, done_state);
+                self.publish_status(
+                    name,
+                    SequenceStatusPayload::from_state_as_response(done_state, cmd.pza_id),
+                )
+                .await;
+            }
+            Err(e) => {
+                error!("[sequences] Sequence '{}' failed: {}", name, e);
+
+                // Power down the whole group after a failure
+                if let Err(down_error) = self.power_down(sequence).await {
+                    error!(
+                        "[sequences] Failed to power down '{}' after abort: {}",
+                        name, down_error
+                    );
+                }
+
+                self.publish_status(
+                    name,
+                    SequenceStatusPayload::from_state_as_response(
+                        SequenceState::Aborted,
+                        cmd.pza_id.clone(),
+                    )
+                    .with_message(e.to_string()),
+                )
+                .await;
+                self.publish_error(name, e.to_string(), cmd.pza_id).await;
+            }
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Bring the rails up in the configured order
+    async fn power_up(&self, sequence: &SequenceConfig) -> anyhow::Result<()> {
+        for step in &sequence.steps {
+            if let Some(delay_ms) = step.delay_ms {
+                sleep(Duration::from_millis(delay_ms)).await;
+            }
+
+            let client = self.psu_client(&step.runner)?;
+            let step_error = |e: anyhow::Error| anyhow::anyhow!("[{}] {}", step.runner, e);
+
+            // Apply setpoints before enabling the output
+            if let Some(voltage) = &step.voltage {
+                client
+                    .set_voltage_wait_ack(voltage.clone(), ACK_TIMEOUT)
+                    .await
+                    .map_err(step_error)?;
+            }
+            if let Some(current) = &step.current {
+                client
+                    .set_current_wait_ack(current.clone(), ACK_TIMEOUT)
+                    .await
+                    .map_err(step_error)?;
+            }
+
+            // Subscribe before enabling so no measurement is missed
+            let measured_rx = client.subscribe_measured_voltage_changes();
+            client
+                .enable_output_wait_ack(ACK_TIMEOUT)
+                .await
+                .map_err(step_error)?;
+
+            if step.wait_voltage.is_some() {
+                Self::wait_voltage(client, step, measured_rx)
+                    .await
+                    .map_err(step_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Tear the rails down in the reverse order
+    ///
+    /// Every step is attempted even if a previous one failed, the first error is returned.
+    async fn power_down(&self, sequence: &SequenceConfig) -> anyhow::Result<()> {
+        let mut first_error = None;
+        let mut previous_delay_ms: Option<u64> = None;
+
+        for step in sequence.steps.iter().rev() {
+            if let Some(delay_ms) = previous_delay_ms {
+                sleep(Duration::from_millis(delay_ms)).await;
+            }
+            previous_delay_ms = step.delay_ms;
+
+            let result = match self.psu_client(&step.runner) {
+                Ok(client) => client.disable_output_wait_ack(ACK_TIMEOUT).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error!("[sequences] Failed to disable '{}': {}", step.runner, e);
+                first_error.get_or_insert(anyhow::anyhow!("[{}] {}", step.runner, e));
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Wait until the measured voltage is within tolerance of the setpoint
+    async fn wait_voltage(
+        client: &PowerSupplyClient,
+        step: &SequenceStepConfig,
+        mut measured_rx: broadcast::Receiver<Arc<pza_power_supply_client::payload::VoltagePayload>>,
+    ) -> anyhow::Result<()> {
+        let wait = match &step.wait_voltage {
+            Some(wait) => wait,
+            None => return Ok(()),
+        };
+
+        // Target is the step setpoint, or the current runner setpoint
+        let setpoint = match &step.voltage {
+            Some(voltage) => voltage.clone(),
+            None => client.get_voltage().await,
+        };
+        let target: f32 = setpoint
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid voltage setpoint: {}", setpoint))?;
+
+        let timeout_ms = wait.timeout_ms.unwrap_or(DEFAULT_WAIT_TIMEOUT_MS);
+        let result = tokio::time::timeout(Duration::from_millis(timeout_ms), async {
+            loop {
+                match measured_rx.recv().await {
+                    Ok(payload) => {
+                        if let Ok(measured) = payload.voltage.parse::<f32>() {
+                            if (measured - target).abs() <= wait.tolerance {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(e) => {
+                        return Err(anyhow::anyhow!("Measurement channel closed: {}", e));
+                    }
+                }
+            }
+        })
+        .await;
+
+        // Return based on the result
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(anyhow::anyhow!(
+                "Measured voltage did not reach {} V (+/- {} V) within {} ms",
+                target,
+                wait.tolerance,
+                timeout_ms
+            )),
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Get the client of a runner involved in a sequence
+    fn psu_client(&self, runner: &str) -> anyhow::Result<&PowerSupplyClient> {
+        self.psu_clients
+            .get(runner)
+            .ok_or_else(|| anyhow::anyhow!("No client for runner '{}'", runner))
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Publish the status of a sequence
+    async fn publish_status(&self, name: &str, payload: SequenceStatusPayload) {
+        let topics = match self.topics.get(name) {
+            Some(topics) => topics,
+            None => return,
+        };
+        match payload.to_json_bytes() {
+            Ok(bytes) => {
+                if let Err(e) = self.client.pubsh(&topics.status, bytes).await {
+                    error!("[sequences] Failed to publish '{}' status: {}", name, e);
+                }
+            }
+            Err(e) => error!("[sequences] Failed to serialize '{}' status: {}", name, e),
+        }
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Publish an error message on the error topic of a sequence
+    async fn publish_error(&self, name: &str, message: String, pza_id: String) {
+        let topics = match self.topics.get(name) {
+            Some(topics) => topics,
+            None => return,
+        };
+        let error_payload = ErrorPayload::from_message_as_response(message, pza_id)
+            .to_json_bytes()
+            .expect("Failed to serialize error payload");
+
+        self.client
+            .pubsh(&topics.error, error_payload)
+            .await
+            .expect("Failed to publish error payload");
+    }
+
+    // --------------------------------------------------------------------------------
+
+    /// Handle error and send error response via MQTT
+    async fn handle_command_error(&self, name: &str, error: anyhow::Error, payload: &Bytes) {
+        // Try to parse payload as a simple json and try to extract pza_id for error response
+        let pza_id = match serde_json::from_slice::<serde_json::Value>(payload) {
+            Ok(json_value) => json_value
+                .get("pza_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("????")
+                .to_string(),
+            Err(_) => "????".to_string(),
+        };
+
+        error!("[sequences] Error handling '{}' command: {}", name, error);
+        self.publish_error(
+            name,
+            format!("Invalid sequence command payload: {}", error),
+            pza_id,
+        )
+        .await;
+    }
+}
@@ -0,0 +1,146 @@
+//! History of the measurements of a power supply instance
+//!
+//! Samples are timestamped on reception and kept for a limited time, the
+//! widgets render them as sparklines over a selectable window.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Windows of the history the user can select, in order
+pub const HISTORY_WINDOWS: [Duration; 4] = [
+    Duration::from_secs(10),
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(15 * 60),
+];
+
+/// Short label of a window, e.g. `10s` or `5min`
+pub fn window_label(window: Duration) -> String {
+    let seconds = window.as_secs();
+    if seconds >= 60 && seconds % 60 == 0 {
+        format!("{}min", seconds / 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Age after which the samples are dropped
+///
+/// Longer than the largest window so that a frozen view keeps its samples for a while.
+const RETENTION: Duration = Duration::from_secs(30 * 60);
+
+/// Maximum number of samples kept, whatever their age
+const MAX_SAMPLES: usize = 20_000;
+
+/// Statistics of the samples of a window
+#[derive(Clone, Copy, Debug)]
+pub struct HistoryStats {
+    pub min: f32,
+    pub max: f32,
+    pub avg: f32,
+}
+
+/// Part of the history displayed by the widgets
+#[derive(Clone, Copy, Debug)]
+pub struct HistoryView {
+    /// End of the displayed window, the freeze time or now
+    pub end: Instant,
+    /// Duration of the displayed window
+    pub window: Duration,
+    /// Whether the view is frozen by the user
+    pub frozen: bool,
+}
+
+/// Timestamped samples of one measurement
+#[derive(Default)]
+pub struct MeasurementHistory {
+    samples: VecDeque<(Instant, f32)>,
+}
+
+impl MeasurementHistory {
+    // ------------------------------------------------------------------------------
+
+    /// Record a measurement, values that are not numbers are ignored
+    pub fn push(&mut self, value: &str, at: Instant) {
+        let Ok(value) = value.trim().parse::<f32>() else {
+            return;
+        };
+        self.samples.push_back((at, value));
+
+        // Drop the old samples
+        while self.samples.len() > MAX_SAMPLES
+            || self
+                .samples
+                .front()
+                .is_some_and(|(t, _)| at.duration_since(*t) > RETENTION)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Samples inside the window of the view
+    fn in_view(&self, view: &HistoryView) -> impl Iterator<Item = &(Instant, f32)> + '_ {
+        let end = view.end;
+        let window = view.window;
+        self.samples
+            .iter()
+            .filter(move |(t, _)| *t <= end && end.duration_since(*t) <= window)
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Minimum, maximum and average of the samples of the view, None if empty
+    pub fn stats(&self, view: &HistoryView) -> Option<HistoryStats> {
+        let mut count = 0;
+        let mut sum = 0.0;
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+        for (_, value) in self.in_view(view) {
+            count += 1;
+            sum += value;
+            min = min.min(*value);
+            max = max.max(*value);
+        }
+
+        (count > 0).then(|| HistoryStats {
+            min,
+            max,
+            avg: sum / count as f32,
+        })
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Average the samples of the view in `columns` time slots, in milli-units
+    ///
+    /// Empty slots repeat the previous value, so that a slow measurement period
+    /// draws a continuous line.
+    pub fn columns(&self, view: &HistoryView, columns: usize) -> Vec<u64> {
+        if columns == 0 {
+            return Vec::new();
+        }
+
+        let window = view.window.as_secs_f64();
+        let mut sums = vec![(0.0f64, 0u32); columns];
+        for (t, value) in self.in_view(view) {
+            let age = view.end.duration_since(*t).as_secs_f64();
+            let position = ((window - age) / window * columns as f64) as usize;
+            let slot = &mut sums[position.min(columns - 1)];
+            slot.0 += *value as f64;
+            slot.1 += 1;
+        }
+
+        let mut last = 0;
+        sums.into_iter()
+            .map(|(sum, count)| {
+                if count > 0 {
+                    last = (sum / count as f64 * 1000.0).max(0.0) as u64;
+                }
+                last
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,184 @@
+//! Log pane of the TUI
+//!
+//! Displays the tracing events recorded in the log buffer, filtered by level
+//! and optionally by runner.
+
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use tracing::Level;
+
+use crate::server::logs::LogBuffer;
+use crate::server::logs::LogEntry;
+
+/// Minimum levels the user can select, in order
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+/// Color of the level of an event
+fn level_color(level: &Level) -> Color {
+    if *level == Level::ERROR {
+        Color::Red
+    } else if *level == Level::WARN {
+        Color::Yellow
+    } else if *level == Level::INFO {
+        Color::Green
+    } else if *level == Level::DEBUG {
+        Color::Blue
+    } else {
+        Color::Gray
+    }
+}
+
+/// State of the log pane
+pub struct LogPane {
+    /// Whether the pane is displayed
+    visible: bool,
+    /// Index of the minimum displayed level in `LEVELS`
+    level: usize,
+    /// Whether only the events of the selected runner are displayed
+    runner_only: bool,
+    /// Number of newest events scrolled out of the view
+    scroll: usize,
+}
+
+impl Default for LogPane {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            // INFO
+            level: 2,
+            runner_only: false,
+            scroll: 0,
+        }
+    }
+}
+
+impl LogPane {
+    // ------------------------------------------------------------------------------
+
+    /// Whether the pane is displayed
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Show or hide the pane
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.scroll = 0;
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Select the next minimum level, from ERROR to TRACE then back to ERROR
+    pub fn next_level(&mut self) {
+        self.level = (self.level + 1) % LEVELS.len();
+        self.scroll = 0;
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Switch between the events of all the runners and those of the selected one
+    pub fn toggle_runner_filter(&mut self) {
+        self.runner_only = !self.runner_only;
+        self.scroll = 0;
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Scroll towards the older events
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_add(lines);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Scroll towards the newer events
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Check whether an event passes the filters of the pane
+    fn accepts(&self, entry: &LogEntry, selected_runner: Option<&str>) -> bool {
+        if entry.level > LEVELS[self.level] {
+            return false;
+        }
+        match (self.runner_only, selected_runner) {
+            (true, Some(runner)) => entry.concerns(runner),
+            _ => true,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Render the pane, newest events at the bottom
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The ratatui Frame for rendering
+    /// * `area` - The terminal area where the pane should be drawn
+    /// * `selected_runner` - Name of the runner of the selected widget, used by the runner filter
+    pub fn render(&self, f: &mut Frame, area: Rect, selected_runner: Option<&str>) {
+        let runner_text = match (self.runner_only, selected_runner) {
+            (true, Some(runner)) => runner,
+            _ => "all",
+        };
+        let mut title = format!(
+            "Logs [min level: {}] [runner: {}]",
+            LEVELS[self.level], runner_text
+        );
+        if self.scroll > 0 {
+            title.push_str(&format!(" [scrolled {}]", self.scroll));
+        }
+
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(Color::White));
+        let height = block.inner(area).height as usize;
+
+        let mut entries = LogBuffer::global().recent(self.scroll, height, |entry| {
+            self.accepts(entry, selected_runner)
+        });
+        entries.reverse();
+
+        let lines: Vec<Line> = entries
+            .iter()
+            .map(|entry| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", entry.time_of_day()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("{:<5} ", entry.level),
+                        Style::default()
+                            .fg(level_color(&entry.level))
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(entry.message.clone()),
+                ])
+            })
+            .collect();
+
+        f.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
@@ -1,29 +1,37 @@
 /// Terminal User Interface module
 ///
 /// Provides a simple TUI for power supply control and monitoring.
+mod history;
 mod loading;
+mod log_pane;
 mod psi_widget;
+mod setpoint_editor;
 
-use std::any;
 use std::io;
 use std::time::Duration;
+use std::time::Instant;
 
 use crossterm::event;
 use crossterm::event::DisableMouseCapture;
 use crossterm::event::EnableMouseCapture;
 use crossterm::event::Event;
 use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
 use crossterm::execute;
 use crossterm::terminal::disable_raw_mode;
 use crossterm::terminal::enable_raw_mode;
 use crossterm::terminal::EnterAlternateScreen;
 use crossterm::terminal::LeaveAlternateScreen;
+use pza_power_supply_client::client::discover_power_supplies;
 use pza_power_supply_client::PowerSupplyClient;
+use pza_toolkit::config::IPEndpointConfig;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::Alignment;
 use ratatui::layout::Constraint;
 use ratatui::layout::Direction;
 use ratatui::layout::Layout;
+use ratatui::layout::Rect;
 use ratatui::style::Color;
 use ratatui::style::Modifier;
 use ratatui::style::Style;
@@ -33,39 +41,202 @@ use ratatui::widgets::Block;
 use ratatui::widgets::BorderType;
 use ratatui::widgets::Borders;
 use ratatui::widgets::Paragraph;
+use ratatui::Frame;
 use ratatui::Terminal;
 
+use history::window_label;
+use history::HistoryView;
+use history::HISTORY_WINDOWS;
 use loading::LoadingWidget;
+use log_pane::LogPane;
 use psi_widget::PowerSupplyInstanceWidget;
+use setpoint_editor::SetpointKind;
+use setpoint_editor::SetpointLimits;
 use tokio::task::JoinHandle;
+use tracing::error;
+use tracing::info;
+
+use crate::server::config::PowerSupplyConfig;
+use crate::server::config::ServerConfig;
+use crate::server::config::TuiConfig;
+
+/// Maximum number of instance widgets displayed on the same row
+const MAX_WIDGETS_PER_ROW: usize = 3;
+
+/// Number of lines scrolled by the Page Up / Page Down keys in the log pane
+const LOG_SCROLL_LINES: usize = 10;
+
+/// Time given to the runners of a remote broker to report their status
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum time the loading screen waits for the runners to report their status
+const LOADING_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Application state for the TUI
 pub struct TuiService {
     /// Whether the application should quit
     should_quit: bool,
-    //     /// Power supply instance widgets
-    //     widgets: Vec<PowerSupplyInstanceWidget>,
-    //     /// Currently selected widget index
-    //     selected_widget: usize,
-    //     /// TUI configuration
-    //     tui_config: TuiConfig,
+    /// Key that toggles the output of the selected runner
+    power_toggle_key: Option<char>,
+    /// Key that triggers the emergency stop of all runners
+    emergency_stop_key: Option<char>,
+    /// Whether an emergency stop has been requested by the user
+    emergency_stop_requested: bool,
+    /// Power supply instance widgets
+    widgets: Vec<PowerSupplyInstanceWidget>,
+    /// Currently selected widget index
+    selected_widget: usize,
+    /// Index of the displayed history window in `HISTORY_WINDOWS`
+    history_window: usize,
+    /// Time at which the user froze the history view, None when live
+    frozen_at: Option<Instant>,
+    /// Pane displaying the tracing events
+    log_pane: LogPane,
 }
 
 impl TuiService {
     /// Starts the TUI service in a separate task
-    pub fn start() -> JoinHandle<Result<(), anyhow::Error>> {
+    pub fn start(server_config: ServerConfig) -> JoinHandle<Result<(), anyhow::Error>> {
         println!("Starting TUI service...");
-        let handle = tokio::spawn(Self::render_loop());
+        let handle = tokio::spawn(async move {
+            let broker =
+                server_config.broker.tcp.clone().ok_or_else(|| {
+                    anyhow::anyhow!("No TCP endpoint configured for the MQTT broker")
+                })?;
+            let runners = server_config
+                .runner_names()
+                .into_iter()
+                .map(|name| {
+                    let config = server_config
+                        .runners
+                        .as_ref()
+                        .and_then(|runners| runners.get(&name))
+                        .cloned();
+                    (name, config)
+                })
+                .collect();
+
+            TuiService::new(&server_config.tui, runners)
+                .render_loop(broker)
+                .await
+        });
 
         handle
     }
 
     // ------------------------------------------------------------------------------
 
+    /// Run the TUI as a remote console of the power supplies served on a broker
+    ///
+    /// The runners are discovered through their status topics, their security
+    /// limits are unknown and only enforced by the server.
+    pub async fn run_console(
+        broker: IPEndpointConfig,
+        tui_config: TuiConfig,
+    ) -> anyhow::Result<()> {
+        info!("Discovering power supplies...");
+        let names = discover_power_supplies(broker.clone(), DISCOVERY_TIMEOUT).await?;
+        info!("Found {} power supplies: {:?}", names.len(), names);
+
+        let runners = names.into_iter().map(|name| (name, None)).collect();
+        TuiService::new(&tui_config, runners)
+            .render_loop(broker)
+            .await
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Create the TUI application state
+    ///
+    /// One widget is created for each runner, with the security limits of the
+    /// runner configuration when known.
+    fn new(tui_config: &TuiConfig, mut runners: Vec<(String, Option<PowerSupplyConfig>)>) -> Self {
+        runners.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let widgets = runners
+            .into_iter()
+            .map(|(name, runner_config)| {
+                let mut widget = PowerSupplyInstanceWidget::new(name);
+                if let Some(config) = runner_config {
+                    widget.set_limits(
+                        SetpointLimits {
+                            min: config.security_min_voltage,
+                            max: config.security_max_voltage,
+                        },
+                        SetpointLimits {
+                            min: config.security_min_current,
+                            max: config.security_max_current,
+                        },
+                    );
+                }
+                widget
+            })
+            .collect();
+
+        Self {
+            should_quit: false,
+            power_toggle_key: tui_config
+                .power_toggle_key
+                .as_ref()
+                .and_then(|key| key.chars().next()),
+            emergency_stop_key: tui_config
+                .emergency_stop_key
+                .as_ref()
+                .and_then(|key| key.chars().next()),
+            emergency_stop_requested: false,
+            widgets,
+            selected_widget: 0,
+            history_window: 0,
+            frozen_at: None,
+            log_pane: LogPane::default(),
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Initialize clients for all widgets
+    ///
+    /// Creates and assigns MQTT clients to each power supply instance widget.
+    /// This establishes the communication channel between the TUI and the power supplies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PowerSupplyClient cannot be built for any instance.
+    async fn initialize_clients(&mut self, broker: &IPEndpointConfig) -> anyhow::Result<()> {
+        for widget in &mut self.widgets {
+            let client = PowerSupplyClient::builder()
+                .with_ip(broker.clone())
+                .with_power_supply_name(widget.instance_name.clone())
+                .build()
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to connect to power supply instance '{}': {}",
+                        widget.instance_name,
+                        e
+                    )
+                })?;
+            widget.set_client(client).await;
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Update state for all widgets from their client channels
+    async fn update_state(&mut self) {
+        for widget in &mut self.widgets {
+            widget.update_state().await;
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
     /// Handle keyboard input events
     ///
     /// Processes keyboard input and updates application state accordingly.
-    /// Supports navigation between widgets and power toggle commands.
+    /// Supports navigation between widgets, power toggle and setpoint edition.
+    /// While the setpoint editor of the selected widget is open, keys are
+    /// forwarded to the editor, except the emergency stop key.
     ///
     /// # Arguments
     ///
@@ -73,17 +244,93 @@ impl TuiService {
     ///
     /// # Supported Keys
     ///
-    /// * `q` or `Esc` - Quit the application
-    /// * `Space` or `Enter` - Toggle power state (default keys)
-    /// * Configured power toggle key - Toggle power state (from TUI config)
-    /// * `Up Arrow` - Navigate to previous widget
-    /// * `Down Arrow` - Navigate to next widget
-    pub fn handle_input(&mut self, key: KeyCode) {
+    /// * `Esc` - Quit the application
+    /// * `Up Arrow` or `Left Arrow` - Navigate to previous widget
+    /// * `Down Arrow` or `Right Arrow` - Navigate to next widget
+    /// * Configured power toggle key - Toggle power state of the selected widget
+    /// * `v` / `c` - Edit the voltage / current setpoint of the selected widget
+    /// * `w` - Select the next history window
+    /// * `f` - Freeze or resume the history view
+    /// * `l` - Show or hide the log pane
+    /// * `m` - Select the next minimum level of the log pane
+    /// * `r` - Show the events of all runners or of the selected one in the log pane
+    /// * `Page Up` / `Page Down` - Scroll the log pane
+    /// * Configured emergency stop key - Disable and lock all runners
+    ///
+    /// Only key presses are handled, some terminals (Windows) also report the releases.
+    pub fn handle_input(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        let key = key.code;
+
+        // The emergency stop must always be reachable
+        if let KeyCode::Char(c) = key {
+            if Some(c) == self.emergency_stop_key {
+                self.emergency_stop_requested = true;
+                return;
+            }
+        }
+
+        // The editor of the selected widget captures the keys
+        if let Some(widget) = self.widgets.get_mut(self.selected_widget) {
+            if widget.is_editing() {
+                widget.handle_editor_input(key);
+                return;
+            }
+        }
+
         match key {
             KeyCode::Esc => {
                 println!("Quitting TUI...");
                 self.should_quit = true;
             }
+            KeyCode::Up | KeyCode::Left => {
+                if !self.widgets.is_empty() {
+                    self.selected_widget =
+                        (self.selected_widget + self.widgets.len() - 1) % self.widgets.len();
+                }
+            }
+            KeyCode::Down | KeyCode::Right => {
+                if !self.widgets.is_empty() {
+                    self.selected_widget = (self.selected_widget + 1) % self.widgets.len();
+                }
+            }
+            KeyCode::Char(c) if Some(c) == self.power_toggle_key => {
+                if let Some(widget) = self.widgets.get_mut(self.selected_widget) {
+                    widget.toggle_power();
+                }
+            }
+            KeyCode::Char('v') => {
+                if let Some(widget) = self.widgets.get_mut(self.selected_widget) {
+                    widget.open_editor(SetpointKind::Voltage);
+                }
+            }
+            KeyCode::Char('c') => {
+                if let Some(widget) = self.widgets.get_mut(self.selected_widget) {
+                    widget.open_editor(SetpointKind::Current);
+                }
+            }
+            KeyCode::Char('w') => {
+                self.history_window = (self.history_window + 1) % HISTORY_WINDOWS.len();
+            }
+            KeyCode::Char('f') => {
+                self.frozen_at = match self.frozen_at {
+                    Some(_) => None,
+                    None => Some(Instant::now()),
+                };
+            }
+            KeyCode::Char('l') => self.log_pane.toggle(),
+            KeyCode::Char('m') if self.log_pane.is_visible() => self.log_pane.next_level(),
+            KeyCode::Char('r') if self.log_pane.is_visible() => {
+                self.log_pane.toggle_runner_filter()
+            }
+            KeyCode::PageUp if self.log_pane.is_visible() => {
+                self.log_pane.scroll_up(LOG_SCROLL_LINES)
+            }
+            KeyCode::PageDown if self.log_pane.is_visible() => {
+                self.log_pane.scroll_down(LOG_SCROLL_LINES)
+            }
             _ => {}
         }
     }
@@ -95,9 +342,134 @@ impl TuiService {
         self.should_quit
     }
 
-    async fn render_loop() -> anyhow::Result<()> {
-        let mut app = TuiService { should_quit: false };
+    // ------------------------------------------------------------------------------
+
+    /// Split the main area in a grid with one cell per widget
+    fn widget_areas(area: Rect, count: usize) -> Vec<Rect> {
+        let columns = count.clamp(1, MAX_WIDGETS_PER_ROW);
+        let rows = count.div_ceil(columns).max(1);
+
+        let row_areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Ratio(1, rows as u32); rows])
+            .split(area);
+
+        row_areas
+            .iter()
+            .flat_map(|row_area| {
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+                    .split(*row_area)
+                    .to_vec()
+            })
+            .take(count)
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Render the dashboard: all the instance widgets, or an error block if there is none
+    fn render_dashboard(&self, f: &mut Frame, area: Rect) {
+        if self.widgets.is_empty() {
+            let no_instances_block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(Color::Yellow))
+                .title("No Instances Available");
+
+            let message = vec![
+                Line::from(""),
+                Line::from(Span::styled(
+                    "No power supply instances are configured.",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )),
+                Line::from(""),
+                Line::from("Please configure at least one runner in the server"),
+                Line::from("configuration file to use the TUI."),
+            ];
+
+            let message_paragraph = Paragraph::new(message)
+                .block(no_instances_block)
+                .alignment(Alignment::Center);
+            f.render_widget(message_paragraph, area);
+            return;
+        }
+
+        // The same history view is shared by all the widgets so that they can be compared
+        let view = HistoryView {
+            end: self.frozen_at.unwrap_or_else(Instant::now),
+            window: HISTORY_WINDOWS[self.history_window],
+            frozen: self.frozen_at.is_some(),
+        };
+
+        let areas = Self::widget_areas(area, self.widgets.len());
+        for (i, (widget, widget_area)) in self.widgets.iter().zip(areas).enumerate() {
+            widget.render(f, widget_area, i == self.selected_widget, &view);
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Render the help bar with the available keys
+    fn render_help(&self, f: &mut Frame, area: Rect) {
+        let editing = self
+            .widgets
+            .get(self.selected_widget)
+            .is_some_and(|widget| widget.is_editing());
+
+        let mut help_text = if editing {
+            "Enter: Apply | Esc: Cancel | ↑/↓: ± resolution | 0-9 .: Edit".to_string()
+        } else {
+            "Esc: Quit".to_string()
+        };
+        if !editing && !self.widgets.is_empty() {
+            help_text.push_str(" | ←/→ ↑/↓: Select");
+            if let Some(key) = self.power_toggle_key {
+                help_text.push_str(&format!(" | {}: Toggle power", key));
+            }
+            help_text.push_str(" | v/c: Edit voltage/current");
+            help_text.push_str(&format!(
+                " | w: Window ({})",
+                window_label(HISTORY_WINDOWS[self.history_window])
+            ));
+            help_text.push_str(if self.frozen_at.is_some() {
+                " | f: Resume"
+            } else {
+                " | f: Freeze"
+            });
+        }
+        if !editing {
+            help_text.push_str(if self.log_pane.is_visible() {
+                " | l: Hide logs | m: Level | r: Runner filter | PgUp/PgDn: Scroll"
+            } else {
+                " | l: Logs"
+            });
+        }
+        if let Some(key) = self.emergency_stop_key {
+            help_text.push_str(&format!(" | {}: Emergency stop", key));
+        }
+
+        let help_block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Help");
+        let help_paragraph = Paragraph::new(help_text).block(help_block);
+        f.render_widget(help_paragraph, area);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Connect the widgets to the broker, then draw the TUI and handle the user input until quit
+    async fn render_loop(self, broker: IPEndpointConfig) -> anyhow::Result<()> {
+        let mut app = self;
+        app.initialize_clients(&broker).await?;
+
         let mut loading_widget = LoadingWidget::new("Please wait, TUI is initializing...");
+        let loading_start = Instant::now();
+        let mut loading = !app.widgets.is_empty();
 
         // Setup terminal
         let mut stdout = io::stdout();
@@ -107,6 +479,16 @@ impl TuiService {
         let mut terminal = Terminal::new(backend)?;
 
         loop {
+            app.update_state().await;
+
+            // Leave the loading screen once every runner has reported its status
+            if loading
+                && (app.widgets.iter().all(|widget| widget.has_data())
+                    || loading_start.elapsed() > LOADING_TIMEOUT)
+            {
+                loading = false;
+            }
+
             terminal.draw(|f| {
                 let chunks = Layout::default()
                     .direction(Direction::Vertical)
@@ -117,21 +499,50 @@ impl TuiService {
                     ])
                     .split(f.area());
 
-                // Render loading widget with basic content
-                let widget_message = loading_widget.get_message().to_string();
-                let widget_copy = LoadingWidget::new(widget_message);
-                f.render_widget(widget_copy, chunks[0]);
+                if loading {
+                    // Render loading widget with basic content
+                    let widget_message = loading_widget.get_message().to_string();
+                    let widget_copy = LoadingWidget::new(widget_message);
+                    f.render_widget(widget_copy, chunks[0]);
+
+                    // Apply post-render effects directly to the buffer
+                    loading_widget.apply_effects(f.buffer_mut(), chunks[0]);
+                } else if app.log_pane.is_visible() {
+                    let main_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                        .split(chunks[0]);
+                    app.render_dashboard(f, main_chunks[0]);
+                    let selected_runner = app
+                        .widgets
+                        .get(app.selected_widget)
+                        .map(|widget| widget.instance_name.as_str());
+                    app.log_pane.render(f, main_chunks[1], selected_runner);
+                } else {
+                    app.render_dashboard(f, chunks[0]);
+                }
 
-                // Apply post-render effects directly to the buffer
-                loading_widget.apply_effects(f.buffer_mut(), chunks[0]);
+                app.render_help(f, chunks[1]);
             })?;
 
             // Handle events
             if event::poll(Duration::from_millis(50))? {
                 if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        _ => {
-                            app.handle_input(key.code);
+                    app.handle_input(key);
+                }
+            }
+
+            // Forward the emergency stop request to the broker
+            // The emergency stop topic is shared by all runners, any client can publish it
+            // A failure is reported in the status line, the TUI keeps running
+            if app.emergency_stop_requested {
+                app.emergency_stop_requested = false;
+                if let Some(widget) = app.widgets.first_mut() {
+                    if let Some(client) = widget.client.as_ref() {
+                        if let Err(e) = client.emergency_stop().await {
+                            error!("Failed to trigger the emergency stop: {}", e);
+                            widget
+                                .set_status_message(format!("Emergency stop failed: {}", e), true);
                         }
                     }
                 }
@@ -156,349 +567,58 @@ impl TuiService {
         Ok(())
     }
 }
-//     /// Create a new application instance with power supply instances
-//     pub fn new(instance_names: Vec<String>, tui_config: TuiConfig) -> Self {
-//         let widgets = instance_names
-//             .into_iter()
-//             .map(PowerSupplyInstanceWidget::new)
-//             .collect();
-
-//         Self {
-//             should_quit: false,
-//             widgets,
-//             selected_widget: 0,
-//             tui_config,
-//         }
-//     }
-
-//     // ------------------------------------------------------------------------------
-
-//     /// Initialize clients for all widgets
-//     ///
-//     /// Creates and assigns MQTT clients to each power supply instance widget.
-//     /// This establishes the communication channel between the TUI and the power supplies.
-//     ///
-//     /// # Returns
-//     ///
-//     /// * `Ok(())` - If all clients are successfully initialized
-//     /// * `Err(Box<dyn std::error::Error>)` - If any client fails to connect
-//     ///
-//     /// # Errors
-//     ///
-//     /// Returns an error if the PowerSupplyClient cannot be built for any instance,
-//     /// typically due to MQTT connection issues.
-//     pub async fn initialize_clients(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-//         for widget in &mut self.widgets {
-//             match PowerSupplyClient::builder()
-//                 .with_power_supply_name(widget.instance_name.clone())
-//                 .build()
-//             {
-//                 Ok(client) => {
-//                     widget.set_client(client);
-//                 }
-//                 Err(e) => {
-//                     return Err(format!(
-//                         "Failed to connect to power supply instance '{}': {}",
-//                         widget.instance_name, e
-//                     )
-//                     .into());
-//                 }
-//             }
-//         }
-//         Ok(())
-//     }
-
-//     // ------------------------------------------------------------------------------
-
-//     /// Update state for all widgets
-//     ///
-//     /// Refreshes the current state (power, voltage, current) for all power supply
-//     /// instance widgets by querying their respective MQTT clients.
-//     /// This method is called periodically to keep the display up to date.
-//     pub async fn update_state(&mut self) {
-//         for widget in &mut self.widgets {
-//             widget.update_state().await;
-//         }
-//     }
-
-//     // ------------------------------------------------------------------------------
-
-//     /// Toggle power state of selected widget
-//     ///
-//     /// Toggles the power output state of the currently selected power supply instance.
-//     /// Updates the status message to reflect the action taken.
-//     ///
-//     /// # Returns
-//     ///
-//     /// * `Ok(())` - If the power toggle command was sent successfully
-//     /// * `Err(Box<dyn std::error::Error>)` - If the MQTT command fails
-//     ///
-//     /// # Errors
-//     ///
-//     /// Returns an error if the underlying power supply client cannot execute
-//     /// the enable/disable output command.
-//     pub async fn toggle_power(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-//         if let Some(widget) = self.widgets.get_mut(self.selected_widget) {
-//             widget.toggle_power().await?;
-//         }
-//         Ok(())
-//     }
-// }
-
-// /// Run the TUI application
-// ///
-// /// This function initializes and runs the terminal user interface for power supply control.
-// /// It waits for the server state to be ready, creates widgets for all available power supply
-// /// instances, and handles user input for controlling the power supplies.
-// ///
-// /// # Arguments
-// ///
-// /// * `_instance_name` - Optional instance name (currently unused, reserved for future use)
-// ///
-// /// # Returns
-// ///
-// /// * `Ok(())` - When the TUI exits normally
-// /// * `Err(Box<dyn std::error::Error>)` - If there's an error initializing or running the TUI
-// ///
-// /// # Errors
-// ///
-// /// This function can return errors for:
-// /// - Terminal setup failures
-// /// - Server state initialization issues
-// /// - MQTT client connection problems
-// pub async fn run_tui(_instance_name: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-//     // Get server state reference
-//     let server_state = SERVER_STATE_STORAGE
-//         .get()
-//         .ok_or("Server state not initialized")?;
-
-//     // Wait for state ready signal while showing loading screen
-//     let mut ready_receiver = server_state.ready_receiver();
-//     loop {
-//         // Draw loading screen
-//         terminal.draw(|f| {
-//             let chunks = Layout::default()
-//                 .direction(Direction::Vertical)
-//                 .margin(1)
-//                 .constraints([
-//                     Constraint::Min(8),    // Main content
-//                     Constraint::Length(3), // Help bar
-//                 ])
-//                 .split(f.area());
-
-//             // Loading message
-//             let loading_block = Block::default()
-//                 .borders(Borders::ALL)
-//                 .border_type(BorderType::Rounded)
-//                 .style(Style::default().fg(Color::Cyan))
-//                 .title("Loading");
-
-//             let message = vec![
-//                 Line::from(""),
-//                 Line::from(Span::styled(
-//                     "Please wait while the server initializes...",
-//                     Style::default()
-//                         .fg(Color::Cyan)
-//                         .add_modifier(Modifier::BOLD),
-//                 )),
-//                 Line::from(""),
-//                 Line::from("Starting services and connecting to devices..."),
-//             ];
-
-//             let loading_paragraph = Paragraph::new(message)
-//                 .block(loading_block)
-//                 .alignment(Alignment::Center);
-//             f.render_widget(loading_paragraph, chunks[0]);
-
-//             // Help bar
-//             let help_block = Block::default()
-//                 .borders(Borders::ALL)
-//                 .style(Style::default().fg(Color::White))
-//                 .title("Help");
-//             let help_paragraph = Paragraph::new("q/Esc: Quit").block(help_block);
-//             f.render_widget(help_paragraph, chunks[1]);
-//         })?;
-
-//         // Check for quit input during loading
-//         if event::poll(Duration::from_millis(50))? {
-//             if let Event::Key(key) = event::read()? {
-//                 match key.code {
-//                     KeyCode::Char('q') | KeyCode::Esc => {
-//                         // Restore terminal
-//                         disable_raw_mode()?;
-//                         execute!(
-//                             terminal.backend_mut(),
-//                             LeaveAlternateScreen,
-//                             DisableMouseCapture
-//                         )?;
-//                         terminal.show_cursor()?;
-//                         return Ok(());
-//                     }
-//                     _ => {}
-//                 }
-//             }
-//         }
-
-//         // Check if ready signal has been received
-//         if *ready_receiver.borrow() {
-//             break;
-//         }
-
-//         // Wait for signal change
-//         tokio::select! {
-//             _ = ready_receiver.changed() => {
-//                 if *ready_receiver.borrow() {
-//                     break;
-//                 }
-//             }
-//             _ = tokio::time::sleep(Duration::from_millis(100)) => {
-//                 // Continue showing loading screen
-//             }
-//         }
-//     }
-
-//     // Now get available instances after state is ready
-//     let available_instances = server_state.instances_names().await;
-
-//     // Get TUI configuration from server state
-//     let tui_config = {
-//         let config_guard = server_state.server_config.lock().await;
-//         config_guard.tui.clone()
-//     };
-
-//     // Create app state with all available instances and TUI config
-//     let mut app = App::new(available_instances.clone(), tui_config);
-
-//     // Initialize clients for all instances (only if instances exist)
-//     if !available_instances.is_empty() {
-//         app.initialize_clients().await?;
-//     }
-
-//     let mut last_update = std::time::Instant::now();
-//     let mut toggle_requested = false;
-
-//     // Main event loop
-//     loop {
-//         // Update state every 500ms
-//         if last_update.elapsed() > Duration::from_millis(500) {
-//             app.update_state().await;
-//             last_update = std::time::Instant::now();
-//         }
-
-//         // Handle toggle request
-//         if toggle_requested {
-//             let _ = app.toggle_power().await;
-//             toggle_requested = false;
-//         }
-
-//         terminal.draw(|f| {
-//             let chunks = Layout::default()
-//                 .direction(Direction::Vertical)
-//                 .margin(1)
-//                 .constraints([
-//                     Constraint::Min(8),    // Main content
-//                     Constraint::Length(3), // Help bar
-//                 ])
-//                 .split(f.area());
-
-//             // Main content area
-//             if app.widgets.is_empty() {
-//                 // Display "no instances available" message
-//                 let no_instances_block = Block::default()
-//                     .borders(Borders::ALL)
-//                     .border_type(BorderType::Rounded)
-//                     .style(Style::default().fg(Color::Yellow))
-//                     .title("No Instances Available");
-
-//                 let message = vec![
-//                     Line::from(""),
-//                     Line::from(Span::styled(
-//                         "No power supply instances are configured.",
-//                         Style::default()
-//                             .fg(Color::Yellow)
-//                             .add_modifier(Modifier::BOLD),
-//                     )),
-//                     Line::from(""),
-//                     Line::from("Please configure at least one device in the server"),
-//                     Line::from("configuration file to use the TUI."),
-//                 ];
-
-//                 let message_paragraph = Paragraph::new(message)
-//                     .block(no_instances_block)
-//                     .alignment(Alignment::Center);
-//                 f.render_widget(message_paragraph, chunks[0]);
-//             } else {
-//                 // Display all power supply widgets
-//                 let widget_count = app.widgets.len();
-//                 let constraints: Vec<Constraint> = (0..widget_count)
-//                     .map(|_| Constraint::Percentage(100 / widget_count as u16))
-//                     .collect();
-
-//                 let widget_chunks = Layout::default()
-//                     .direction(Direction::Horizontal)
-//                     .constraints(constraints)
-//                     .split(chunks[0]);
-
-//                 // Render each widget
-//                 for (i, widget) in app.widgets.iter().enumerate() {
-//                     if let Some(area) = widget_chunks.get(i) {
-//                         // Highlight the selected widget
-//                         let mut area_to_use = *area;
-//                         if i == app.selected_widget {
-//                             // Add highlighting for selected widget
-//                             let highlight_block = Block::default()
-//                                 .borders(Borders::ALL)
-//                                 .border_type(BorderType::Thick)
-//                                 .style(Style::default().fg(Color::Magenta));
-//                             area_to_use = highlight_block.inner(area_to_use);
-//                             f.render_widget(highlight_block, *area);
-//                         }
-//                         widget.render(f, area_to_use);
-//                     }
-//                 }
-//             }
-
-//             // Help bar
-//             let help_block = Block::default()
-//                 .borders(Borders::ALL)
-//                 .style(Style::default().fg(Color::White))
-//                 .title("Help");
-//             let help_text = if app.widgets.is_empty() {
-//                 "q/Esc: Quit"
-//             } else {
-//                 "q/Esc: Quit | ↑/↓: Navigate | Space/Enter: Toggle Power"
-//             };
-//             let help_paragraph = Paragraph::new(help_text).block(help_block);
-//             f.render_widget(help_paragraph, chunks[1]);
-//         })?;
-
-//         // Handle events
-//         if event::poll(Duration::from_millis(50))? {
-//             if let Event::Key(key) = event::read()? {
-//                 match key.code {
-//                     KeyCode::Char(' ') | KeyCode::Enter => {
-//                         toggle_requested = true;
-//                     }
-//                     _ => {
-//                         app.handle_input(key.code);
-//                     }
-//                 }
-//             }
-//         }
-
-//         if app.should_quit() {
-//             break;
-//         }
-//     }
-
-//     // Restore terminal
-//     disable_raw_mode()?;
-//     execute!(
-//         terminal.backend_mut(),
-//         LeaveAlternateScreen,
-//         DisableMouseCapture
-//     )?;
-//     terminal.show_cursor()?;
-
-//     Ok(())
-// }
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    // ------------------------------------------------------------------------------
+
+    fn app() -> TuiService {
+        let config = TuiConfig {
+            enable: None,
+            power_toggle_key: None,
+            emergency_stop_key: Some("e".to_string()),
+        };
+        TuiService::new(
+            &config,
+            vec![("a".to_string(), None), ("b".to_string(), None)],
+        )
+    }
+
+    // ------------------------------------------------------------------------------
+
+    fn key(code: KeyCode, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::new_with_kind(code, KeyModifiers::NONE, kind)
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn handles_key_presses() {
+        let mut app = app();
+        app.handle_input(key(KeyCode::Down, KeyEventKind::Press));
+        assert_eq!(app.selected_widget, 1);
+
+        app.handle_input(key(KeyCode::Char('e'), KeyEventKind::Press));
+        assert!(app.emergency_stop_requested);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn ignores_key_releases_and_repeats() {
+        let mut app = app();
+        for kind in [KeyEventKind::Release, KeyEventKind::Repeat] {
+            app.handle_input(key(KeyCode::Down, kind));
+            app.handle_input(key(KeyCode::Char('e'), kind));
+            app.handle_input(key(KeyCode::Esc, kind));
+        }
+        assert_eq!(app.selected_widget, 0);
+        assert!(!app.emergency_stop_requested);
+        assert!(!app.should_quit);
+    }
+}
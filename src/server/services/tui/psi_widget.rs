@@ -1,4 +1,20 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossterm::event::KeyCode;
+use pza_power_supply_client::payload::CurrentPayload;
+use pza_power_supply_client::payload::EstopPayload;
+use pza_power_supply_client::payload::PowerState;
+use pza_power_supply_client::payload::PowerStatePayload;
+use pza_power_supply_client::payload::Status;
+use pza_power_supply_client::payload::StatusPayload;
+use pza_power_supply_client::payload::VoltagePayload;
 use pza_power_supply_client::PowerSupplyClient;
+use ratatui::layout::Constraint;
+use ratatui::layout::Direction;
+use ratatui::layout::Layout;
 use ratatui::layout::Rect;
 use ratatui::style::Color;
 use ratatui::style::Modifier;
@@ -9,7 +25,75 @@ use ratatui::widgets::Block;
 use ratatui::widgets::BorderType;
 use ratatui::widgets::Borders;
 use ratatui::widgets::Paragraph;
+use ratatui::widgets::Sparkline;
 use ratatui::Frame;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::task::JoinHandle;
+
+use super::history::window_label;
+use super::history::HistoryView;
+use super::history::MeasurementHistory;
+use super::setpoint_editor::SetpointEditor;
+use super::setpoint_editor::SetpointKind;
+use super::setpoint_editor::SetpointLimits;
+
+/// Width of the field names, so that all the values start at the same column
+const FIELD_NAME_WIDTH: usize = 8;
+
+/// Number of lines of the fields part of the widget
+const FIELDS_HEIGHT: u16 = 8;
+
+/// Minimum height of a sparkline, below it the sparklines are not displayed
+const MIN_SPARKLINE_HEIGHT: u16 = 2;
+
+/// Maximum time to wait for the runner to acknowledge a command
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Receivers of the client broadcast channels followed by the widget
+struct WidgetChannels {
+    state: broadcast::Receiver<Arc<PowerStatePayload>>,
+    voltage: broadcast::Receiver<Arc<VoltagePayload>>,
+    current: broadcast::Receiver<Arc<CurrentPayload>>,
+    estop: broadcast::Receiver<Arc<EstopPayload>>,
+    measured_voltage: broadcast::Receiver<Arc<VoltagePayload>>,
+    measured_current: broadcast::Receiver<Arc<CurrentPayload>>,
+    status: broadcast::Receiver<Arc<StatusPayload>>,
+}
+
+impl WidgetChannels {
+    /// Subscribe to all the channels of the client
+    fn subscribe(client: &PowerSupplyClient) -> Self {
+        Self {
+            state: client.subscribe_state_changes(),
+            voltage: client.subscribe_voltage_changes(),
+            current: client.subscribe_current_changes(),
+            estop: client.subscribe_estop_changes(),
+            measured_voltage: client.subscribe_measured_voltage_changes(),
+            measured_current: client.subscribe_measured_current_changes(),
+            status: client.subscribe_status_changes(),
+        }
+    }
+}
+
+/// Take all the pending messages of a receiver without waiting
+///
+/// `lagged` is set when messages have been dropped by the channel.
+fn all_pending<T: Clone>(receiver: &mut broadcast::Receiver<T>, lagged: &mut bool) -> Vec<T> {
+    let mut values = Vec::new();
+    loop {
+        match receiver.try_recv() {
+            Ok(value) => values.push(value),
+            Err(TryRecvError::Lagged(_)) => *lagged = true,
+            Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return values,
+        }
+    }
+}
+
+/// Take all the pending messages of a receiver without waiting and return the last one
+fn last_pending<T: Clone>(receiver: &mut broadcast::Receiver<T>, lagged: &mut bool) -> Option<T> {
+    all_pending(receiver, lagged).pop()
+}
 
 /// Power Supply Instance Widget
 ///
@@ -19,14 +103,40 @@ pub struct PowerSupplyInstanceWidget {
     pub instance_name: String,
     /// Power supply client for MQTT communication
     pub client: Option<PowerSupplyClient>,
+    /// Receivers of the client channels, feeding the displayed values
+    channels: Option<WidgetChannels>,
     /// Current power state (output enable)
     pub power_state: bool,
-    /// Current voltage value
+    /// Current voltage setpoint
     pub voltage: String,
-    /// Current current value
+    /// Current current limit setpoint
     pub current: String,
+    /// Last measured output voltage
+    pub measured_voltage: String,
+    /// Last measured output current
+    pub measured_current: String,
+    /// History of the measured output voltage
+    voltage_history: MeasurementHistory,
+    /// History of the measured output current
+    current_history: MeasurementHistory,
+    /// Whether the emergency stop of the runner is latched
+    pub estop_latched: bool,
+    /// Last status reported by the runner, None until received
+    pub status: Option<Status>,
+    /// Panic message reported with the last status
+    pub panic_message: Option<String>,
     /// Status message for this instance
     pub status_message: String,
+    /// Whether the status message reports an error
+    status_is_error: bool,
+    /// Security limits of the voltage setpoint
+    voltage_limits: SetpointLimits,
+    /// Security limits of the current setpoint
+    current_limits: SetpointLimits,
+    /// Inline editor of a setpoint, when open
+    editor: Option<SetpointEditor>,
+    /// Command sent to the runner and waiting for its acknowledgement
+    pending_command: Option<JoinHandle<anyhow::Result<String>>>,
 }
 
 impl PowerSupplyInstanceWidget {
@@ -35,43 +145,179 @@ impl PowerSupplyInstanceWidget {
         Self {
             instance_name,
             client: None,
+            channels: None,
             power_state: false,
-            voltage: "0.00V".to_string(),
-            current: "0.00A".to_string(),
+            voltage: "0.00".to_string(),
+            current: "0.00".to_string(),
+            measured_voltage: "0.00".to_string(),
+            measured_current: "0.00".to_string(),
+            voltage_history: MeasurementHistory::default(),
+            current_history: MeasurementHistory::default(),
+            estop_latched: false,
+            status: None,
+            panic_message: None,
             status_message: "Initializing...".to_string(),
+            status_is_error: false,
+            voltage_limits: SetpointLimits::default(),
+            current_limits: SetpointLimits::default(),
+            editor: None,
+            pending_command: None,
         }
     }
 
     // ------------------------------------------------------------------------------
 
+    /// Set the security limits checked before sending a setpoint
+    pub fn set_limits(&mut self, voltage_limits: SetpointLimits, current_limits: SetpointLimits) {
+        self.voltage_limits = voltage_limits;
+        self.current_limits = current_limits;
+    }
+
+    // ------------------------------------------------------------------------------
+
     /// Set the power supply client
     ///
     /// Assigns an MQTT client to this widget, establishing communication
-    /// with the corresponding power supply instance. Updates the status
-    /// message to indicate successful connection.
+    /// with the corresponding power supply instance. The widget subscribes
+    /// to the client channels immediately so that no update is missed, then
+    /// loads the values already cached by the client.
     ///
     /// # Arguments
     ///
     /// * `client` - The PowerSupplyClient for MQTT communication
-    pub fn set_client(&mut self, client: PowerSupplyClient) {
+    pub async fn set_client(&mut self, client: PowerSupplyClient) {
+        self.channels = Some(WidgetChannels::subscribe(&client));
         self.client = Some(client);
-        self.status_message = "Connected".to_string();
+        self.load_from_cache().await;
+        self.set_status_message("Connected", false);
     }
 
     // ------------------------------------------------------------------------------
 
-    /// Update power supply state from client
-    ///
-    /// Retrieves the current state information (power output, voltage, current)
-    /// from the power supply via MQTT and updates the widget's display values.
-    /// This method is called periodically to keep the UI synchronized with
-    /// the actual power supply state.
-    pub async fn update_state(&mut self) {
+    /// Set the status message of the widget
+    pub fn set_status_message(&mut self, message: impl Into<String>, is_error: bool) {
+        self.status_message = message.into();
+        self.status_is_error = is_error;
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Check whether the runner has already reported its status
+    pub fn has_data(&self) -> bool {
+        self.status.is_some()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Load all the displayed values from the client cache
+    async fn load_from_cache(&mut self) {
         if let Some(ref client) = self.client {
             self.power_state = client.get_oe().await;
             self.voltage = client.get_voltage().await;
             self.current = client.get_current().await;
+            self.measured_voltage = client.get_measured_voltage().await;
+            self.measured_current = client.get_measured_current().await;
+            self.estop_latched = client.get_estop_latched().await;
+            self.status = client.get_status().await;
+            self.panic_message = client.get_panic_message().await;
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Update power supply state from the client channels
+    ///
+    /// Applies the updates received on the client broadcast channels since the
+    /// last call, without waiting. If the widget fell behind and updates were
+    /// dropped, all the values are reloaded from the client cache instead.
+    /// This method is called at each frame to keep the UI synchronized with
+    /// the actual power supply state, it also reports the result of the last
+    /// command once acknowledged.
+    pub async fn update_state(&mut self) {
+        self.poll_pending_command().await;
+
+        let Some(channels) = self.channels.as_mut() else {
+            return;
+        };
+
+        let mut lagged = false;
+        if let Some(payload) = last_pending(&mut channels.state, &mut lagged) {
+            self.power_state = payload.state == PowerState::On;
+        }
+        if let Some(payload) = last_pending(&mut channels.voltage, &mut lagged) {
+            self.voltage = payload.voltage.clone();
+        }
+        if let Some(payload) = last_pending(&mut channels.current, &mut lagged) {
+            self.current = payload.current.clone();
+        }
+        if let Some(payload) = last_pending(&mut channels.estop, &mut lagged) {
+            self.estop_latched = payload.latched;
+        }
+
+        // Every measurement feeds the history
+        let now = Instant::now();
+        for payload in all_pending(&mut channels.measured_voltage, &mut lagged) {
+            self.voltage_history.push(&payload.voltage, now);
+            self.measured_voltage = payload.voltage.clone();
+        }
+        for payload in all_pending(&mut channels.measured_current, &mut lagged) {
+            self.current_history.push(&payload.current, now);
+            self.measured_current = payload.current.clone();
+        }
+        if let Some(payload) = last_pending(&mut channels.status, &mut lagged) {
+            self.status = Some(payload.status.clone());
+            self.panic_message = payload.panic_message.clone();
+        }
+
+        if lagged {
+            self.load_from_cache().await;
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Report the result of the pending command once it is finished
+    async fn poll_pending_command(&mut self) {
+        let finished = self
+            .pending_command
+            .as_ref()
+            .is_some_and(|handle| handle.is_finished());
+        if !finished {
+            return;
+        }
+
+        if let Some(handle) = self.pending_command.take() {
+            match handle.await {
+                Ok(Ok(message)) => self.set_status_message(message, false),
+                Ok(Err(e)) => self.set_status_message(format!("Error: {}", e), true),
+                Err(e) => self.set_status_message(format!("Command failed: {}", e), true),
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Send a command to the runner without blocking the UI
+    ///
+    /// The command runs in its own task, its acknowledgement or the error
+    /// reported by the runner is shown in the status message when finished.
+    /// Only one command can be pending at a time.
+    fn send_command<F, C>(&mut self, description: &str, command: C)
+    where
+        C: FnOnce(PowerSupplyClient) -> F,
+        F: Future<Output = anyhow::Result<String>> + Send + 'static,
+    {
+        let Some(client) = self.client.clone() else {
+            self.set_status_message("Not connected", true);
+            return;
+        };
+        if self.pending_command.is_some() {
+            self.set_status_message("Busy, waiting for the previous command", true);
+            return;
         }
+
+        self.set_status_message(format!("{}...", description), false);
+        self.pending_command = Some(tokio::spawn(command(client)));
     }
 
     // ------------------------------------------------------------------------------
@@ -80,28 +326,193 @@ impl PowerSupplyInstanceWidget {
     ///
     /// Toggles the power output state of this power supply instance.
     /// If currently enabled, it will be disabled, and vice versa.
-    /// Updates the status message to reflect the action taken.
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` - If the power toggle command was executed successfully
-    /// * `Err(Box<dyn std::error::Error>)` - If the MQTT command fails
+    /// The status message reports the acknowledgement of the runner, or the
+    /// reason of the rejection (e.g. emergency stop latched, interlock).
+    pub fn toggle_power(&mut self) {
+        if self.power_state {
+            self.send_command("Disabling output", |client| async move {
+                client.disable_output_wait_ack(COMMAND_TIMEOUT).await?;
+                Ok("Power disabled".to_string())
+            });
+        } else {
+            self.send_command("Enabling output", |client| async move {
+                client.enable_output_wait_ack(COMMAND_TIMEOUT).await?;
+                Ok("Power enabled".to_string())
+            });
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Check whether the inline setpoint editor is open
+    pub fn is_editing(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Open the inline editor on the current value of a setpoint
+    pub fn open_editor(&mut self, kind: SetpointKind) {
+        let (value, limits) = match kind {
+            SetpointKind::Voltage => (&self.voltage, self.voltage_limits),
+            SetpointKind::Current => (&self.current, self.current_limits),
+        };
+        self.editor = Some(SetpointEditor::new(kind, value, limits));
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Handle a key while the inline editor is open
     ///
-    /// # Errors
+    /// * Digits and `.` - Edit the value
+    /// * `Backspace` - Remove the last character
+    /// * `Up Arrow` / `Down Arrow` - Adjust the value by one step of the device resolution
+    /// * `Enter` - Check the value against the security limits and send it
+    /// * `Esc` - Close the editor without sending
+    pub fn handle_editor_input(&mut self, key: KeyCode) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+
+        match key {
+            KeyCode::Esc => {
+                self.editor = None;
+            }
+            KeyCode::Enter => self.submit_editor(),
+            KeyCode::Up => editor.adjust(1),
+            KeyCode::Down => editor.adjust(-1),
+            KeyCode::Backspace => editor.backspace(),
+            KeyCode::Char(c) => editor.push_char(c),
+            _ => {}
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Validate the edited value and send it to the runner
     ///
-    /// Returns an error if the client is not set or if the underlying
-    /// enable_output/disable_output command fails.
-    pub async fn toggle_power(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(ref client) = self.client {
-            if self.power_state {
-                client.disable_output().await?;
-                self.status_message = "Power disabled".to_string();
-            } else {
-                client.enable_output().await?;
-                self.status_message = "Power enabled".to_string();
+    /// The editor stays open if the value is invalid or outside the security limits.
+    fn submit_editor(&mut self) {
+        let Some(editor) = self.editor.as_ref() else {
+            return;
+        };
+        let kind = editor.kind();
+
+        let value = match editor.value() {
+            Ok(value) => value,
+            Err(message) => {
+                self.set_status_message(message, true);
+                return;
             }
+        };
+        self.editor = None;
+
+        let description = format!(
+            "Setting {} to {} {}",
+            kind.name().to_lowercase(),
+            value,
+            kind.unit()
+        );
+        match kind {
+            SetpointKind::Voltage => self.send_command(&description, |client| async move {
+                let readback = client.set_voltage_wait_ack(value, COMMAND_TIMEOUT).await?;
+                Ok(format!("Voltage set to {} V", readback))
+            }),
+            SetpointKind::Current => self.send_command(&description, |client| async move {
+                let readback = client.set_current_wait_ack(value, COMMAND_TIMEOUT).await?;
+                Ok(format!("Current set to {} A", readback))
+            }),
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Build a `FIELD_NAME: value` line, values of all lines start at the same column
+    fn field_line<'a>(name: &str, value: impl Into<String>, value_style: Style) -> Line<'a> {
+        Line::from(vec![
+            Span::styled(
+                format!("{:<width$}: ", name, width = FIELD_NAME_WIDTH),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(value.into(), value_style),
+        ])
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Build the line of a setpoint, showing the inline editor when it edits this setpoint
+    fn setpoint_line<'a>(&self, kind: SetpointKind, value: &str, color: Color) -> Line<'a> {
+        match &self.editor {
+            Some(editor) if editor.kind() == kind => Self::field_line(
+                kind.name(),
+                format!(
+                    "[{}_] {} (±{})",
+                    editor.buffer(),
+                    kind.unit(),
+                    editor.step_text()
+                ),
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(color)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            _ => Self::field_line(
+                kind.name(),
+                format!("{} {}", value, kind.unit()),
+                Style::default().fg(color),
+            ),
         }
-        Ok(())
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Render the sparkline of a measurement history, with its statistics above it
+    fn render_history(
+        f: &mut Frame,
+        area: Rect,
+        name: &str,
+        unit: &str,
+        history: &MeasurementHistory,
+        view: &HistoryView,
+        color: Color,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(area);
+
+        let stats = match history.stats(view) {
+            Some(stats) => format!(
+                "min {:.3} {unit}  max {:.3} {unit}  avg {:.3} {unit}",
+                stats.min,
+                stats.max,
+                stats.avg,
+                unit = unit
+            ),
+            None => "no data".to_string(),
+        };
+        let mut title = vec![
+            Span::styled(
+                format!("{} {} ", name, window_label(view.window)),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(stats, Style::default().fg(Color::Gray)),
+        ];
+        if view.frozen {
+            title.push(Span::styled(
+                " FROZEN",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+        f.render_widget(Paragraph::new(Line::from(title)), chunks[0]);
+
+        let data = history.columns(view, chunks[1].width as usize);
+        let sparkline = Sparkline::default()
+            .data(&data)
+            .style(Style::default().fg(color));
+        f.render_widget(sparkline, chunks[1]);
     }
 
     // ------------------------------------------------------------------------------
@@ -109,20 +520,31 @@ impl PowerSupplyInstanceWidget {
     /// Render the widget
     ///
     /// Draws the power supply instance widget in the specified terminal area.
-    /// The widget displays the instance name, power state, voltage, current,
-    /// and status in a rounded border block with aligned field labels.
+    /// The widget displays the instance name, power state, setpoints,
+    /// measurements, runner status and emergency stop latch in a rounded
+    /// border block with aligned field labels. When the area is tall enough,
+    /// sparklines of the measured voltage and current are drawn below.
     ///
     /// # Arguments
     ///
     /// * `f` - The ratatui Frame for rendering
     /// * `area` - The terminal area where the widget should be drawn
-    pub fn render(&self, f: &mut Frame, area: Rect) {
+    /// * `selected` - Whether the widget is the one targeted by the user commands
+    /// * `view` - Part of the measurement history to display
+    pub fn render(&self, f: &mut Frame, area: Rect, selected: bool, view: &HistoryView) {
         // Create main block with instance name and rounded borders
+        let border_style = if selected {
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
         let main_block = Block::default()
             .title(self.instance_name.clone())
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .style(Style::default().fg(Color::White));
+            .border_style(border_style);
 
         // Power state color and text
         let power_color = if self.power_state {
@@ -132,62 +554,105 @@ impl PowerSupplyInstanceWidget {
         };
         let power_text = if self.power_state { "ON" } else { "OFF" };
 
+        // Runner status color and text
+        let (status_text, status_color) = match (&self.status, &self.panic_message) {
+            (None, _) => ("Waiting for server...".to_string(), Color::Gray),
+            (Some(Status::Initializing), _) => ("Initializing".to_string(), Color::Yellow),
+            (Some(Status::Running), _) => ("Running".to_string(), Color::Green),
+            (Some(Status::Panicking), Some(message)) => {
+                (format!("Panicking: {}", message), Color::Red)
+            }
+            (Some(Status::Panicking), None) => ("Panicking".to_string(), Color::Red),
+        };
+
+        // Emergency stop color and text
+        let (estop_text, estop_color) = if self.estop_latched {
+            ("LATCHED", Color::Red)
+        } else {
+            ("released", Color::Green)
+        };
+
         // Create content lines with FIELD_NAME: value format
         // Field names have different color from values and are aligned for consistent spacing
         let content = vec![
-            Line::from(""), // Empty line for spacing
-            Line::from(vec![
-                Span::styled(
-                    "Power  : ",
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(
-                    power_text,
-                    Style::default()
-                        .fg(power_color)
-                        .add_modifier(Modifier::BOLD),
-                ),
-            ]),
-            Line::from(""), // Empty line for spacing
-            Line::from(vec![
-                Span::styled(
-                    "Voltage: ",
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(&self.voltage, Style::default().fg(Color::Yellow)),
-            ]),
-            Line::from(""), // Empty line for spacing
-            Line::from(vec![
-                Span::styled(
-                    "Current: ",
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(&self.current, Style::default().fg(Color::Cyan)),
-            ]),
-            Line::from(""), // Empty line for spacing
-            Line::from(vec![
-                Span::styled(
-                    "Status : ",
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(&self.status_message, Style::default().fg(Color::Gray)),
-            ]),
+            Self::field_line(
+                "Power",
+                power_text,
+                Style::default()
+                    .fg(power_color)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            self.setpoint_line(SetpointKind::Voltage, &self.voltage, Color::Yellow),
+            self.setpoint_line(SetpointKind::Current, &self.current, Color::Cyan),
+            Self::field_line(
+                "Meas. V",
+                format!("{} V", self.measured_voltage),
+                Style::default().fg(Color::Yellow),
+            ),
+            Self::field_line(
+                "Meas. I",
+                format!("{} A", self.measured_current),
+                Style::default().fg(Color::Cyan),
+            ),
+            Self::field_line("Runner", status_text, Style::default().fg(status_color)),
+            Self::field_line("E-stop", estop_text, Style::default().fg(estop_color)),
+            Self::field_line(
+                "Status",
+                self.status_message.clone(),
+                Style::default().fg(if self.status_is_error {
+                    Color::Red
+                } else {
+                    Color::Gray
+                }),
+            ),
         ];
 
-        // Create paragraph with content inside the main block
+        // Only the container has a border, the inner parts are drawn inside it
+        let inner = main_block.inner(area);
+        f.render_widget(main_block, area);
+
+        let show_history = inner.height >= FIELDS_HEIGHT + 2 * (1 + MIN_SPARKLINE_HEIGHT);
+        let chunks = if show_history {
+            Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(FIELDS_HEIGHT),
+                    Constraint::Ratio(1, 2),
+                    Constraint::Ratio(1, 2),
+                ])
+                .split(inner)
+        } else {
+            Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0)])
+                .split(inner)
+        };
+
+        // Create paragraph with content
         let paragraph = Paragraph::new(content)
-            .block(main_block)
             .alignment(ratatui::layout::Alignment::Left)
             .wrap(ratatui::widgets::Wrap { trim: true });
+        f.render_widget(paragraph, chunks[0]);
 
-        f.render_widget(paragraph, area);
+        if show_history {
+            Self::render_history(
+                f,
+                chunks[1],
+                "Meas. V",
+                "V",
+                &self.voltage_history,
+                view,
+                Color::Yellow,
+            );
+            Self::render_history(
+                f,
+                chunks[2],
+                "Meas. I",
+                "A",
+                &self.current_history,
+                view,
+                Color::Cyan,
+            );
+        }
     }
 }
@@ -0,0 +1,272 @@
+//! Inline numeric editor for the voltage and current setpoints
+//!
+//! The editor keeps the typed text, the Up/Down keys adjust the value by one
+//! step of the device resolution.
+
+/// Decimals used for the voltage when the device resolution is unknown
+const DEFAULT_VOLTAGE_DECIMALS: usize = 2;
+
+/// Decimals used for the current when the device resolution is unknown
+const DEFAULT_CURRENT_DECIMALS: usize = 3;
+
+/// Setpoint edited by the editor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetpointKind {
+    /// Output voltage, in Volts
+    Voltage,
+    /// Output current limit, in Amperes
+    Current,
+}
+
+impl SetpointKind {
+    /// Name of the setpoint for the messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Voltage => "Voltage",
+            Self::Current => "Current",
+        }
+    }
+
+    /// Unit of the setpoint
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Voltage => "V",
+            Self::Current => "A",
+        }
+    }
+}
+
+/// Security limits applied to a setpoint, None when not configured
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SetpointLimits {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl SetpointLimits {
+    /// Check the value against the limits
+    pub fn check(&self, kind: SetpointKind, value: f32) -> Result<(), String> {
+        let below = self.min.is_some_and(|min| value < min);
+        let above = self.max.is_some_and(|max| value > max);
+        if below || above {
+            let bound = |limit: Option<f32>| limit.map_or("-".to_string(), |l| l.to_string());
+            return Err(format!(
+                "{} {} {} out of the security limits [{}, {}] {}",
+                kind.name(),
+                value,
+                kind.unit(),
+                bound(self.min),
+                bound(self.max),
+                kind.unit()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bring the value back inside the limits, never below zero
+    fn clamp(&self, value: f32) -> f32 {
+        let value = value.max(self.min.unwrap_or(0.0)).max(0.0);
+        match self.max {
+            Some(max) => value.min(max),
+            None => value,
+        }
+    }
+}
+
+/// Inline editor of a setpoint
+pub struct SetpointEditor {
+    /// Setpoint being edited
+    kind: SetpointKind,
+    /// Text typed by the user
+    buffer: String,
+    /// Number of decimals supported by the device
+    decimals: usize,
+    /// Security limits of the setpoint
+    limits: SetpointLimits,
+}
+
+impl SetpointEditor {
+    // ------------------------------------------------------------------------------
+
+    /// Open the editor on the current setpoint value
+    ///
+    /// The device resolution is deduced from the decimals of the value read
+    /// back from the device, the defaults of the drivers are used otherwise.
+    pub fn new(kind: SetpointKind, current_value: &str, limits: SetpointLimits) -> Self {
+        let decimals = match current_value.split_once('.') {
+            Some((_, fraction)) if !fraction.is_empty() => fraction.len(),
+            _ => match kind {
+                SetpointKind::Voltage => DEFAULT_VOLTAGE_DECIMALS,
+                SetpointKind::Current => DEFAULT_CURRENT_DECIMALS,
+            },
+        };
+
+        Self {
+            kind,
+            buffer: current_value.to_string(),
+            decimals,
+            limits,
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Setpoint being edited
+    pub fn kind(&self) -> SetpointKind {
+        self.kind
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Text typed by the user
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Smallest increment supported by the device
+    fn step(&self) -> f32 {
+        10f32.powi(-(self.decimals as i32))
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Smallest increment supported by the device, formatted for display
+    pub fn step_text(&self) -> String {
+        format!("{:.1$}", self.step(), self.decimals)
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Add a typed character, only digits and a single decimal point are accepted
+    pub fn push_char(&mut self, c: char) {
+        if c.is_ascii_digit() || (c == '.' && !self.buffer.contains('.')) {
+            self.buffer.push(c);
+        }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Remove the last typed character
+    pub fn backspace(&mut self) {
+        self.buffer.pop();
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Increase or decrease the value by one step, inside the security limits
+    pub fn adjust(&mut self, steps: i32) {
+        let value = self.buffer.parse::<f32>().unwrap_or(0.0);
+        let value = self.limits.clamp(value + steps as f32 * self.step());
+        self.buffer = format!("{:.1$}", value, self.decimals);
+    }
+
+    // ------------------------------------------------------------------------------
+
+    /// Validate the typed value and format it at the device resolution
+    pub fn value(&self) -> Result<String, String> {
+        let value = self.buffer.parse::<f32>().map_err(|_| {
+            format!(
+                "Invalid {} '{}'",
+                self.kind.name().to_lowercase(),
+                self.buffer
+            )
+        })?;
+        self.limits.check(self.kind, value)?;
+        Ok(format!("{:.1$}", value, self.decimals))
+    }
+}
+
+// ================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ------------------------------------------------------------------------------
+
+    fn limits(min: Option<f32>, max: Option<f32>) -> SetpointLimits {
+        SetpointLimits { min, max }
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn resolution_follows_the_read_back_value() {
+        let editor = SetpointEditor::new(SetpointKind::Voltage, "5.000", SetpointLimits::default());
+        assert_eq!(editor.step_text(), "0.001");
+
+        let editor = SetpointEditor::new(SetpointKind::Voltage, "5", SetpointLimits::default());
+        assert_eq!(editor.step_text(), "0.01");
+
+        let editor = SetpointEditor::new(SetpointKind::Current, "", SetpointLimits::default());
+        assert_eq!(editor.step_text(), "0.001");
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn typing_accepts_digits_and_a_single_point() {
+        let mut editor = SetpointEditor::new(SetpointKind::Voltage, "", SetpointLimits::default());
+        for c in "1a2.5.0-".chars() {
+            editor.push_char(c);
+        }
+        assert_eq!(editor.buffer(), "12.50");
+
+        editor.backspace();
+        assert_eq!(editor.buffer(), "12.5");
+        assert_eq!(editor.value(), Ok("12.50".to_string()));
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn adjust_steps_inside_the_limits() {
+        let mut editor =
+            SetpointEditor::new(SetpointKind::Voltage, "4.99", limits(Some(1.0), Some(5.0)));
+        editor.adjust(1);
+        assert_eq!(editor.buffer(), "5.00");
+        editor.adjust(1);
+        assert_eq!(editor.buffer(), "5.00");
+
+        editor.adjust(-1000);
+        assert_eq!(editor.buffer(), "1.00");
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn adjust_never_goes_below_zero() {
+        let mut editor =
+            SetpointEditor::new(SetpointKind::Current, "0.001", SetpointLimits::default());
+        editor.adjust(-5);
+        assert_eq!(editor.buffer(), "0.000");
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn value_rejects_invalid_text() {
+        let mut editor = SetpointEditor::new(SetpointKind::Current, "", SetpointLimits::default());
+        editor.push_char('.');
+        assert_eq!(editor.value(), Err("Invalid current '.'".to_string()));
+    }
+
+    // ------------------------------------------------------------------------------
+
+    #[test]
+    fn value_rejects_values_out_of_the_limits() {
+        let editor = SetpointEditor::new(SetpointKind::Voltage, "12.00", limits(None, Some(10.0)));
+        assert_eq!(
+            editor.value(),
+            Err("Voltage 12 V out of the security limits [-, 10] V".to_string())
+        );
+
+        let editor = SetpointEditor::new(SetpointKind::Current, "0.05", limits(Some(0.1), None));
+        assert_eq!(
+            editor.value(),
+            Err("Current 0.05 A out of the security limits [0.1, -] A".to_string())
+        );
+    }
+}